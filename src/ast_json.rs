@@ -0,0 +1,437 @@
+use std::ops::Range;
+
+use serde_json::{json, Value};
+
+use crate::parser::parse_node::*;
+use crate::source_map::SourceFile;
+use crate::string_name::StringName;
+
+/// Converts the parsed statements into JSON, for tools outside of Rust. Every node is an object
+/// with a `type`, its fields and a `range`. Ranges have the byte offsets and the 1-based
+/// line and column (counted in characters) of both ends. A real too large to be finite, such as
+/// `1e999`, has the value `"inf"`, since JSON has no infinity.
+pub fn ast_to_json(statements: &[ParseNode<Statement>], file: &SourceFile) -> Value {
+    let converter = Converter { file };
+    json!({
+        "type": "Program",
+        "range": converter.range(&(0..file.source().len())),
+        "statements": converter.statements(statements),
+    })
+}
+
+// offset with its 1-based line and column
+pub(crate) fn position_json(file: &SourceFile, offset: usize) -> Value {
+    let (line, column) = file.line_column(offset);
+    json!({
+        "offset": offset,
+        "line": line,
+        "column": column,
+    })
+}
+pub(crate) fn range_json(file: &SourceFile, range: &Range<usize>) -> Value {
+    json!({
+        "start": position_json(file, range.start),
+        "end": position_json(file, range.end),
+    })
+}
+
+struct Converter<'a> {
+    file: &'a SourceFile,
+}
+impl<'a> Converter<'a> {
+    fn range(&self, range: &Range<usize>) -> Value {
+        range_json(self.file, range)
+    }
+    // adds the `type` and `range` to the fields
+    fn node(&self, node_type: &str, range: &Range<usize>, fields: Value) -> Value {
+        let mut node = json!({
+            "type": node_type,
+            "range": self.range(range),
+        });
+        if let (Value::Object(node), Value::Object(fields)) = (&mut node, fields) {
+            node.extend(fields);
+        }
+        node
+    }
+    fn name(&self, name: Option<StringName>, range: &Range<usize>) -> Value {
+        json!({
+            "name": name.map(|n| n.as_str()),
+            "range": self.range(range),
+        })
+    }
+
+    fn statements(&self, statements: &[ParseNode<Statement>]) -> Value {
+        statements
+            .iter()
+            .map(|stmt| self.statement(&stmt.data, &stmt.range))
+            .collect()
+    }
+    fn statement(&self, statement: &Statement, range: &Range<usize>) -> Value {
+        match statement {
+            Statement::Declaration(Declaration::Var(decl)) => self.var_decl(decl, range),
+            Statement::Declaration(Declaration::Func(decl)) => self.func_decl(decl, range),
+            Statement::Expression(expr) => self.node(
+                "ExpressionStatement",
+                range,
+                json!({ "expression": self.expression(expr, range) }),
+            ),
+            Statement::If(if_stmt) => self.if_statement(if_stmt, range),
+            Statement::While(while_stmt) => {
+                let block = |block: &ParseNode<Block>| self.block(&block.data, &block.range);
+                self.node(
+                    "While",
+                    range,
+                    json!({
+                        "condition": self.expr(&while_stmt.condition),
+                        "body": block(&while_stmt.loop_block),
+                        "on_break": while_stmt.on_break.as_ref().map(block),
+                        "on_continue": while_stmt.on_continue.as_ref().map(block),
+                    }),
+                )
+            }
+            Statement::Try(try_stmt) => {
+                let block = |block: &ParseNode<Block>| self.block(&block.data, &block.range);
+                let catch = try_stmt.catch.as_ref().map(|catch| {
+                    self.node(
+                        "Catch",
+                        &catch.range,
+                        json!({
+                            "name": self.name(catch.data.name.data, &catch.data.name.range),
+                            "body": block(&catch.data.block),
+                        }),
+                    )
+                });
+                self.node(
+                    "Try",
+                    range,
+                    json!({
+                        "body": block(&try_stmt.try_block),
+                        "catch": catch,
+                        "finally": try_stmt.finally.as_ref().map(block),
+                    }),
+                )
+            }
+            Statement::Block(block) => self.block(block, range),
+            Statement::Break => self.node("Break", range, json!({})),
+            Statement::Continue => self.node("Continue", range, json!({})),
+            Statement::Return(value) => self.node(
+                "Return",
+                range,
+                json!({ "value": value.as_ref().map(|v| self.expr(v)) }),
+            ),
+            Statement::Throw(value) => {
+                self.node("Throw", range, json!({ "value": self.expr(value) }))
+            }
+        }
+    }
+    fn block(&self, block: &Block, range: &Range<usize>) -> Value {
+        self.node(
+            "Block",
+            range,
+            json!({ "statements": self.statements(&block.statements) }),
+        )
+    }
+    fn if_statement(&self, if_stmt: &IfStatement, range: &Range<usize>) -> Value {
+        let else_block = if_stmt
+            .else_block
+            .as_ref()
+            .map(|else_block| match &else_block.data {
+                ElseBlock::Block(block) => self.block(block, &else_block.range),
+                ElseBlock::If(elif) => self.if_statement(elif, &else_block.range),
+            });
+        self.node(
+            "If",
+            range,
+            json!({
+                "condition": self.expr(&if_stmt.condition),
+                "body": self.block(&if_stmt.met_block.data, &if_stmt.met_block.range),
+                "else": else_block,
+            }),
+        )
+    }
+    fn var_decl(&self, decl: &VarDecl, range: &Range<usize>) -> Value {
+        self.node(
+            "VarDecl",
+            range,
+            json!({
+                "pattern": self.pattern(&decl.pattern.data, &decl.pattern.range),
+                "annotation": decl.annotation.as_ref().map(|a| self.type_annotation(a)),
+                "value": decl.value.as_ref().map(|v| self.expr(v)),
+            }),
+        )
+    }
+    fn func_decl(&self, decl: &FuncDecl, range: &Range<usize>) -> Value {
+        let body = match &decl.block.data {
+            FuncBlock::Block(block) => self.block(block, &decl.block.range),
+            FuncBlock::ReturnExpr(expr) => self.node(
+                "ReturnExpr",
+                &decl.block.range,
+                json!({ "expression": self.expression(expr, &decl.block.range) }),
+            ),
+        };
+        let params: Vec<Value> = decl
+            .params
+            .iter()
+            .map(|p| self.var_decl(&p.data, &p.range))
+            .collect();
+        self.node(
+            "FuncDecl",
+            range,
+            json!({
+                "name": self.name(Some(decl.name.data), &decl.name.range),
+                "params": params,
+                "returns": decl.return_type.as_ref().map(|t| self.type_annotation(t)),
+                "body": body,
+            }),
+        )
+    }
+    fn type_annotation(&self, annotation: &ParseNode<TypeAnnotation>) -> Value {
+        match &annotation.data {
+            TypeAnnotation::Name(name) => self.node(
+                "NamedType",
+                &annotation.range,
+                json!({ "name": name.as_str() }),
+            ),
+            TypeAnnotation::Array(element) => self.node(
+                "ArrayType",
+                &annotation.range,
+                json!({ "element": self.type_annotation(element) }),
+            ),
+            TypeAnnotation::Dictionary(fields) => {
+                let fields: Vec<Value> = fields
+                    .iter()
+                    .map(|(name, field)| {
+                        json!({
+                            "name": self.name(Some(name.data), &name.range),
+                            "annotation": self.type_annotation(field),
+                        })
+                    })
+                    .collect();
+                self.node(
+                    "DictionaryType",
+                    &annotation.range,
+                    json!({ "fields": fields }),
+                )
+            }
+        }
+    }
+    fn pattern(&self, pattern: &VarNameType, range: &Range<usize>) -> Value {
+        match pattern {
+            // `_` has a `null` name
+            VarNameType::Ident(name) => self.node(
+                "IdentPattern",
+                range,
+                json!({ "name": name.map(|n| n.as_str()) }),
+            ),
+            VarNameType::Array {
+                start_names,
+                end_names,
+            } => {
+                let names = |names: &[ParseNode<Option<StringName>>]| -> Vec<Value> {
+                    names.iter().map(|n| self.name(n.data, &n.range)).collect()
+                };
+                self.node(
+                    "ArrayPattern",
+                    range,
+                    json!({
+                        "start_names": names(start_names),
+                        "end_names": names(end_names),
+                    }),
+                )
+            }
+            VarNameType::Object(names) => {
+                let fields: Vec<Value> = names
+                    .iter()
+                    .map(|(field, name)| {
+                        json!({
+                            "field": field.as_str(),
+                            "name": name.data.as_str(),
+                            "range": self.range(&name.range),
+                        })
+                    })
+                    .collect();
+                self.node("ObjectPattern", range, json!({ "fields": fields }))
+            }
+        }
+    }
+
+    fn expr(&self, expr: &ParseNode<Expression>) -> Value {
+        self.expression(&expr.data, &expr.range)
+    }
+    fn exprs(&self, exprs: &[ParseNode<Expression>]) -> Value {
+        exprs.iter().map(|e| self.expr(e)).collect()
+    }
+    fn expression(&self, expr: &Expression, range: &Range<usize>) -> Value {
+        match expr {
+            Expression::None => self.node("None", range, json!({})),
+            Expression::Bool(b) => self.node("Bool", range, json!({ "value": b })),
+            Expression::Int(i) => self.node("Int", range, json!({ "value": i })),
+            Expression::Real(r) if !r.is_finite() => {
+                self.node("Real", range, json!({ "value": r.to_string() }))
+            }
+            Expression::Real(r) => self.node("Real", range, json!({ "value": r })),
+            Expression::String(s) => self.node("String", range, json!({ "value": s })),
+            Expression::Char(c) => self.node("Char", range, json!({ "value": c })),
+            Expression::Array(items) => {
+                self.node("Array", range, json!({ "items": self.exprs(items) }))
+            }
+            Expression::Dictionary(items) => {
+                let entries: Vec<Value> = items
+                    .iter()
+                    .map(|(key, value)| {
+                        let key = match &key.data {
+                            DictionaryKey::Ident(name) => {
+                                self.node("IdentKey", &key.range, json!({ "name": name.as_str() }))
+                            }
+                            DictionaryKey::Expr(expr) => self.node(
+                                "ExprKey",
+                                &key.range,
+                                json!({ "expression": self.expr(expr) }),
+                            ),
+                        };
+                        json!({ "key": key, "value": self.expr(value) })
+                    })
+                    .collect();
+                self.node("Dictionary", range, json!({ "entries": entries }))
+            }
+            Expression::Variable(name) => {
+                self.node("Variable", range, json!({ "name": name.as_str() }))
+            }
+            Expression::Binary(binary) => {
+                let operator = match binary.operator {
+                    SymbolKeyword::Symbol(s) => s.as_str(),
+                    SymbolKeyword::Keyword(k) => k.as_str(),
+                };
+                self.node(
+                    "Binary",
+                    range,
+                    json!({
+                        "operator": operator,
+                        "left": self.expr(&binary.left),
+                        "right": self.expr(&binary.right),
+                    }),
+                )
+            }
+            Expression::Assign(assign) => {
+                let operator = match assign.operator {
+                    Some(operator) => format!("{}=", operator),
+                    None => Symbol::Assign.to_string(),
+                };
+                self.node(
+                    "Assign",
+                    range,
+                    json!({
+                        "operator": operator,
+                        "target": self.expr(&assign.left),
+                        "value": self.expr(&assign.right),
+                    }),
+                )
+            }
+            Expression::Unary(unary) => self.node(
+                "Unary",
+                range,
+                json!({
+                    "operator": unary.operator.as_str(),
+                    "operand": self.expr(&unary.operand),
+                }),
+            ),
+            Expression::Suffix(suffix) => {
+                let object = self.expr(&suffix.node);
+                match &suffix.suffix {
+                    SuffixType::Call(args) => self.node(
+                        "Call",
+                        range,
+                        json!({ "callee": object, "arguments": self.exprs(args) }),
+                    ),
+                    SuffixType::Index(index) => self.node(
+                        "Index",
+                        range,
+                        json!({ "object": object, "index": self.expr(index) }),
+                    ),
+                    SuffixType::Property(name) => self.node(
+                        "Property",
+                        range,
+                        json!({ "object": object, "name": name.as_str() }),
+                    ),
+                }
+            }
+            Expression::Grouping(expr) => {
+                self.node("Grouping", range, json!({ "expression": self.expr(expr) }))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    fn to_json(source: &str) -> Value {
+        let file = SourceFile::new("main.lx", source);
+        let statements = Parser::new(source).parse().unwrap();
+        ast_to_json(&statements, &file)
+    }
+    // the byte offsets of the range of a node
+    fn offsets(node: &Value) -> (u64, u64) {
+        let offset = |end: &str| node["range"][end]["offset"].as_u64().unwrap();
+        (offset("start"), offset("end"))
+    }
+
+    #[test]
+    fn nodes_have_a_type_and_a_range() {
+        let json = to_json("let x: int = 1;\nprintln(x);");
+        assert_eq!(json["type"], "Program");
+        assert_eq!(offsets(&json), (0, 27));
+
+        let decl = &json["statements"][0];
+        assert_eq!(decl["type"], "VarDecl");
+        assert_eq!(decl["pattern"]["type"], "IdentPattern");
+        assert_eq!(decl["pattern"]["name"], "x");
+        assert_eq!(decl["annotation"]["name"], "int");
+        assert_eq!(
+            decl["value"],
+            json!({
+                "type": "Int",
+                "range": {
+                    "start": { "offset": 13, "line": 1, "column": 14 },
+                    "end": { "offset": 14, "line": 1, "column": 15 },
+                },
+                "value": 1,
+            })
+        );
+
+        let call = &json["statements"][1];
+        assert_eq!(
+            call["range"]["start"],
+            json!({ "offset": 16, "line": 2, "column": 1 })
+        );
+    }
+
+    #[test]
+    fn infinite_reals_are_strings() {
+        let json = to_json("[1e999, 2.5];");
+        let items = &json["statements"][0]["expression"]["items"];
+        assert_eq!(items[0]["type"], "Real");
+        assert_eq!(items[0]["value"], "inf");
+        assert_eq!(items[1]["value"], 2.5);
+    }
+
+    #[test]
+    fn dictionary_keys() {
+        let json = to_json("let d = {a = 1, [ \"b\" ] = 2};");
+        let entries = &json["statements"][0]["value"]["entries"];
+
+        assert_eq!(entries[0]["key"]["type"], "IdentKey");
+        assert_eq!(entries[0]["key"]["name"], "a");
+        assert_eq!(offsets(&entries[0]["key"]), (9, 10));
+
+        // the key includes the brackets, its expression doesn't
+        let key = &entries[1]["key"];
+        assert_eq!(key["type"], "ExprKey");
+        assert_eq!(offsets(key), (16, 23));
+        assert_eq!(key["expression"]["type"], "String");
+        assert_eq!(offsets(&key["expression"]), (18, 21));
+        assert_eq!(offsets(&entries[1]["value"]), (26, 27));
+    }
+}
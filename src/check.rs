@@ -0,0 +1,73 @@
+use crate::lint::{lint, LintConfig};
+use crate::parser::{error::*, parse_node::*, Parser};
+use crate::resolver::resolve;
+use crate::source_map::{FileId, SourceMap};
+use crate::string_name::StringName;
+use crate::typecheck::type_check;
+
+/// Runs every static analysis pass over the parsed program of `file`, without executing anything.
+/// Returns the diagnostics of all passes, in `file` and sorted by position. Lints are warnings
+/// unless denied in `lints`, so only diagnostics with `Severity::Error` should fail the check.
+/// `globals` are the names the program can use without declaring them, see `resolve`.
+pub fn check(
+    statements: &[ParseNode<Statement>],
+    sources: &SourceMap,
+    file: FileId,
+    lints: &LintConfig,
+    globals: &[StringName],
+) -> Vec<Error> {
+    let source = sources.get(file).source();
+    let mut errors = resolve(statements, globals.iter().copied()).errors;
+    errors.extend(type_check(statements));
+    errors.extend(lint(statements, source, lints));
+    errors.sort_by_key(|e| e.range.start);
+    errors.into_iter().map(|err| err.in_file(file)).collect()
+}
+
+/// Parses the source of `file`, the error is in `file`.
+pub fn parse_file(sources: &SourceMap, file: FileId) -> Result<Vec<ParseNode<Statement>>> {
+    Parser::new(sources.get(file).source())
+        .parse()
+        .map_err(|err| err.in_file(file))
+}
+
+/// Parses and checks `file`. A parse error stops the other passes from running.
+pub fn check_file(
+    sources: &SourceMap,
+    file: FileId,
+    lints: &LintConfig,
+    globals: &[StringName],
+) -> Vec<Error> {
+    match parse_file(sources, file) {
+        Ok(statements) => check(&statements, sources, file, lints, globals),
+        Err(err) => vec![err],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source_map::Span;
+
+    #[test]
+    fn diagnostics_are_in_their_file() {
+        let mut sources = SourceMap::new();
+        let first = sources.add("first.lx", "let a = 1;\nprintln(a);");
+        let second = sources.add("second.lx", "let b = c;\nprintln(b);");
+        let third = sources.add("third.lx", "let = 1;");
+        let lints = LintConfig::default();
+        let globals = [StringName::from("println")];
+
+        assert!(check_file(&sources, first, &lints, &globals).is_empty());
+
+        let errors = check_file(&sources, second, &lints, &globals);
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(errors[0].error, ErrorType::UndefinedVariable(_)));
+        assert_eq!(errors[0].span(), Span::new(second, 8..9));
+
+        let errors = check_file(&sources, third, &lints, &globals);
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(errors[0].error, ErrorType::ExpectedVarName));
+        assert_eq!(errors[0].file, third);
+    }
+}
@@ -0,0 +1,330 @@
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use serde_json::{json, Value};
+
+use crate::ast_json::range_json;
+use crate::explain::explain;
+use crate::parser::error::*;
+use crate::source_map::{SourceFile, SourceMap};
+
+/// Converts a diagnostic into a JSON object with its code, severity, message, file, range,
+/// labels, notes and suggestions. Ranges have the byte offsets and the 1-based line and column
+/// (counted in characters) of both ends, like in `ast_to_json`. The file is the one of the
+/// error in `sources`.
+pub fn error_to_json(error: &Error, sources: &SourceMap) -> Value {
+    let file = sources.get(error.file);
+    let labels: Vec<Value> = error
+        .labels
+        .iter()
+        .map(|label| {
+            json!({
+                "message": label.message,
+                "range": range_json(file, &label.range),
+            })
+        })
+        .collect();
+    let suggestions: Vec<Value> = error
+        .suggestions
+        .iter()
+        .map(|suggestion| {
+            json!({
+                "message": suggestion.message,
+                "replacement": suggestion.replacement,
+                "range": range_json(file, &suggestion.range),
+            })
+        })
+        .collect();
+
+    json!({
+        "code": error.error.code(),
+        "severity": error.severity.as_str(),
+        "message": error.error.to_string(),
+        "file": file.name(),
+        "range": range_json(file, &error.range),
+        "labels": labels,
+        "notes": error.notes,
+        "suggestions": suggestions,
+    })
+}
+
+/// Collects diagnostics of several files into a SARIF 2.1.0 log, for code scanning tools.
+#[derive(Debug, Default)]
+pub struct SarifLog {
+    results: Vec<Value>,
+    rules: BTreeMap<&'static str, String>, // code to the message of its first occurrence
+}
+impl SarifLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn add(&mut self, error: &Error, sources: &SourceMap) {
+        let file = sources.get(error.file);
+        let location = |range: &Range<usize>| {
+            json!({
+                "physicalLocation": {
+                    "artifactLocation": { "uri": file.name() },
+                    "region": region(file, range),
+                }
+            })
+        };
+
+        let related: Vec<Value> = error
+            .labels
+            .iter()
+            .map(|label| {
+                let mut related = location(&label.range);
+                related["message"] = json!({ "text": label.message });
+                related
+            })
+            .collect();
+        let fixes: Vec<Value> = error
+            .suggestions
+            .iter()
+            .map(|suggestion| {
+                json!({
+                    "description": { "text": suggestion.message },
+                    "artifactChanges": [{
+                        "artifactLocation": { "uri": file.name() },
+                        "replacements": [{
+                            "deletedRegion": region(file, &suggestion.range),
+                            "insertedContent": { "text": suggestion.replacement },
+                        }],
+                    }],
+                })
+            })
+            .collect();
+
+        // sarif messages have a single text, so the notes are appended to it
+        let mut message = error.error.to_string();
+        for note in &error.notes {
+            message.push_str("\nnote: ");
+            message.push_str(note);
+        }
+
+        let code = error.error.code();
+        self.rules
+            .entry(code)
+            .or_insert_with(|| error.error.to_string());
+        self.results.push(json!({
+            "ruleId": code,
+            "level": error.severity.as_str(),
+            "message": { "text": message },
+            "locations": [location(&error.range)],
+            "relatedLocations": related,
+            "fixes": fixes,
+        }));
+    }
+    pub fn to_json(&self) -> Value {
+        let rules: Vec<Value> = self
+            .rules
+            .iter()
+            .map(|(code, message)| {
+                json!({
+                    "id": code,
+                    "shortDescription": { "text": message },
+                    "help": { "text": explain(code).unwrap_or_default() },
+                })
+            })
+            .collect();
+        json!({
+            "version": "2.1.0",
+            "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": env!("CARGO_PKG_NAME"),
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "rules": rules,
+                    }
+                },
+                "columnKind": "unicodeCodePoints",
+                "results": self.results,
+            }],
+        })
+    }
+}
+
+fn region(file: &SourceFile, range: &Range<usize>) -> Value {
+    let (start_line, start_column) = file.line_column(range.start);
+    let (end_line, end_column) = file.line_column(range.end);
+    json!({
+        "startLine": start_line,
+        "startColumn": start_column,
+        "endLine": end_line,
+        "endColumn": end_column,
+        "byteOffset": range.start,
+        "byteLength": range.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source_map::FileId;
+
+    const SOURCE: &str = "let é = 1;\nfunc f(a, a) {}\n";
+
+    fn range_of(text: &str, from: usize) -> Range<usize> {
+        let start = from + SOURCE[from..].find(text).unwrap();
+        start..start + text.len()
+    }
+    // the diagnostics are in the second file, so the first one isn't picked by default
+    fn sources() -> (SourceMap, FileId) {
+        let mut sources = SourceMap::new();
+        sources.add("other.lx", "");
+        let file = sources.add("main.lx", SOURCE);
+        (sources, file)
+    }
+    // a warning on the first line, after a character of two bytes
+    fn unused(file: FileId) -> Error {
+        Error::new(range_of("é", 0), ErrorType::UnusedVariable("é".into()))
+            .with_severity(Severity::Warning)
+            .in_file(file)
+    }
+    // an error on the second line with a label, a note and a suggestion
+    fn duplicate(file: FileId) -> Error {
+        let first = range_of("a", range_of("(", 0).end);
+        let second = range_of("a", first.end);
+        Error::new(second.clone(), ErrorType::DuplicateParameter("a".into()))
+            .with_label(first, "first declared here")
+            .with_note("parameters need different names")
+            .with_suggestion(second, "b", "rename it")
+            .in_file(file)
+    }
+
+    #[test]
+    fn json_fields() {
+        let (sources, file) = sources();
+
+        assert_eq!(
+            error_to_json(&duplicate(file), &sources),
+            json!({
+                "code": "E0034",
+                "severity": "error",
+                "message": "Duplicate parameter name 'a'",
+                "file": "main.lx",
+                "range": {
+                    "start": { "offset": 22, "line": 2, "column": 11 },
+                    "end": { "offset": 23, "line": 2, "column": 12 },
+                },
+                "labels": [{
+                    "message": "first declared here",
+                    "range": {
+                        "start": { "offset": 19, "line": 2, "column": 8 },
+                        "end": { "offset": 20, "line": 2, "column": 9 },
+                    },
+                }],
+                "notes": ["parameters need different names"],
+                "suggestions": [{
+                    "message": "rename it",
+                    "replacement": "b",
+                    "range": {
+                        "start": { "offset": 22, "line": 2, "column": 11 },
+                        "end": { "offset": 23, "line": 2, "column": 12 },
+                    },
+                }],
+            })
+        );
+
+        // columns count characters, offsets count bytes
+        let json = error_to_json(&unused(file), &sources);
+        assert_eq!(json["severity"], "warning");
+        assert_eq!(json["code"], "W0001");
+        assert_eq!(
+            json["range"],
+            json!({
+                "start": { "offset": 4, "line": 1, "column": 5 },
+                "end": { "offset": 6, "line": 1, "column": 6 },
+            })
+        );
+        assert_eq!(json["labels"], json!([]));
+        assert_eq!(json["notes"], json!([]));
+        assert_eq!(json["suggestions"], json!([]));
+    }
+
+    #[test]
+    fn sarif_log() {
+        let (sources, file) = sources();
+        let mut log = SarifLog::new();
+        log.add(&duplicate(file), &sources);
+        log.add(&unused(file), &sources);
+        log.add(&unused(file), &sources);
+        let log = log.to_json();
+
+        assert_eq!(log["version"], "2.1.0");
+        let run = &log["runs"][0];
+        assert_eq!(run["tool"]["driver"]["name"], env!("CARGO_PKG_NAME"));
+        assert_eq!(run["columnKind"], "unicodeCodePoints");
+
+        // one rule per code, sorted by the code
+        let rules = run["tool"]["driver"]["rules"].as_array().unwrap();
+        let ids: Vec<&str> = rules.iter().map(|r| r["id"].as_str().unwrap()).collect();
+        assert_eq!(ids, ["E0034", "W0001"]);
+        assert_eq!(
+            rules[0]["shortDescription"]["text"],
+            "Duplicate parameter name 'a'"
+        );
+        assert_eq!(rules[0]["help"]["text"], explain("E0034").unwrap());
+
+        let results = run["results"].as_array().unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(
+            results[0],
+            json!({
+                "ruleId": "E0034",
+                "level": "error",
+                "message": {
+                    "text": "Duplicate parameter name 'a'\nnote: parameters need different names",
+                },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": "main.lx" },
+                        "region": {
+                            "startLine": 2,
+                            "startColumn": 11,
+                            "endLine": 2,
+                            "endColumn": 12,
+                            "byteOffset": 22,
+                            "byteLength": 1,
+                        },
+                    },
+                }],
+                "relatedLocations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": "main.lx" },
+                        "region": {
+                            "startLine": 2,
+                            "startColumn": 8,
+                            "endLine": 2,
+                            "endColumn": 9,
+                            "byteOffset": 19,
+                            "byteLength": 1,
+                        },
+                    },
+                    "message": { "text": "first declared here" },
+                }],
+                "fixes": [{
+                    "description": { "text": "rename it" },
+                    "artifactChanges": [{
+                        "artifactLocation": { "uri": "main.lx" },
+                        "replacements": [{
+                            "deletedRegion": {
+                                "startLine": 2,
+                                "startColumn": 11,
+                                "endLine": 2,
+                                "endColumn": 12,
+                                "byteOffset": 22,
+                                "byteLength": 1,
+                            },
+                            "insertedContent": { "text": "b" },
+                        }],
+                    }],
+                }],
+            })
+        );
+        assert_eq!(results[1]["level"], "warning");
+        assert_eq!(results[1]["ruleId"], "W0001");
+        assert_eq!(results[1]["fixes"], json!([]));
+    }
+}
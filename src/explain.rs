@@ -0,0 +1,1160 @@
+/// Longer description of the diagnostic with the given code (such as `E0007`), with an example
+/// of wrong code and how to correct it. Returns `None` for unknown codes.
+pub fn explain(code: &str) -> Option<&'static str> {
+    let code = code.trim().to_ascii_uppercase();
+    EXPLANATIONS
+        .iter()
+        .find(|(c, _)| *c == code)
+        .map(|(_, explanation)| explanation.trim())
+}
+
+// codes are never reused or renumbered, a new diagnostic takes the next free code
+const EXPLANATIONS: &[(&str, &str)] = &[
+    (
+        "E0001",
+        r#"
+An integer literal is too large to fit in 64 bits.
+
+Wrong:
+    let big = 18446744073709551616;
+
+Correct, use a real number instead:
+    let big = 1.8446744073709552e19;
+"#,
+    ),
+    (
+        "E0002",
+        r#"
+A '(' was expected, such as the start of the parameter list of a function.
+
+Wrong:
+    func add a, b -> a + b
+
+Correct:
+    func add(a, b) -> a + b
+"#,
+    ),
+    (
+        "E0003",
+        r#"
+A '(' was opened, but never closed with a ')'. The error points at where the ')' was
+expected, and labels the '(' it should close.
+
+Wrong:
+    let x = (1 + 2;
+    print(x;
+
+Correct:
+    let x = (1 + 2);
+    print(x);
+"#,
+    ),
+    (
+        "E0004",
+        r#"
+A '{' was expected. Unicode escapes need their character code between curly brackets.
+
+Wrong:
+    let s = "\u41";
+
+Correct:
+    let s = "\u{41}";
+"#,
+    ),
+    (
+        "E0005",
+        r#"
+A '{' was opened, but never closed with a '}'. This applies to blocks, dictionaries and unicode
+escapes. The error points at where the '}' was expected, and labels the '{' it should close.
+
+Wrong:
+    func f(a) {
+        if a {
+            print(a);
+    }
+
+Correct:
+    func f(a) {
+        if a {
+            print(a);
+        }
+    }
+"#,
+    ),
+    (
+        "E0006",
+        r#"
+A '[' was expected. The current parser doesn't report this error, the code is reserved.
+
+Wrong:
+    let a = 1, 2, 3];
+
+Correct:
+    let a = [1, 2, 3];
+"#,
+    ),
+    (
+        "E0007",
+        r#"
+A '[' was opened, but never closed with a ']'. This applies to arrays, indexing, array
+patterns and computed dictionary keys. The error points at where the ']' was expected, and
+labels the '[' it should close.
+
+Wrong:
+    let a = [1, 2, 3;
+    let first = a[0;
+
+Correct:
+    let a = [1, 2, 3];
+    let first = a[0];
+"#,
+    ),
+    (
+        "E0008",
+        r#"
+A number was expected. The current parser doesn't report this error, the code is reserved.
+
+Wrong:
+    let x = -;
+
+Correct:
+    let x = -1;
+"#,
+    ),
+    (
+        "E0009",
+        r#"
+An integer was expected. This happens after a radix prefix ('0b', '0o' or '0x') without
+digits, or an exponent ('e' or 'E') without digits.
+
+Wrong:
+    let mask = 0x;
+    let large = 1e;
+
+Correct:
+    let mask = 0xff;
+    let large = 1e9;
+"#,
+    ),
+    (
+        "E0010",
+        r#"
+An identifier was expected, such as the name of a function, or of a property after '.'.
+
+Wrong:
+    func (a) -> a
+    let n = point.;
+
+Correct:
+    func id(a) -> a
+    let n = point.x;
+"#,
+    ),
+    (
+        "E0011",
+        r#"
+An expression was expected, such as after an operator, '=' or the 'if' and 'while' keywords.
+
+Wrong:
+    let x = 1 + ;
+    if { x = 2; }
+
+Correct:
+    let x = 1 + 2;
+    if x > 2 { x = 2; }
+"#,
+    ),
+    (
+        "E0012",
+        r#"
+A variable name or pattern was expected after 'let'.
+
+Wrong:
+    let = 5;
+
+Correct:
+    let five = 5;
+    let [first, ...] = [1, 2, 3];
+"#,
+    ),
+    (
+        "E0013",
+        r#"
+A function name was expected after 'func'. The current parser reports a missing function name
+as E0010 instead, the code is reserved.
+
+Wrong:
+    func (a, b) -> a + b
+
+Correct:
+    func add(a, b) -> a + b
+"#,
+    ),
+    (
+        "E0014",
+        r#"
+Expression statements and variable declarations must end with a ';'. Statements ending in a
+block, and functions written with '->', don't need one.
+
+Wrong:
+    let x = 5
+    print(x)
+
+Correct:
+    let x = 5;
+    print(x);
+"#,
+    ),
+    (
+        "E0015",
+        r#"
+A ':' was expected. The current parser doesn't report this error, the code is reserved.
+
+Wrong:
+    let [a b] = pair;
+
+Correct:
+    let [a, b] = pair;
+"#,
+    ),
+    (
+        "E0016",
+        r#"
+A '=' was expected between a dictionary key and its value.
+
+Wrong:
+    let point = {x: 1, y: 2};
+
+Correct:
+    let point = {x = 1, y = 2};
+    let squares = {[2] = 4, [3] = 9};
+"#,
+    ),
+    (
+        "E0017",
+        r#"
+A block of statements between '{' and '}' was expected, such as after a condition, 'else',
+'onbreak' or 'oncontinue'.
+
+Wrong:
+    if ready print("go");
+
+Correct:
+    if ready { print("go"); }
+"#,
+    ),
+    (
+        "E0018",
+        r#"
+A function needs a body after its parameters: either a block, or '->' followed by the
+returned expression.
+
+Wrong:
+    func double(x) x * 2
+
+Correct:
+    func double(x) -> x * 2
+    func greet(name) { print("hello " + name); }
+"#,
+    ),
+    (
+        "E0019",
+        r#"
+The parser stopped before the end of the file, because what follows is not a statement. This is
+often a stray closing bracket.
+
+Wrong:
+    func f() { }
+    }
+
+Correct:
+    func f() { }
+"#,
+    ),
+    (
+        "E0020",
+        r#"
+A string literal is missing its closing '"'. Raw strings must end with a '"' followed by as
+many ')' as there are '(' after their 'r'.
+
+Wrong:
+    let s = "hello;
+
+Correct:
+    let s = "hello";
+    let raw = r(("a "quoted" word"));
+"#,
+    ),
+    (
+        "E0021",
+        r#"
+A '\x' escape must be followed by exactly 2 hexadecimal digits.
+
+Wrong:
+    let tab = '\x9';
+
+Correct:
+    let tab = '\x09';
+"#,
+    ),
+    (
+        "E0022",
+        r#"
+A '\' at the end of the source starts an escape sequence that never ends.
+
+Wrong:
+    let s = "back\
+
+Correct:
+    let s = "back\\";
+"#,
+    ),
+    (
+        "E0023",
+        r#"
+A character literal is missing its closing '.
+
+Wrong:
+    let c = 'a;
+
+Correct:
+    let c = 'a';
+"#,
+    ),
+    (
+        "E0024",
+        r#"
+An unknown escape sequence was used. The valid escapes are '\n', '\t', '\r', '\0', '\\',
+'\'', '\"', '\x' followed by 2 hexadecimal digits and '\u{...}'.
+
+Wrong:
+    let s = "C:\path";
+
+Correct:
+    let s = "C:\\path";
+"#,
+    ),
+    (
+        "E0025",
+        r#"
+The code of a '\x' or '\u{...}' escape is not a valid unicode character. '\u{...}' takes up
+to 4 hexadecimal digits.
+
+Wrong:
+    let c = '\u{d800}';
+
+Correct:
+    let c = '\u{e9}';
+"#,
+    ),
+    (
+        "E0026",
+        r#"
+A character literal must contain exactly 1 character. Use a string for empty text.
+
+Wrong:
+    let c = '';
+
+Correct:
+    let s = "";
+"#,
+    ),
+    (
+        "E0027",
+        r#"
+A character literal must contain exactly 1 character. Use a string for longer text.
+
+Wrong:
+    let c = 'ab';
+
+Correct:
+    let s = "ab";
+"#,
+    ),
+    (
+        "E0028",
+        r#"
+A symbol was found where it can't be used. The current parser reports these as E0019 or E0011
+instead, the code is reserved.
+
+Wrong:
+    let x = 1 $ 2;
+
+Correct:
+    let x = 1 + 2;
+"#,
+    ),
+    (
+        "E0029",
+        r#"
+An array pattern can contain '...' only once, to skip the items between its start and end
+names.
+
+Wrong:
+    let [first, ..., middle, ..., last] = items;
+
+Correct:
+    let [first, ..., last] = items;
+"#,
+    ),
+    (
+        "E0030",
+        r#"
+'_' discards the value assigned to it, so it can't be read.
+
+Wrong:
+    let _ = compute();
+    print(_);
+
+Correct:
+    let result = compute();
+    print(result);
+"#,
+    ),
+    (
+        "E0031",
+        r#"
+Only variables, properties and indexes can be assigned to.
+
+Wrong:
+    f() = 5;
+    1 + x = 2;
+
+Correct:
+    x = 5;
+    point.x = 5;
+    items[0] = 5;
+"#,
+    ),
+    (
+        "E0032",
+        r#"
+A variable was used, but it isn't declared in this scope or any scope around it. Variables are
+only visible in the block they are declared in.
+
+Wrong:
+    if ready { let message = "go"; }
+    print(message);
+
+Correct:
+    let message = none;
+    if ready { message = "go"; }
+    print(message);
+"#,
+    ),
+    (
+        "E0033",
+        r#"
+A variable was read in its own initializer. The variable only exists once its initializer is
+evaluated, even if a variable with the same name is declared in an outer scope.
+
+Wrong:
+    let count = 1;
+    {
+        let count = count + 1;
+    }
+
+Correct:
+    let count = 1;
+    {
+        let next = count + 1;
+        count = next;
+    }
+"#,
+    ),
+    (
+        "E0034",
+        r#"
+A function declares the same parameter name more than once, including names inside parameter
+patterns.
+
+Wrong:
+    func distance(a, [a, b]) -> a - b
+
+Correct:
+    func distance(c, [a, b]) -> c * (a - b)
+"#,
+    ),
+    (
+        "E0035",
+        r#"
+'break' and 'continue' can only be used inside the block of a loop. The 'onbreak' and
+'oncontinue' blocks run after the loop, and a function body can't jump out of a loop around
+the function.
+
+Wrong:
+    let i = 0;
+    if i > 3 { break; }
+
+Correct:
+    let i = 0;
+    while true {
+        if i > 3 { break; }
+        i += 1;
+    }
+"#,
+    ),
+    (
+        "E0036",
+        r#"
+'return' can only be used inside a function.
+
+Wrong:
+    let x = 5;
+    return x;
+
+Correct:
+    func five() {
+        let x = 5;
+        return x;
+    }
+"#,
+    ),
+    (
+        "E0037",
+        r#"
+A statement starts with a word that is a keyword in other languages, or a misspelled keyword.
+Variables are declared with 'let', functions with 'func', and an 'else if' is written 'elif'.
+
+Wrong:
+    var x = 1;
+    if x > 0 {} else if x < 0 {}
+
+Correct:
+    let x = 1;
+    if x > 0 {} elif x < 0 {}
+"#,
+    ),
+    (
+        "E0038",
+        r#"
+'&&' and '||' aren't operators, the logical operators are the 'and' and 'or' keywords. A
+single '&' or '|' is the bitwise operator.
+
+Wrong:
+    let a = true;
+    let b = a && false;
+
+Correct:
+    let a = true;
+    let b = a and false;
+"#,
+    ),
+    (
+        "E0039",
+        r#"
+The code has a character that isn't displayed, such as a zero-width space, a bidirectional
+text control or a byte order mark. These can split an identifier in two, or make the code
+look different from how it runs. They are allowed inside strings and comments, and the
+zero-width joiners are allowed inside identifiers (see W0011).
+
+Wrong (a zero-width space, U+200B, hides between 'total' and 'count'):
+    let totalcount = 1;
+
+Correct:
+    let total_count = 1;
+"#,
+    ),
+    (
+        "E0040",
+        r#"
+A 'try' block is followed by neither a 'catch' nor a 'finally' block. Without one of them the
+'try' does nothing: an error inside it would be raised all the same.
+
+Wrong:
+    try { risky(); }
+
+Correct:
+    try { risky(); } catch err { println("failed:", err.message); }
+    try { risky(); } finally { cleanup(); }
+"#,
+    ),
+    (
+        "E0041",
+        r#"
+A ':' that starts a type annotation isn't followed by a type. A type is the name of a
+type, '[type]' for an array of it or '{key: type}' for a dictionary with these keys.
+
+Wrong:
+    let count: = 0;
+
+Correct:
+    let count: int = 0;
+    let names: [string] = [];
+    let user: {name: string, age: int} = {name = "Ada", age = 36};
+    func count_of(names: [string]): int -> names.len()
+"#,
+    ),
+    (
+        "E0042",
+        r#"
+A type annotation uses a name that isn't a type. The types are any, none, bool, int, real,
+string, char, array, dictionary, function and error.
+
+Wrong:
+    let name: str = "Ada";
+
+Correct:
+    let name: string = "Ada";
+"#,
+    ),
+    (
+        "E0043",
+        r#"
+A value is given where an annotation expects a value of another type: in the declaration or
+an assignment of an annotated variable, as the argument of an annotated parameter or as the
+return value of a function with a return type. Values without an annotation can have any type,
+so they are never reported. An int is not a real, since they divide differently.
+
+Wrong:
+    func greet(name: string): string -> "hello " + name
+    greet(42);
+
+Correct:
+    func greet(name: string): string -> "hello " + name
+    greet("Ada");
+"#,
+    ),
+    (
+        "E0044",
+        r#"
+An operator is applied to operands of types it doesn't accept, and at least one of the types
+comes from an annotation. The operation would fail when it runs.
+
+Wrong:
+    let count: int = 3;
+    println("count: " + count);
+
+Correct:
+    println("count: " + str(count));
+"#,
+    ),
+    (
+        "E0045",
+        r#"
+A variable or parameter unpacks a dictionary with '{ }'. Only arrays can be unpacked, with
+'[ ]'; the entries of a dictionary are read one by one.
+
+Wrong:
+    let {name, age} = person;
+
+Correct:
+    let name = person.name;
+    let age = person.age;
+"#,
+    ),
+    (
+        "R0001",
+        r#"
+A variable is read or assigned, but it isn't defined. The checks before running a program
+report this as E0032, so it only happens to programs that are run without being checked, such
+as ones given to `Interpreter::run` by a host.
+
+Wrong:
+    func f() -> g()
+    f();
+
+Correct:
+    func g() -> 1
+    func f() -> g()
+    f();
+"#,
+    ),
+    (
+        "R0002",
+        r#"
+A variable is read before its declaration has run. Functions are hoisted, so they can be
+called before a variable they use is declared.
+
+Wrong:
+    func f() -> x
+    f();
+    let x = 1;
+
+Correct:
+    func f() -> x
+    let x = 1;
+    f();
+"#,
+    ),
+    (
+        "R0003",
+        r#"
+A binary operator is applied to values of types it doesn't accept. There is no implicit
+conversion between types, apart from ints and reals in arithmetic.
+
+Wrong:
+    let n = 3;
+    println("n: " + n);
+
+Correct:
+    println("n: " + str(n));
+"#,
+    ),
+    (
+        "R0004",
+        r#"
+A unary operator, '-' or '!', is applied to a value of a type it doesn't accept.
+
+Wrong:
+    let s = "a";
+    println(-s);
+
+Correct:
+    println(-int("1"));
+"#,
+    ),
+    (
+        "R0005",
+        r#"
+The result of an int operation doesn't fit in 64 bits. Ints don't wrap around.
+
+Wrong:
+    let big = 9223372036854775807;
+    println(big + 1);
+
+Correct, use a real number instead:
+    println(real(big) + 1.0);
+"#,
+    ),
+    (
+        "R0006",
+        r#"
+An int is divided by zero, or its remainder by zero is taken. Reals divided by zero give an
+infinity or a NaN instead.
+
+Wrong:
+    let n = 0;
+    println(10 / n);
+
+Correct:
+    if n != 0 {
+        println(10 / n);
+    }
+"#,
+    ),
+    (
+        "R0007",
+        r#"
+An int is shifted by a negative amount, or by 64 bits or more.
+
+Wrong:
+    let n = 64;
+    println(1 << n);
+
+Correct:
+    println(1 << 63);
+"#,
+    ),
+    (
+        "R0008",
+        r#"
+A value that isn't a function is called.
+
+Wrong:
+    let f = 3;
+    f();
+
+Correct:
+    func f() -> 3
+    f();
+"#,
+    ),
+    (
+        "R0009",
+        r#"
+A function is called with fewer or more arguments than it takes.
+
+Wrong:
+    func add(a, b) -> a + b
+    add(1);
+
+Correct:
+    add(1, 2);
+"#,
+    ),
+    (
+        "R0010",
+        r#"
+A native function or method is given an argument of a type it doesn't take.
+
+Wrong:
+    println(sqrt("4"));
+
+Correct:
+    println(sqrt(4));
+"#,
+    ),
+    (
+        "R0011",
+        r#"
+A value can't be converted to the type asked for, such as a string that isn't a number given
+to 'int', or a code that isn't a character given to 'char'.
+
+Wrong:
+    println(int("4x"));
+
+Correct:
+    println(int("4"));
+"#,
+    ),
+    (
+        "R0012",
+        r#"
+An argument of a native function or method has the right type, but a value it doesn't accept,
+such as an empty range or a negative count.
+
+Wrong:
+    println(random(5, 1));
+
+Correct:
+    println(random(1, 5));
+"#,
+    ),
+    (
+        "R0013",
+        r#"
+A value that isn't an array, string or dictionary is indexed.
+
+Wrong:
+    let n = 5;
+    println(n[0]);
+
+Correct:
+    let n = [5];
+    println(n[0]);
+"#,
+    ),
+    (
+        "R0014",
+        r#"
+An array or string is indexed with something other than an int.
+
+Wrong:
+    let a = [1];
+    println(a["0"]);
+
+Correct:
+    println(a[0]);
+"#,
+    ),
+    (
+        "R0015",
+        r#"
+An array or string is indexed past its end, or with a negative index. Strings are indexed by
+chars, so the last index of a string is `len(s) - 1`.
+
+Wrong:
+    let a = [1, 2];
+    println(a[2]);
+
+Correct:
+    println(a[len(a) - 1]);
+"#,
+    ),
+    (
+        "R0016",
+        r#"
+A value that can't be a dictionary key is used as one. Arrays, dictionaries and functions
+can't be keys, since they can change or have no value to compare.
+
+Wrong:
+    let d = {};
+    d[[1]] = 2;
+
+Correct:
+    d["1"] = 2;
+"#,
+    ),
+    (
+        "R0017",
+        r#"
+A dictionary is read with a key it doesn't have. The 'get' method gives a default instead, and
+'has' tells whether the key is there.
+
+Wrong:
+    let d = {a = 1};
+    println(d.b);
+
+Correct:
+    println(d.get("b", 0));
+"#,
+    ),
+    (
+        "R0018",
+        r#"
+A property is read from a value that doesn't have it. Only dictionaries and errors have
+properties.
+
+Wrong:
+    let n = 1;
+    println(n.size);
+
+Correct:
+    let a = [1];
+    println(a.len());
+"#,
+    ),
+    (
+        "R0019",
+        r#"
+A method is called on a value whose type doesn't have it.
+
+Wrong:
+    let a = [1];
+    a.append(2);
+
+Correct:
+    a.push(2);
+"#,
+    ),
+    (
+        "R0020",
+        r#"
+A value that isn't an array is unpacked with '[ ]'.
+
+Wrong:
+    let n = 1;
+    let [a, b] = n;
+
+Correct:
+    let [a, b] = [n, n];
+"#,
+    ),
+    (
+        "R0021",
+        r#"
+An array is unpacked into more names than it has elements. With '...', the names before and
+after it still need an element each.
+
+Wrong:
+    let [a, b, c] = [1, 2];
+
+Correct:
+    let [a, b] = [1, 2];
+"#,
+    ),
+    (
+        "R0022",
+        r#"
+Functions were called inside of each other too many times, usually because a recursive
+function never reaches the case where it stops.
+
+Wrong:
+    func f(n) -> f(n + 1)
+    f(0);
+
+Correct:
+    func f(n) {
+        if n >= 10 {
+            return n;
+        }
+        return f(n + 1);
+    }
+    f(0);
+"#,
+    ),
+    (
+        "R0023",
+        r#"
+A value was thrown with 'throw' and never caught. A caught error that is thrown again keeps
+its kind, but has this code.
+
+Wrong:
+    throw error("no such user", "NotFound");
+
+Correct:
+    try {
+        throw error("no such user", "NotFound");
+    } catch e {
+        println(e.message);
+    }
+"#,
+    ),
+    (
+        "W0001",
+        r#"
+A local variable is declared but never read. Assigning to it doesn't count as a use. This is
+the 'unused_variables' lint; prefix the name with an underscore to silence it.
+
+Wrong:
+    func area(w, h) {
+        let unused = w;
+        return w * h;
+    }
+
+Correct:
+    func area(w, h) {
+        let _unused = w;
+        return w * h;
+    }
+"#,
+    ),
+    (
+        "W0002",
+        r#"
+A variable is declared with the same name as a variable of an outer scope, which hides the
+outer one for the rest of the block. This is the 'shadowing' lint.
+
+Wrong:
+    let count = 1;
+    {
+        let count = 2;
+        print(count);
+    }
+
+Correct:
+    let count = 1;
+    {
+        let inner_count = 2;
+        print(inner_count);
+    }
+"#,
+    ),
+    (
+        "W0003",
+        r#"
+A statement comes after a 'break', 'continue' or 'return' in the same block, so it can never
+run. This is the 'unreachable_code' lint.
+
+Wrong:
+    func one() {
+        return 1;
+        print("never printed");
+    }
+
+Correct:
+    func one() {
+        print("printed");
+        return 1;
+    }
+"#,
+    ),
+    (
+        "W0004",
+        r#"
+A 'while true' loop has no 'break' or 'return' in it, so it never ends. This is the
+'while_true' lint.
+
+Wrong:
+    let i = 0;
+    while true {
+        i += 1;
+    }
+
+Correct:
+    let i = 0;
+    while true {
+        if i > 10 { break; }
+        i += 1;
+    }
+"#,
+    ),
+    (
+        "W0005",
+        r#"
+An 'onbreak' or 'oncontinue' block is empty, so it does nothing and can be removed. This is
+the 'empty_loop_handlers' lint.
+
+Wrong:
+    while false {} onbreak {}
+
+Correct:
+    while false {}
+"#,
+    ),
+    (
+        "W0006",
+        r#"
+A value is compared with itself, so the result is always the same. This is usually a typo for
+a comparison with another value. This is the 'self_comparison' lint.
+
+Wrong:
+    let a = 1;
+    let b = 2;
+    print(a == a);
+
+Correct:
+    let a = 1;
+    let b = 2;
+    print(a == b);
+"#,
+    ),
+    (
+        "W0007",
+        r#"
+A '// lint:' or '// lint-file:' comment names a lint that doesn't exist. The lints are
+unused_variables, shadowing, unreachable_code, while_true, empty_loop_handlers,
+self_comparison, mixed_script_identifiers, confusable_identifiers, constant_errors and
+invisible_joiners.
+
+Wrong:
+    // lint: allow(unused)
+    let x = 1;
+
+Correct:
+    // lint: allow(unused_variables)
+    let x = 1;
+"#,
+    ),
+    (
+        "W0008",
+        r#"
+An identifier has letters of more than one script, such as a latin word with a cyrillic 'а'
+in it. It reads like another identifier but is a different name. This is the
+'mixed_script_identifiers' lint.
+
+Wrong:
+    let pаssword = "secret";
+
+Correct:
+    let password = "secret";
+"#,
+    ),
+    (
+        "W0009",
+        r#"
+Two different identifiers look the same, because one uses characters that are confusable with
+the other's, such as the cyrillic 'а' and the latin 'a'. This is the 'confusable_identifiers'
+lint.
+
+Wrong:
+    let a = 1;
+    let а = 2;
+
+Correct:
+    let a = 1;
+    let b = 2;
+"#,
+    ),
+    (
+        "W0010",
+        r#"
+An operator is applied to literals in a way that always fails when it runs, such as an int
+overflow or a division by zero. This is the 'constant_errors' lint, which is denied by default.
+If the error is intended, for example to test a 'catch' block, allow the lint.
+
+Wrong:
+    let big = 9223372036854775807 + 1;
+
+Correct:
+    let big = 9223372036854775807.0 + 1;
+"#,
+    ),
+    (
+        "W0011",
+        r#"
+An identifier contains a zero-width non-joiner (U+200C) or joiner (U+200D) where it can't
+change how the name is drawn: next to an ascii character, next to another joiner or at the end
+of the name. Scripts such as Persian or Devanagari need them between letters, so they are
+allowed inside identifiers, but elsewhere they only make a name differ from one that looks the
+same. This is the 'invisible_joiners' lint.
+
+Wrong (a zero-width joiner, U+200D, hides between 'total' and 'count'):
+    let total‍count = 1;
+
+Correct:
+    let totalcount = 1;
+"#,
+    ),
+];
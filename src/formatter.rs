@@ -0,0 +1,530 @@
+use std::ops::Range;
+
+use crate::parser::{error::*, parse_node::*, Parser};
+use crate::string_name::StringName;
+
+#[derive(Debug, Clone)]
+pub struct FormatOptions {
+    pub indent_width: usize,
+    pub max_width: usize, // lists (arguments, arrays, dictionaries, params) longer than this are broken into multiple lines
+}
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            indent_width: 4,
+            max_width: 100,
+        }
+    }
+}
+
+/// Parses the source and prints it back in the canonical style. Comments are kept, but comments
+/// in the middle of an expression are moved before the statement they belong to.
+pub fn format(source: &str, options: &FormatOptions) -> Result<String> {
+    let statements = Parser::new(source).parse()?;
+    let comments = Parser::new(source).comments();
+
+    let mut formatter = Formatter {
+        source,
+        options,
+        comments,
+        next_comment: 0,
+        out: String::new(),
+        indent: 0,
+        flat: false,
+    };
+    formatter.statements(&statements, source.len());
+
+    Ok(formatter.out)
+}
+
+struct Formatter<'a> {
+    source: &'a str,
+    options: &'a FormatOptions,
+    comments: Vec<Range<usize>>,
+    next_comment: usize,
+    out: String,
+    indent: usize,
+    flat: bool, // when set, lists are never broken into multiple lines
+}
+impl<'a> Formatter<'a> {
+    fn write(&mut self, s: &str) {
+        if s.is_empty() {
+            return;
+        }
+        // indentation is written lazily, so empty lines don't have trailing whitespace
+        if self.out.ends_with('\n') {
+            for _ in 0..self.indent * self.options.indent_width {
+                self.out.push(' ');
+            }
+        }
+        self.out.push_str(s);
+    }
+    fn newline(&mut self) {
+        self.out.push('\n');
+    }
+    fn line_width(&self) -> usize {
+        let line = match self.out.rfind('\n') {
+            Some(i) => &self.out[i + 1..],
+            None => &self.out,
+        };
+        let indent = if line.is_empty() {
+            self.indent * self.options.indent_width
+        } else {
+            0
+        };
+        indent + line.chars().count()
+    }
+    // renders without line breaks, and returns the result instead of writing it
+    fn flat(&mut self, render: impl FnOnce(&mut Self)) -> String {
+        let out = std::mem::take(&mut self.out);
+        let flat = std::mem::replace(&mut self.flat, true);
+        let indent = std::mem::replace(&mut self.indent, 0);
+
+        render(self);
+
+        self.indent = indent;
+        self.flat = flat;
+        std::mem::replace(&mut self.out, out)
+    }
+
+    // writes an empty line if the source had at least one between the two positions
+    fn blank_line(&mut self, prev_end: Option<usize>, start: usize) {
+        let Some(prev_end) = prev_end else {
+            return;
+        };
+        if prev_end < start && self.source[prev_end..start].matches('\n').count() > 1 {
+            self.newline();
+        }
+    }
+    // writes every comment that starts before `end` on its own line
+    fn comments_before(&mut self, end: usize, prev_end: &mut Option<usize>) {
+        while let Some(comment) = self.comments.get(self.next_comment).cloned() {
+            if comment.start >= end {
+                break;
+            }
+            self.next_comment += 1;
+
+            self.blank_line(*prev_end, comment.start);
+            self.write(&self.source[comment.clone()]);
+            self.newline();
+            *prev_end = Some(comment.end);
+        }
+    }
+    // writes the comment following `end` on the same line, if there is one
+    fn trailing_comment(&mut self, end: usize) -> Option<usize> {
+        let comment = self.comments.get(self.next_comment).cloned()?;
+        if comment.start < end || self.source[end..comment.start].contains('\n') {
+            return None;
+        }
+        self.next_comment += 1;
+
+        self.write(" ");
+        self.write(&self.source[comment.clone()]);
+        Some(comment.end)
+    }
+
+    fn statements(&mut self, statements: &[ParseNode<Statement>], end: usize) {
+        let mut prev_end = None;
+
+        for stmt in statements {
+            // comments inside simple statements can't be placed anywhere else but before it
+            let comments_end = match &stmt.data {
+                Statement::Declaration(Declaration::Func(decl)) => decl.block.start(),
+                Statement::If(if_stmt) => if_stmt.met_block.start(),
+                Statement::While(while_stmt) => while_stmt.loop_block.start(),
+                Statement::Try(try_stmt) => try_stmt.try_block.start(),
+                Statement::Block(_) => stmt.start(),
+                Statement::Declaration(Declaration::Var(_))
+                | Statement::Expression(_)
+                | Statement::Break
+                | Statement::Continue
+                | Statement::Return(_)
+                | Statement::Throw(_) => stmt.end(),
+            };
+            self.comments_before(comments_end, &mut prev_end);
+            self.blank_line(prev_end, stmt.start());
+
+            self.statement(stmt);
+
+            let mut stmt_end = stmt.end();
+            if let Some(semicolon) = self.source[stmt_end..end].find(';') {
+                if self.source[stmt_end..stmt_end + semicolon]
+                    .trim()
+                    .is_empty()
+                {
+                    stmt_end += semicolon + 1;
+                }
+            }
+            prev_end = Some(self.trailing_comment(stmt_end).unwrap_or(stmt_end));
+            self.newline();
+        }
+
+        self.comments_before(end, &mut prev_end);
+    }
+    fn statement(&mut self, stmt: &ParseNode<Statement>) {
+        match &stmt.data {
+            Statement::Declaration(Declaration::Var(decl)) => {
+                self.write(Keyword::Var.as_str());
+                self.write(" ");
+                self.var_decl(decl);
+                self.write(";");
+            }
+            Statement::Declaration(Declaration::Func(decl)) => self.func_decl(decl),
+            Statement::Expression(expr) => {
+                self.expression(expr, &stmt.range);
+                self.write(";");
+            }
+            Statement::If(if_stmt) => self.if_statement(if_stmt),
+            Statement::While(while_stmt) => {
+                self.write("while ");
+                self.expr(&while_stmt.condition);
+                self.write(" ");
+                self.block(&while_stmt.loop_block);
+                if let Some(block) = &while_stmt.on_break {
+                    self.write(" onbreak ");
+                    self.block(block);
+                }
+                if let Some(block) = &while_stmt.on_continue {
+                    self.write(" oncontinue ");
+                    self.block(block);
+                }
+            }
+            Statement::Try(try_stmt) => {
+                self.write("try ");
+                self.block(&try_stmt.try_block);
+                if let Some(catch) = &try_stmt.catch {
+                    self.write(" catch ");
+                    self.write(catch.data.name.data.map_or("_", |n| n.as_str()));
+                    self.write(" ");
+                    self.block(&catch.data.block);
+                }
+                if let Some(block) = &try_stmt.finally {
+                    self.write(" finally ");
+                    self.block(block);
+                }
+            }
+            Statement::Block(block) => self.block_statements(&block.statements, stmt.end()),
+            Statement::Break => self.write("break;"),
+            Statement::Continue => self.write("continue;"),
+            Statement::Return(value) => {
+                self.write("return");
+                if let Some(value) = value {
+                    self.write(" ");
+                    self.expr(value);
+                }
+                self.write(";");
+            }
+            Statement::Throw(value) => {
+                self.write("throw ");
+                self.expr(value);
+                self.write(";");
+            }
+        }
+    }
+    fn if_statement(&mut self, if_stmt: &IfStatement) {
+        let mut if_stmt = if_stmt;
+        self.write("if ");
+
+        loop {
+            self.expr(&if_stmt.condition);
+            self.write(" ");
+            self.block(&if_stmt.met_block);
+
+            let Some(else_block) = &if_stmt.else_block else {
+                return;
+            };
+            match &else_block.data {
+                ElseBlock::If(elif) => {
+                    self.write(" elif ");
+                    if_stmt = elif;
+                }
+                ElseBlock::Block(block) => {
+                    self.write(" else ");
+                    self.block_statements(&block.statements, else_block.end());
+                    return;
+                }
+            }
+        }
+    }
+    fn block(&mut self, block: &ParseNode<Block>) {
+        self.block_statements(&block.data.statements, block.end());
+    }
+    // `end` is the position past the closing '}'
+    fn block_statements(&mut self, statements: &[ParseNode<Statement>], end: usize) {
+        let has_comments = self
+            .comments
+            .get(self.next_comment)
+            .is_some_and(|c| c.start < end);
+
+        if statements.is_empty() && !has_comments {
+            self.write("{}");
+            return;
+        }
+
+        self.write("{");
+        self.newline();
+        self.indent += 1;
+        self.statements(statements, end);
+        self.indent -= 1;
+        self.write("}");
+    }
+    fn func_decl(&mut self, decl: &FuncDecl) {
+        self.write(Keyword::Func.as_str());
+        self.write(" ");
+        self.write(decl.name.data.as_str());
+        self.list("(", &decl.params, ")", |f, param| f.var_decl(&param.data));
+        if let Some(return_type) = &decl.return_type {
+            self.write(": ");
+            self.type_annotation(&return_type.data);
+        }
+
+        match &decl.block.data {
+            FuncBlock::Block(block) => {
+                self.write(" ");
+                self.block_statements(&block.statements, decl.block.end());
+            }
+            FuncBlock::ReturnExpr(expr) => {
+                self.write(" -> ");
+                self.expression(expr, &decl.block.range);
+            }
+        }
+    }
+    fn var_decl(&mut self, decl: &VarDecl) {
+        self.var_name_type(&decl.pattern.data);
+        if let Some(annotation) = &decl.annotation {
+            self.write(": ");
+            self.type_annotation(&annotation.data);
+        }
+        if let Some(value) = &decl.value {
+            self.write(" = ");
+            self.expr(value);
+        }
+    }
+    fn type_annotation(&mut self, annotation: &TypeAnnotation) {
+        match annotation {
+            TypeAnnotation::Name(name) => self.write(name.as_str()),
+            TypeAnnotation::Array(element) => {
+                self.write("[");
+                self.type_annotation(&element.data);
+                self.write("]");
+            }
+            TypeAnnotation::Dictionary(fields) => {
+                self.list("{", fields, "}", |f, (name, annotation)| {
+                    f.write(name.data.as_str());
+                    f.write(": ");
+                    f.type_annotation(&annotation.data);
+                });
+            }
+        }
+    }
+    fn var_name_type(&mut self, pattern: &VarNameType) {
+        let name = |name: &Option<StringName>| name.map_or("_", |n| n.as_str());
+
+        match pattern {
+            VarNameType::Ident(ident) => self.write(name(ident)),
+            VarNameType::Array {
+                start_names,
+                end_names,
+            } => {
+                let mut names: Vec<&str> = start_names.iter().map(|n| name(&n.data)).collect();
+                if !end_names.is_empty() {
+                    names.push(Symbol::Dots.as_str());
+                    names.extend(end_names.iter().map(|n| name(&n.data)));
+                }
+                self.list("[", &names, "]", |f, name| f.write(name));
+            }
+            VarNameType::Object(names) => {
+                self.list("{", names, "}", |f, (field, name)| {
+                    f.write(field.as_str());
+                    if *field != name.data {
+                        f.write(": ");
+                        f.write(name.data.as_str());
+                    }
+                });
+            }
+        }
+    }
+
+    // writes the items separated with commas, breaking them into one item per line (with a
+    // trailing comma) if they don't fit in the line width
+    fn list<T>(&mut self, open: &str, items: &[T], close: &str, item_fn: impl Fn(&mut Self, &T)) {
+        let flat = self.flat(|f| {
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    f.write(", ");
+                }
+                item_fn(f, item);
+            }
+        });
+        let flat_width = open.len() + flat.chars().count() + close.len();
+
+        if self.flat || items.is_empty() || self.line_width() + flat_width <= self.options.max_width
+        {
+            self.write(open);
+            self.write(&flat);
+            self.write(close);
+            return;
+        }
+
+        self.write(open);
+        self.newline();
+        self.indent += 1;
+        for item in items {
+            item_fn(self, item);
+            self.write(",");
+            self.newline();
+        }
+        self.indent -= 1;
+        self.write(close);
+    }
+    fn expr(&mut self, expr: &ParseNode<Expression>) {
+        self.expression(&expr.data, &expr.range);
+    }
+    // literals are written as they appear in the source, to keep raw strings and number formats
+    fn expression(&mut self, expr: &Expression, range: &Range<usize>) {
+        match expr {
+            Expression::None => self.write(Keyword::None.as_str()),
+            Expression::Bool(true) => self.write(Keyword::True.as_str()),
+            Expression::Bool(false) => self.write(Keyword::False.as_str()),
+            Expression::Int(_)
+            | Expression::Real(_)
+            | Expression::String(_)
+            | Expression::Char(_) => self.write(self.source[range.clone()].trim()),
+            Expression::Array(items) => self.list("[", items, "]", |f, item| f.expr(item)),
+            Expression::Dictionary(items) => self.list("{", items, "}", |f, (key, value)| {
+                match &key.data {
+                    DictionaryKey::Ident(name) => f.write(name.as_str()),
+                    DictionaryKey::Expr(expr) => {
+                        f.write("[");
+                        f.expr(expr);
+                        f.write("]");
+                    }
+                }
+                f.write(" = ");
+                f.expr(value);
+            }),
+            Expression::Variable(name) => self.write(name.as_str()),
+            Expression::Binary(binary) => {
+                self.expr(&binary.left);
+                self.write(" ");
+                match binary.operator {
+                    SymbolKeyword::Symbol(s) => self.write(s.as_str()),
+                    SymbolKeyword::Keyword(k) => self.write(k.as_str()),
+                }
+                self.write(" ");
+                self.expr(&binary.right);
+            }
+            Expression::Assign(assign) => {
+                self.expr(&assign.left);
+                self.write(" ");
+                if let Some(operator) = assign.operator {
+                    self.write(operator.as_str());
+                }
+                self.write("= ");
+                self.expr(&assign.right);
+            }
+            Expression::Unary(unary) => {
+                self.write(unary.operator.as_str());
+                self.expr(&unary.operand);
+            }
+            Expression::Suffix(suffix) => {
+                self.expr(&suffix.node);
+                match &suffix.suffix {
+                    SuffixType::Call(args) => self.list("(", args, ")", |f, arg| f.expr(arg)),
+                    SuffixType::Index(index) => {
+                        self.write("[");
+                        self.expr(index);
+                        self.write("]");
+                    }
+                    SuffixType::Property(name) => {
+                        self.write(".");
+                        self.write(name.as_str());
+                    }
+                }
+            }
+            Expression::Grouping(expr) => {
+                self.write("(");
+                self.expr(expr);
+                self.write(")");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format_default(source: &str) -> String {
+        format(source, &FormatOptions::default()).unwrap()
+    }
+    // formats the source, and checks that formatting the result again doesn't change it
+    fn formatted(source: &str) -> String {
+        let output = format_default(source);
+        assert_eq!(
+            format_default(&output),
+            output,
+            "formatting is not idempotent"
+        );
+        output
+    }
+
+    #[test]
+    fn spacing_and_indentation() {
+        assert_eq!(
+            formatted("let   x=1+2*3;\nfunc f( a,b ) {return a+b ;}"),
+            "let x = 1 + 2 * 3;\nfunc f(a, b) {\n    return a + b;\n}\n"
+        );
+        assert_eq!(
+            formatted("if x {} elif y { x = f( 1 , 2 ) ; } else {}"),
+            "if x {} elif y {\n    x = f(1, 2);\n} else {}\n"
+        );
+    }
+
+    #[test]
+    fn keeps_comments_and_blank_lines() {
+        let source = "// leading\nlet x = 1;   // trailing\n\n\n\nif x { /* inside */ }\n";
+        assert_eq!(
+            formatted(source),
+            "// leading\nlet x = 1; // trailing\n\nif x {\n    /* inside */\n}\n"
+        );
+    }
+
+    #[test]
+    fn breaks_long_lists() {
+        let options = FormatOptions {
+            max_width: 20,
+            ..FormatOptions::default()
+        };
+        let output = format("let list = [first, second, third];", &options).unwrap();
+        assert_eq!(
+            output,
+            "let list = [\n    first,\n    second,\n    third,\n];\n"
+        );
+        assert_eq!(format(&output, &options).unwrap(), output);
+        // a list that fits is joined back on one line
+        assert_eq!(formatted(&output), "let list = [first, second, third];\n");
+    }
+
+    #[test]
+    fn is_idempotent() {
+        formatted(
+            r#"
+            let [a, ..., b] = [1, 2, 3];  // pattern
+            let typed: {x: int} = {x = 1, ["y" + "z"] = (a + 1) * 2};
+            func add(x: int, y = 2): int -> x + y
+            while a > 0 { a -= 1; } onbreak { println('b'); } oncontinue { continue; }
+            try { throw "oops"; } catch e { println(e.message); } finally { return; }
+
+            // a comment before the block
+            { ({k = 1}).k = -(9223372036854775808) + (d.f)(x) + (1).abs(); }
+            "#,
+        );
+    }
+
+    #[test]
+    fn syntax_errors_are_returned() {
+        let error = format("let = 1;", &FormatOptions::default()).unwrap_err();
+        assert!(matches!(error.error, ErrorType::ExpectedVarName));
+    }
+}
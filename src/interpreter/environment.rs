@@ -0,0 +1,41 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use super::value::Value;
+use crate::resolver::VarLocation;
+
+// the variables of a scope, in the slots given by the resolver. a slot is `None` until its
+// variable is assigned
+pub(crate) struct Environment {
+    values: RefCell<Vec<Option<Value>>>,
+    parent: Option<Rc<Environment>>,
+}
+impl Environment {
+    pub fn new(parent: Option<Rc<Environment>>) -> Rc<Self> {
+        Rc::new(Self {
+            values: RefCell::new(vec![]),
+            parent,
+        })
+    }
+    fn ancestor(&self, depth: usize) -> &Environment {
+        let mut env = self;
+        for _ in 0..depth {
+            env = env
+                .parent
+                .as_ref()
+                .expect("the resolver found a deeper scope");
+        }
+        env
+    }
+    pub fn get(&self, location: VarLocation) -> Option<Value> {
+        let values = self.ancestor(location.depth).values.borrow();
+        values.get(location.slot).cloned().flatten()
+    }
+    pub fn set(&self, location: VarLocation, value: Value) {
+        let mut values = self.ancestor(location.depth).values.borrow_mut();
+        if values.len() <= location.slot {
+            values.resize(location.slot + 1, None);
+        }
+        values[location.slot] = Some(value);
+    }
+}
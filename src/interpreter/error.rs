@@ -0,0 +1,377 @@
+use std::fmt::{self, Write};
+use std::ops::Range;
+use std::rc::Rc;
+
+use crate::parser::error::{DisplayError, Severity};
+use crate::parser::render::{render_diagnostic, Diagnostic};
+use crate::source_map::{SourceFile, SourceMap, Span};
+use crate::string_name::StringName;
+
+use super::value::ErrorValue;
+
+pub type Result<T> = std::result::Result<T, RuntimeError>;
+
+/// An error raised while running a program, at the range of the expression or statement that
+/// failed.
+#[derive(Debug, Clone)]
+pub struct RuntimeError {
+    pub range: Range<usize>,
+    pub error: RuntimeErrorType,
+    /// The functions that were running, the one that raised the error first. It's filled in by
+    /// the interpreter before the error is returned, so the first frame is where `range` is.
+    pub trace: Vec<Frame>,
+}
+impl RuntimeError {
+    pub fn new(range: Range<usize>, error: RuntimeErrorType) -> Self {
+        Self {
+            range,
+            error,
+            trace: vec![],
+        }
+    }
+    /// Writes the trace, one frame per line as `at name (file:line:column)`. Repeated frames,
+    /// like the ones of a stack overflow, are written once.
+    pub fn display_trace(&self, sources: &SourceMap, message: &mut impl Write) -> fmt::Result {
+        writeln!(message, "stack trace:")?;
+        let mut frames = self.trace.iter().peekable();
+        while let Some(frame) = frames.next() {
+            let (file, line, column) = sources.location(&frame.span);
+            writeln!(message, "    at {} ({}:{}:{})", frame, file, line, column)?;
+            let mut repeated = 0;
+            while frames.next_if_eq(&frame).is_some() {
+                repeated += 1;
+            }
+            if repeated > 0 {
+                writeln!(message, "    ... repeated {} more times", repeated)?;
+            }
+        }
+        Ok(())
+    }
+}
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.error)
+    }
+}
+impl std::error::Error for RuntimeError {}
+// the file is the one of the first frame
+impl DisplayError for RuntimeError {
+    fn display_styled(
+        &self,
+        file: &SourceFile,
+        color: bool,
+        message: &mut impl Write,
+    ) -> fmt::Result {
+        let diagnostic = Diagnostic {
+            severity: Severity::Error,
+            code: self.error.code(),
+            message: self.error.to_string(),
+            range: &self.range,
+            labels: &[],
+            notes: &[],
+            suggestions: &[],
+        };
+        render_diagnostic(&diagnostic, file, color, message)
+    }
+}
+
+/// A function that was running, and the range in it that was running: a call to the next frame,
+/// or what raised the error. The top level of a program has no function.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub function: Option<StringName>,
+    pub span: Span,
+}
+impl fmt::Display for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.function {
+            Some(name) => write!(f, "{}", name),
+            None => write!(f, "<top level>"),
+        }
+    }
+}
+
+// types are the names given by `Value::type_name`
+#[derive(Debug, Clone)]
+pub enum RuntimeErrorType {
+    UndefinedVariable(StringName),
+    UninitializedVariable(StringName),
+    InvalidOperands(&'static str, &'static str, &'static str),
+    InvalidOperand(&'static str, &'static str),
+    IntOverflow,
+    DivisionByZero,
+    ShiftOutOfRange(i64),
+    NotCallable(&'static str),
+    ArgumentCount {
+        min: usize,
+        // `None` when there's no limit
+        max: Option<usize>,
+        found: usize,
+    },
+    ArgumentType {
+        function: StringName,
+        index: usize,
+        expected: &'static str,
+        found: &'static str,
+    },
+    InvalidConversion(String, &'static str),
+    InvalidArgument(String),
+    NotIndexable(&'static str),
+    InvalidIndex(&'static str, &'static str),
+    IndexOutOfRange(i64, usize),
+    UnhashableKey(&'static str),
+    MissingKey(String),
+    NoProperty(&'static str, StringName),
+    NoMethod(&'static str, StringName),
+    CannotUnpack(&'static str),
+    NotEnoughElements(usize, usize),
+    StackOverflow,
+    // a value thrown with `throw`, or an error that was caught and thrown again
+    Thrown(Rc<ErrorValue>),
+}
+impl RuntimeErrorType {
+    /// Stable code of the error, shown next to its message and used by `explain`. Like the codes
+    /// of `ErrorType`, they are never reused or renumbered.
+    pub fn code(&self) -> &'static str {
+        match self {
+            RuntimeErrorType::UndefinedVariable(_) => "R0001",
+            RuntimeErrorType::UninitializedVariable(_) => "R0002",
+            RuntimeErrorType::InvalidOperands(..) => "R0003",
+            RuntimeErrorType::InvalidOperand(..) => "R0004",
+            RuntimeErrorType::IntOverflow => "R0005",
+            RuntimeErrorType::DivisionByZero => "R0006",
+            RuntimeErrorType::ShiftOutOfRange(_) => "R0007",
+            RuntimeErrorType::NotCallable(_) => "R0008",
+            RuntimeErrorType::ArgumentCount { .. } => "R0009",
+            RuntimeErrorType::ArgumentType { .. } => "R0010",
+            RuntimeErrorType::InvalidConversion(..) => "R0011",
+            RuntimeErrorType::InvalidArgument(_) => "R0012",
+            RuntimeErrorType::NotIndexable(_) => "R0013",
+            RuntimeErrorType::InvalidIndex(..) => "R0014",
+            RuntimeErrorType::IndexOutOfRange(..) => "R0015",
+            RuntimeErrorType::UnhashableKey(_) => "R0016",
+            RuntimeErrorType::MissingKey(_) => "R0017",
+            RuntimeErrorType::NoProperty(..) => "R0018",
+            RuntimeErrorType::NoMethod(..) => "R0019",
+            RuntimeErrorType::CannotUnpack(_) => "R0020",
+            RuntimeErrorType::NotEnoughElements(..) => "R0021",
+            RuntimeErrorType::StackOverflow => "R0022",
+            RuntimeErrorType::Thrown(_) => "R0023",
+        }
+    }
+    /// The name of the error, which is the `kind` of the error value a `catch` block gets.
+    pub fn kind(&self) -> &str {
+        match self {
+            RuntimeErrorType::UndefinedVariable(_) => "UndefinedVariable",
+            RuntimeErrorType::UninitializedVariable(_) => "UninitializedVariable",
+            RuntimeErrorType::InvalidOperands(..) => "InvalidOperands",
+            RuntimeErrorType::InvalidOperand(..) => "InvalidOperand",
+            RuntimeErrorType::IntOverflow => "IntOverflow",
+            RuntimeErrorType::DivisionByZero => "DivisionByZero",
+            RuntimeErrorType::ShiftOutOfRange(_) => "ShiftOutOfRange",
+            RuntimeErrorType::NotCallable(_) => "NotCallable",
+            RuntimeErrorType::ArgumentCount { .. } => "ArgumentCount",
+            RuntimeErrorType::ArgumentType { .. } => "ArgumentType",
+            RuntimeErrorType::InvalidConversion(..) => "InvalidConversion",
+            RuntimeErrorType::InvalidArgument(_) => "InvalidArgument",
+            RuntimeErrorType::NotIndexable(_) => "NotIndexable",
+            RuntimeErrorType::InvalidIndex(..) => "InvalidIndex",
+            RuntimeErrorType::IndexOutOfRange(..) => "IndexOutOfRange",
+            RuntimeErrorType::UnhashableKey(_) => "UnhashableKey",
+            RuntimeErrorType::MissingKey(_) => "MissingKey",
+            RuntimeErrorType::NoProperty(..) => "NoProperty",
+            RuntimeErrorType::NoMethod(..) => "NoMethod",
+            RuntimeErrorType::CannotUnpack(_) => "CannotUnpack",
+            RuntimeErrorType::NotEnoughElements(..) => "NotEnoughElements",
+            RuntimeErrorType::StackOverflow => "StackOverflow",
+            RuntimeErrorType::Thrown(error) => &error.kind,
+        }
+    }
+}
+impl fmt::Display for RuntimeErrorType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuntimeErrorType::UndefinedVariable(name) => {
+                write!(f, "Variable '{}' is not defined", name)
+            }
+            RuntimeErrorType::UninitializedVariable(name) => {
+                write!(f, "Variable '{}' is read before it is assigned", name)
+            }
+            RuntimeErrorType::InvalidOperands(operator, left, right) => write!(
+                f,
+                "Operator '{}' can't be applied to {} and {}",
+                operator, left, right
+            ),
+            RuntimeErrorType::InvalidOperand(operator, operand) => {
+                write!(f, "Operator '{}' can't be applied to {}", operator, operand)
+            }
+            RuntimeErrorType::IntOverflow => write!(f, "Integer overflow"),
+            RuntimeErrorType::DivisionByZero => write!(f, "Division by zero"),
+            RuntimeErrorType::ShiftOutOfRange(amount) => {
+                write!(f, "Shift amount {} is out of the range 0 to 63", amount)
+            }
+            RuntimeErrorType::NotCallable(kind) => {
+                write!(f, "Value of type {} is not callable", kind)
+            }
+            RuntimeErrorType::ArgumentCount {
+                min,
+                max: Some(max),
+                found,
+            } if min == max => write!(f, "Expected {} arguments, found {}", max, found),
+            RuntimeErrorType::ArgumentCount {
+                min,
+                max: Some(max),
+                found,
+            } => write!(f, "Expected {} to {} arguments, found {}", min, max, found),
+            RuntimeErrorType::ArgumentCount {
+                min,
+                max: None,
+                found,
+            } => write!(f, "Expected at least {} arguments, found {}", min, found),
+            RuntimeErrorType::ArgumentType {
+                function,
+                index,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Argument {} of '{}' must be {}, found {}",
+                index + 1,
+                function,
+                expected,
+                found
+            ),
+            RuntimeErrorType::InvalidConversion(value, kind) => {
+                write!(f, "Can't convert {} to {}", value, kind)
+            }
+            RuntimeErrorType::InvalidArgument(message) => write!(f, "{}", message),
+            RuntimeErrorType::NotIndexable(kind) => {
+                write!(f, "Value of type {} can't be indexed", kind)
+            }
+            RuntimeErrorType::InvalidIndex(kind, index) => {
+                write!(f, "Value of type {} can't be indexed with {}", kind, index)
+            }
+            RuntimeErrorType::IndexOutOfRange(index, len) => {
+                write!(f, "Index {} is out of range for length {}", index, len)
+            }
+            RuntimeErrorType::UnhashableKey(kind) => {
+                write!(f, "Value of type {} can't be a dictionary key", kind)
+            }
+            RuntimeErrorType::MissingKey(key) => write!(f, "Key {} is not in the dictionary", key),
+            RuntimeErrorType::NoProperty(kind, name) => {
+                write!(f, "Value of type {} has no property '{}'", kind, name)
+            }
+            RuntimeErrorType::NoMethod(kind, name) => {
+                write!(f, "Value of type {} has no method '{}'", kind, name)
+            }
+            RuntimeErrorType::CannotUnpack(kind) => {
+                write!(f, "Value of type {} can't be unpacked", kind)
+            }
+            RuntimeErrorType::NotEnoughElements(expected, found) => write!(
+                f,
+                "Expected at least {} elements to unpack, found {}",
+                expected, found
+            ),
+            RuntimeErrorType::StackOverflow => write!(f, "Stack overflow"),
+            RuntimeErrorType::Thrown(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::Interpreter;
+    use crate::parser::Parser;
+
+    // the error as the CLI prints it, followed by its trace
+    fn rendered(files: &[(&str, &str)]) -> String {
+        let mut sources = SourceMap::new();
+        let mut interpreter = Interpreter::new();
+        for &(name, source) in files {
+            let file = sources.add(name, source);
+            let statements = Parser::new(source).parse().unwrap();
+            if let Err(error) = interpreter.run(&statements, &sources, file) {
+                let mut message = String::new();
+                let file = sources.get(error.trace[0].span.file);
+                error.display_styled(file, false, &mut message).unwrap();
+                error.display_trace(&sources, &mut message).unwrap();
+                return message;
+            }
+        }
+        panic!("the program didn't fail");
+    }
+
+    #[test]
+    fn rendered_stack_trace() {
+        let source = "\
+func inner(x) {
+    return x / 0;
+}
+func outer() -> inner(1)
+outer();
+";
+        assert_eq!(
+            rendered(&[("main.lx", source)]),
+            "\
+Error at line: 2, column: 12
+[R0006] Division by zero
+   1 | func inner(x) {
+   2 |     return x / 0;
+     |            ^^^^^
+   3 | }
+stack trace:
+    at inner (main.lx:2:12)
+    at outer (main.lx:4:17)
+    at <top level> (main.lx:5:1)
+"
+        );
+    }
+
+    #[test]
+    fn trace_across_files() {
+        let library = "func first(items) -> items[0]\n";
+        let main = "let empty = [];\nprintln(first(empty));\n";
+        assert_eq!(
+            rendered(&[("library.lx", library), ("main.lx", main)]),
+            "\
+Error at line: 1, column: 22
+[R0015] Index 0 is out of range for length 0
+   1 | func first(items) -> items[0]
+     |                      ^^^^^^^^
+stack trace:
+    at first (library.lx:1:22)
+    at <top level> (main.lx:2:9)
+"
+        );
+    }
+
+    #[test]
+    fn repeated_frames_are_written_once() {
+        let source = "\
+func countdown(n) {
+    if n == 0 {
+        return 1 / n;
+    }
+    return countdown(n - 1);
+}
+countdown(3);
+";
+        assert_eq!(
+            rendered(&[("main.lx", source)]),
+            "\
+Error at line: 3, column: 16
+[R0006] Division by zero
+   2 |     if n == 0 {
+   3 |         return 1 / n;
+     |                ^^^^^
+   4 |     }
+stack trace:
+    at countdown (main.lx:3:16)
+    at countdown (main.lx:5:12)
+    ... repeated 2 more times
+    at <top level> (main.lx:7:1)
+"
+        );
+    }
+}
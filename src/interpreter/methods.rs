@@ -0,0 +1,527 @@
+// methods of the built in types, called as `value.name(args)`. strings are indexed by chars
+// (unicode scalar values), not by bytes or graphemes, and so are the positions given and taken
+// by methods. only the `len` method counts graphemes, the characters as they are seen, like
+// `graphemes` splits them: "e\u{301}".len() is 1, while `len("e\u{301}")`,
+// "e\u{301}".chars().len() and indexing count 2 chars
+
+use std::cmp::Ordering;
+use std::ops::Range;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::error::*;
+use super::native::{Args, NativeResult};
+use super::operator;
+use super::value::*;
+use super::Interpreter;
+use crate::string_name::StringName;
+
+// longer strings made by methods are an error, instead of running out of memory
+const MAX_STRING_LEN: usize = 1 << 30;
+
+impl Interpreter {
+    // a dictionary entry named like the method is called instead, so dictionaries can hold
+    // functions
+    pub(super) fn method(
+        &mut self,
+        receiver: Value,
+        name: StringName,
+        values: Vec<Value>,
+        range: &Range<usize>,
+    ) -> Result<Value> {
+        if let Value::Dictionary(items) = &receiver {
+            let entry = items.borrow().get(&Key::from(name)).cloned();
+            if let Some(callee) = entry {
+                return self.call(callee, values, range);
+            }
+        }
+        let args = Args {
+            name,
+            values: &values,
+        };
+        let result = match &receiver {
+            Value::String(s) => string_method(s, args).map_err(MethodError::from),
+            Value::Char(c) => char_method(*c, args).map_err(MethodError::from),
+            Value::Array(items) => self.array_method(items, args, range),
+            Value::Dictionary(items) => dictionary_method(items, args).map_err(MethodError::from),
+            _ => Err(RuntimeErrorType::NoMethod(receiver.type_name(), name).into()),
+        };
+        result.map_err(|error| match error {
+            MethodError::Method(error) => RuntimeError::new(range.clone(), error),
+            MethodError::Callback(error) => error,
+        })
+    }
+
+    // the callbacks get the items of the array as it was when the method was called
+    fn array_method(&mut self, items: &Array, args: Args, range: &Range<usize>) -> MethodResult {
+        let snapshot = || items.borrow().clone();
+        Ok(match args.name.as_str() {
+            "len" => {
+                args.arity(0, 0)?;
+                Value::Int(items.borrow().len() as i64)
+            }
+            "push" => {
+                args.arity(1, usize::MAX)?;
+                items.borrow_mut().extend(args.values().iter().cloned());
+                Value::None
+            }
+            "pop" => {
+                args.arity(0, 0)?;
+                let Some(item) = items.borrow_mut().pop() else {
+                    return Err(RuntimeErrorType::InvalidArgument(
+                        "Can't pop from an empty array".to_string(),
+                    )
+                    .into());
+                };
+                item
+            }
+            // at an index up to the length, the item at the index and the ones after it move
+            "insert" => {
+                args.arity(2, 2)?;
+                let index = args.int(0)?;
+                let mut items = items.borrow_mut();
+                match usize::try_from(index) {
+                    Ok(i) if i <= items.len() => items.insert(i, args.get(1).clone()),
+                    _ => return Err(RuntimeErrorType::IndexOutOfRange(index, items.len()).into()),
+                }
+                Value::None
+            }
+            "remove" => {
+                args.arity(1, 1)?;
+                let index = args.int(0)?;
+                let mut items = items.borrow_mut();
+                match usize::try_from(index) {
+                    Ok(i) if i < items.len() => items.remove(i),
+                    _ => return Err(RuntimeErrorType::IndexOutOfRange(index, items.len()).into()),
+                }
+            }
+            // in place and stable. the comparator returns a negative number when its first
+            // argument goes first, a positive one when it goes last and 0 when they are equal
+            "sort" => {
+                args.arity(0, 1)?;
+                let sorted = match args.values().first() {
+                    None => merge_sort(snapshot(), &mut |a, b| {
+                        operator::compare(a, b).ok_or_else(|| {
+                            RuntimeErrorType::InvalidOperands("<", a.type_name(), b.type_name())
+                                .into()
+                        })
+                    })?,
+                    Some(comparator) => merge_sort(snapshot(), &mut |a, b| {
+                        let order =
+                            self.call(comparator.clone(), vec![a.clone(), b.clone()], range)?;
+                        let order = match order {
+                            Value::Int(i) => i.cmp(&0),
+                            Value::Real(r) if !r.is_nan() => r.total_cmp(&0.0),
+                            _ => {
+                                return Err(RuntimeErrorType::InvalidArgument(format!(
+                                    "The comparator of 'sort' must return a number, found {}",
+                                    order.type_name()
+                                ))
+                                .into())
+                            }
+                        };
+                        Ok(order)
+                    })?,
+                };
+                *items.borrow_mut() = sorted;
+                Value::None
+            }
+            "reverse" => {
+                args.arity(0, 0)?;
+                items.borrow_mut().reverse();
+                Value::None
+            }
+            "map" => {
+                args.arity(1, 1)?;
+                let mut mapped = vec![];
+                for item in snapshot() {
+                    mapped.push(self.call(args.get(0).clone(), vec![item], range)?);
+                }
+                Value::array(mapped)
+            }
+            "filter" => {
+                args.arity(1, 1)?;
+                let mut kept = vec![];
+                for item in snapshot() {
+                    if self
+                        .call(args.get(0).clone(), vec![item.clone()], range)?
+                        .is_truthy()
+                    {
+                        kept.push(item);
+                    }
+                }
+                Value::array(kept)
+            }
+            // `f(f(initial, a), b)`, or `f(a, b)` without an initial value
+            "reduce" => {
+                args.arity(1, 2)?;
+                let mut items = snapshot().into_iter();
+                let initial = args.values().get(1).cloned().or_else(|| items.next());
+                let Some(mut result) = initial else {
+                    return Err(RuntimeErrorType::InvalidArgument(
+                        "Can't reduce an empty array without an initial value".to_string(),
+                    )
+                    .into());
+                };
+                for item in items {
+                    result = self.call(args.get(0).clone(), vec![result, item], range)?;
+                }
+                result
+            }
+            // the first item the function is true for, `none` without one
+            "find" => {
+                args.arity(1, 1)?;
+                for item in snapshot() {
+                    if self
+                        .call(args.get(0).clone(), vec![item.clone()], range)?
+                        .is_truthy()
+                    {
+                        return Ok(item);
+                    }
+                }
+                Value::None
+            }
+            "contains" => {
+                args.arity(1, 1)?;
+                Value::Bool(items.borrow().contains(args.get(0)))
+            }
+            // pairs of items at the same index, as long as the shortest array
+            "zip" => {
+                args.arity(1, 1)?;
+                let Value::Array(other) = args.get(0) else {
+                    return Err(args.invalid(0, "an array").into());
+                };
+                let pairs = snapshot()
+                    .into_iter()
+                    .zip(other.borrow().clone())
+                    .map(|(a, b)| Value::array(vec![a, b]));
+                Value::array(pairs.collect())
+            }
+            // pairs of the index and the item
+            "enumerate" => {
+                args.arity(0, 0)?;
+                let pairs = snapshot()
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, item)| Value::array(vec![Value::Int(i as i64), item]));
+                Value::array(pairs.collect())
+            }
+            _ => return Err(RuntimeErrorType::NoMethod("array", args.name).into()),
+        })
+    }
+}
+
+// an error of a method, or one raised by a function it called, which has its own range
+enum MethodError {
+    Method(RuntimeErrorType),
+    Callback(RuntimeError),
+}
+impl From<RuntimeErrorType> for MethodError {
+    fn from(error: RuntimeErrorType) -> Self {
+        MethodError::Method(error)
+    }
+}
+impl From<RuntimeError> for MethodError {
+    fn from(error: RuntimeError) -> Self {
+        MethodError::Callback(error)
+    }
+}
+type MethodResult = std::result::Result<Value, MethodError>;
+
+// a comparator that doesn't give a consistent order only gives an unexpected order, unlike with
+// `sort_by`, and its errors stop the sort
+fn merge_sort(
+    items: Vec<Value>,
+    compare: &mut dyn FnMut(&Value, &Value) -> std::result::Result<Ordering, MethodError>,
+) -> std::result::Result<Vec<Value>, MethodError> {
+    if items.len() <= 1 {
+        return Ok(items);
+    }
+    let mut left = items;
+    let right = left.split_off(left.len() / 2);
+    let left = merge_sort(left, compare)?;
+    let right = merge_sort(right, compare)?;
+
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+    let mut left = left.into_iter().peekable();
+    let mut right = right.into_iter().peekable();
+    while let (Some(a), Some(b)) = (left.peek(), right.peek()) {
+        // equal items keep their order
+        if compare(a, b)? == Ordering::Greater {
+            merged.extend(right.next());
+        } else {
+            merged.extend(left.next());
+        }
+    }
+    merged.extend(left);
+    merged.extend(right);
+    Ok(merged)
+}
+
+fn dictionary_method(items: &Dictionary, args: Args) -> NativeResult {
+    let key =
+        |index| Key::try_from(args.get(index).clone()).map_err(RuntimeErrorType::UnhashableKey);
+    Ok(match args.name.as_str() {
+        "len" => {
+            args.arity(0, 0)?;
+            Value::Int(items.borrow().len() as i64)
+        }
+        "keys" => {
+            args.arity(0, 0)?;
+            Value::array(items.borrow().keys().cloned().map(Value::from).collect())
+        }
+        "values" => {
+            args.arity(0, 0)?;
+            Value::array(items.borrow().values().cloned().collect())
+        }
+        // pairs of the key and the value
+        "items" => {
+            args.arity(0, 0)?;
+            let items = items.borrow();
+            let pairs = items
+                .iter()
+                .map(|(key, value)| Value::array(vec![key.clone().into(), value.clone()]));
+            Value::array(pairs.collect())
+        }
+        // the value of the key, or the default (`none` without one) if it's not there
+        "get" => {
+            args.arity(1, 2)?;
+            let value = items.borrow().get(&key(0)?).cloned();
+            value.unwrap_or_else(|| args.values().get(1).cloned().unwrap_or(Value::None))
+        }
+        "has" => {
+            args.arity(1, 1)?;
+            Value::Bool(items.borrow().contains_key(&key(0)?))
+        }
+        // removes the key and returns its value, the keys after it keep their order
+        "remove" => {
+            args.arity(1, 1)?;
+            let key = key(0)?;
+            let value = items.borrow_mut().shift_remove(&key);
+            value.ok_or_else(|| RuntimeErrorType::MissingKey(format!("{:?}", Value::from(key))))?
+        }
+        // a new dictionary with the entries of both, the other one's values win and its new keys
+        // go last
+        "merge" => {
+            args.arity(1, 1)?;
+            let Value::Dictionary(other) = args.get(0) else {
+                return Err(args.invalid(0, "a dictionary"));
+            };
+            let mut merged = items.borrow().clone();
+            merged.extend(other.borrow().iter().map(|(k, v)| (k.clone(), v.clone())));
+            Value::dictionary(merged)
+        }
+        _ => return Err(RuntimeErrorType::NoMethod("dictionary", args.name)),
+    })
+}
+
+fn string_method(s: &str, args: Args) -> NativeResult {
+    let string = |s: &str| Value::String(s.into());
+    Ok(match args.name.as_str() {
+        // graphemes, not chars, see the top of the file
+        "len" => {
+            args.arity(0, 0)?;
+            Value::Int(s.graphemes(true).count() as i64)
+        }
+        // on whitespace without a separator
+        "split" => {
+            args.arity(0, 1)?;
+            let parts: Vec<_> = if args.is_empty() {
+                s.split_whitespace().map(string).collect()
+            } else {
+                let separator = pattern(&args, 0)?;
+                if separator.is_empty() {
+                    return Err(RuntimeErrorType::InvalidArgument(
+                        "The separator of 'split' is empty".to_string(),
+                    ));
+                }
+                s.split(separator.as_str()).map(string).collect()
+            };
+            Value::array(parts)
+        }
+        // the string is the separator, `", ".join(items)`
+        "join" => {
+            args.arity(1, 1)?;
+            let Value::Array(items) = args.get(0) else {
+                return Err(args.invalid(0, "an array"));
+            };
+            let items: Vec<_> = items.borrow().iter().map(Value::to_string).collect();
+            string(&items.join(s))
+        }
+        "trim" => {
+            args.arity(0, 0)?;
+            string(s.trim())
+        }
+        "trim_start" => {
+            args.arity(0, 0)?;
+            string(s.trim_start())
+        }
+        "trim_end" => {
+            args.arity(0, 0)?;
+            string(s.trim_end())
+        }
+        "replace" => {
+            args.arity(2, 2)?;
+            string(&s.replace(pattern(&args, 0)?.as_str(), &pattern(&args, 1)?))
+        }
+        // the index of the first char of the first match, `none` without one
+        "find" => {
+            args.arity(1, 1)?;
+            match s.find(pattern(&args, 0)?.as_str()) {
+                Some(byte) => Value::Int(s[..byte].chars().count() as i64),
+                None => Value::None,
+            }
+        }
+        "contains" => {
+            args.arity(1, 1)?;
+            Value::Bool(s.contains(pattern(&args, 0)?.as_str()))
+        }
+        "starts_with" => {
+            args.arity(1, 1)?;
+            Value::Bool(s.starts_with(pattern(&args, 0)?.as_str()))
+        }
+        "ends_with" => {
+            args.arity(1, 1)?;
+            Value::Bool(s.ends_with(pattern(&args, 0)?.as_str()))
+        }
+        "to_upper" => {
+            args.arity(0, 0)?;
+            string(&s.to_uppercase())
+        }
+        "to_lower" => {
+            args.arity(0, 0)?;
+            string(&s.to_lowercase())
+        }
+        "chars" => {
+            args.arity(0, 0)?;
+            Value::array(s.chars().map(Value::Char).collect())
+        }
+        // the characters as they are seen, as strings since they can be made of several chars
+        "graphemes" => {
+            args.arity(0, 0)?;
+            Value::array(s.graphemes(true).map(string).collect())
+        }
+        // the utf-8 encoding
+        "bytes" => {
+            args.arity(0, 0)?;
+            Value::array(s.bytes().map(|b| Value::Int(b as i64)).collect())
+        }
+        "repeat" => {
+            args.arity(1, 1)?;
+            let count = args.int(0)?;
+            let len = usize::try_from(count)
+                .ok()
+                .and_then(|count| s.len().checked_mul(count));
+            if len.is_none_or(|len| len > MAX_STRING_LEN) {
+                return Err(RuntimeErrorType::InvalidArgument(format!(
+                    "Can't repeat a string {} times",
+                    count
+                )));
+            }
+            string(&s.repeat(count as usize))
+        }
+        // pads up to a number of chars, with spaces or the given char
+        "pad_left" | "pad_right" => {
+            args.arity(1, 2)?;
+            let width = args.int(0)?;
+            let fill = if args.len() > 1 { args.char(1)? } else { ' ' };
+            let missing = usize::try_from(width)
+                .unwrap_or(0)
+                .saturating_sub(s.chars().count());
+            let len = fill
+                .len_utf8()
+                .checked_mul(missing)
+                .and_then(|padding| padding.checked_add(s.len()));
+            if len.is_none_or(|len| len > MAX_STRING_LEN) {
+                return Err(RuntimeErrorType::InvalidArgument(format!(
+                    "Can't pad a string to {} chars",
+                    width
+                )));
+            }
+            let padding: String = std::iter::repeat_n(fill, missing).collect();
+            if args.name.as_str() == "pad_left" {
+                string(&(padding + s))
+            } else {
+                string(&(s.to_string() + &padding))
+            }
+        }
+        _ => return Err(RuntimeErrorType::NoMethod("string", args.name)),
+    })
+}
+
+fn char_method(c: char, args: Args) -> NativeResult {
+    let value = match args.name.as_str() {
+        "is_digit" => Value::Bool(c.is_ascii_digit()),
+        "is_alpha" => Value::Bool(c.is_alphabetic()),
+        "is_alphanumeric" => Value::Bool(c.is_alphanumeric()),
+        "is_whitespace" => Value::Bool(c.is_whitespace()),
+        "is_upper" => Value::Bool(c.is_uppercase()),
+        "is_lower" => Value::Bool(c.is_lowercase()),
+        // a string when the case has more chars, like 'ß'.to_upper() is "SS"
+        "to_upper" => one_char(c.to_uppercase().collect()),
+        "to_lower" => one_char(c.to_lowercase().collect()),
+        _ => return Err(RuntimeErrorType::NoMethod("char", args.name)),
+    };
+    args.arity(0, 0)?;
+    Ok(value)
+}
+
+fn one_char(s: String) -> Value {
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Value::Char(c),
+        _ => Value::String(s.into()),
+    }
+}
+
+// a string or char argument, to search for
+fn pattern(args: &Args, index: usize) -> std::result::Result<String, RuntimeErrorType> {
+    match args.get(index) {
+        Value::String(s) => Ok(s.to_string()),
+        Value::Char(c) => Ok(c.to_string()),
+        _ => Err(args.invalid(index, "a string or char")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::eval;
+    use super::*;
+
+    fn invalid_argument(source: &str) -> bool {
+        matches!(
+            eval(source).unwrap_err().error,
+            RuntimeErrorType::InvalidArgument(_)
+        )
+    }
+
+    #[test]
+    fn repeat_and_pad() {
+        assert_eq!(eval(r#""ab".repeat(3);"#).unwrap().to_string(), "ababab");
+        assert_eq!(eval(r#""7".pad_left(3, '0');"#).unwrap().to_string(), "007");
+        assert_eq!(eval(r#""ab".pad_right(1);"#).unwrap().to_string(), "ab");
+    }
+
+    #[test]
+    fn len_counts_graphemes_and_indexing_counts_chars() {
+        let source =
+            r#"let s = "e\u{301}"; [s.len(), s.graphemes().len(), len(s), s.chars().len()];"#;
+        assert_eq!(eval(source).unwrap().to_string(), "[1, 1, 2, 2]");
+        // the combining mark is a char of its own
+        let source = r#"let s = "e\u{301}x"; [s[1], s[2], s.find("x")];"#;
+        assert_eq!(eval(source).unwrap().to_string(), r"['\u{301}', 'x', 2]");
+        // flags and emoji with modifiers are one grapheme of several chars
+        let source = r#"let s = "🇫🇷👋🏽"; [s.len(), len(s)];"#;
+        assert_eq!(eval(source).unwrap().to_string(), "[2, 4]");
+        assert_eq!(eval(r#""".len();"#).unwrap().to_string(), "0");
+    }
+
+    #[test]
+    fn too_long_strings_are_invalid_arguments() {
+        assert!(invalid_argument(r#""ab".repeat(-1);"#));
+        assert!(invalid_argument(r#""ab".repeat(4611686018427387904);"#));
+        assert!(invalid_argument(r#""ab".repeat(9223372036854775807);"#));
+        assert!(invalid_argument(r#""ab".pad_left(9223372036854775807);"#));
+        assert!(invalid_argument(r#""ab".pad_right(2147483648, 'é');"#));
+    }
+}
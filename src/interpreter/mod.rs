@@ -0,0 +1,845 @@
+// tree walking interpreter, running the syntax tree after it passed `check`
+
+mod environment;
+pub mod error;
+mod methods;
+pub mod native;
+pub(crate) mod operator;
+pub mod value;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ops::{Bound, Range, RangeBounds};
+use std::rc::Rc;
+
+use indexmap::IndexMap;
+
+use crate::parser::parse_node::*;
+use crate::resolver::{resolve, VarLocation};
+use crate::source_map::{FileId, SourceFile, SourceMap, Span};
+use crate::string_name::StringName;
+use environment::Environment;
+use error::*;
+use native::*;
+use value::*;
+
+// deeper calls are a stack overflow error, instead of overflowing the stack of the interpreter
+const MAX_CALL_DEPTH: usize = 512;
+
+// a program that was run, kept alive by the functions declared in it
+pub(crate) struct Program {
+    file: FileId,
+    locations: HashMap<Range<usize>, VarLocation>,
+    // declarations shared by every function value created from them, keyed by their range
+    functions: RefCell<HashMap<Range<usize>, Rc<FuncDecl>>>,
+}
+
+// how a statement finished
+enum Flow {
+    Normal,
+    Break,
+    Continue,
+    Return(Value),
+}
+
+/// Runs programs. The globals defined with `define` are visible to every program run by it, and
+/// so are the top level declarations of the programs it ran before, like in a REPL.
+pub struct Interpreter {
+    globals: Rc<Environment>,
+    global_names: Vec<StringName>,
+    env: Rc<Environment>,
+    program: Rc<Program>,
+    // the calls of the running functions, the outermost first
+    stack: Vec<Frame>,
+    // the running function, `None` at the top level
+    function: Option<StringName>,
+    // the files of the programs that were run, for the traces of caught errors
+    files: HashMap<FileId, Rc<SourceFile>>,
+}
+impl Interpreter {
+    /// An interpreter with the functions of the standard library defined.
+    pub fn new() -> Self {
+        let globals = Environment::new(None);
+        let mut interpreter = Self {
+            env: globals.clone(),
+            globals,
+            global_names: vec![],
+            program: Rc::new(Program {
+                file: FileId::default(),
+                locations: HashMap::new(),
+                functions: RefCell::default(),
+            }),
+            stack: vec![],
+            function: None,
+            files: HashMap::new(),
+        };
+        define_stdlib(&mut interpreter);
+        interpreter
+    }
+    /// Defines a global variable, such as the arguments of the script.
+    pub fn define(&mut self, name: impl Into<StringName>, value: Value) {
+        let slot = self.global_names.len();
+        self.global_names.push(name.into());
+        self.globals.set(VarLocation { depth: 0, slot }, value);
+    }
+    /// Defines a global function written in rust, taking a number of arguments in `params`.
+    /// The standard library is defined with it too.
+    pub fn define_native(
+        &mut self,
+        name: &str,
+        params: impl RangeBounds<usize>,
+        function: impl Fn(Args) -> NativeResult + 'static,
+    ) {
+        let min = match params.start_bound() {
+            Bound::Included(&min) => min,
+            Bound::Excluded(&min) => min + 1,
+            Bound::Unbounded => 0,
+        };
+        let max = match params.end_bound() {
+            Bound::Included(&max) => Some(max),
+            Bound::Excluded(&max) => Some(max - 1),
+            Bound::Unbounded => None,
+        };
+        let native = NativeFunction {
+            name: name.into(),
+            min,
+            max,
+            function: Box::new(function),
+        };
+        self.define(name, Value::Native(Rc::new(native)));
+    }
+    /// Runs the statements of `file`, one of the `sources`. They should have passed `check`
+    /// without errors. Returns the value of the last statement if it's an expression, `none`
+    /// otherwise.
+    pub fn run(
+        &mut self,
+        statements: &[ParseNode<Statement>],
+        sources: &SourceMap,
+        file: FileId,
+    ) -> Result<Value> {
+        self.files.insert(file, sources.shared(file));
+        let resolution = resolve(statements, self.global_names.iter().copied());
+        self.global_names = resolution.globals;
+        self.program = Rc::new(Program {
+            file,
+            locations: resolution.locations,
+            functions: RefCell::default(),
+        });
+        self.env = self.globals.clone();
+        self.stack.clear();
+        self.function = None;
+
+        self.program(statements).map_err(|mut err| {
+            self.trace(&mut err);
+            err
+        })
+    }
+    /// Names of the globals, in the order they were defined.
+    pub fn globals(&self) -> &[StringName] {
+        &self.global_names
+    }
+    /// The file of the program running now.
+    pub fn file(&self) -> FileId {
+        self.program.file
+    }
+
+    fn program(&mut self, statements: &[ParseNode<Statement>]) -> Result<Value> {
+        self.hoist(statements);
+        let Some((last, statements)) = statements.split_last() else {
+            return Ok(Value::None);
+        };
+        // `check` makes sure there are no jumps out of the top level
+        for stmt in statements {
+            self.statement(&stmt.data, &stmt.range)?;
+        }
+        if let Statement::Expression(expr) = &last.data {
+            return self.expression(expr, &last.range);
+        }
+        self.statement(&last.data, &last.range)?;
+        Ok(Value::None)
+    }
+    // fills in the trace of an error raised in the running function, before the stack unwinds
+    fn trace(&self, err: &mut RuntimeError) {
+        if !err.trace.is_empty() {
+            return;
+        }
+        err.trace.push(Frame {
+            function: self.function,
+            span: Span::new(self.program.file, err.range.clone()),
+        });
+        err.trace.extend(self.stack.iter().rev().cloned());
+    }
+
+    fn scope<T>(&mut self, run_fn: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        let env = Environment::new(Some(self.env.clone()));
+        let outer = std::mem::replace(&mut self.env, env);
+        let result = run_fn(self);
+        self.env = outer;
+        result
+    }
+    fn location(&self, range: &Range<usize>) -> Option<VarLocation> {
+        self.program.locations.get(range).copied()
+    }
+    fn declare(&mut self, range: &Range<usize>, value: Value) {
+        if let Some(location) = self.location(range) {
+            self.env.set(location, value);
+        }
+    }
+
+    // function declarations are hoisted, like in the resolver
+    fn hoist(&mut self, statements: &[ParseNode<Statement>]) {
+        for stmt in statements {
+            if let Statement::Declaration(Declaration::Func(decl)) = &stmt.data {
+                self.func_decl(decl, &stmt.range);
+            }
+        }
+    }
+    fn statements(&mut self, statements: &[ParseNode<Statement>]) -> Result<Flow> {
+        self.hoist(statements);
+        for stmt in statements {
+            match self.statement(&stmt.data, &stmt.range)? {
+                Flow::Normal => {}
+                flow => return Ok(flow),
+            }
+        }
+        Ok(Flow::Normal)
+    }
+    fn block(&mut self, block: &Block) -> Result<Flow> {
+        self.scope(|i| i.statements(&block.statements))
+    }
+    fn statement(&mut self, statement: &Statement, range: &Range<usize>) -> Result<Flow> {
+        match statement {
+            Statement::Declaration(Declaration::Var(decl)) => {
+                let value = match &decl.value {
+                    Some(value) => self.expression(&value.data, &value.range)?,
+                    None => Value::None,
+                };
+                self.unpack(&decl.pattern, value, range)?;
+            }
+            Statement::Declaration(Declaration::Func(_)) => {}
+            Statement::Expression(expr) => {
+                self.expression(expr, range)?;
+            }
+            Statement::If(statement) => return self.if_statement(statement),
+            Statement::While(statement) => return self.while_statement(statement),
+            Statement::Try(statement) => return self.try_statement(statement),
+            Statement::Block(block) => return self.block(block),
+            Statement::Break => return Ok(Flow::Break),
+            Statement::Continue => return Ok(Flow::Continue),
+            Statement::Return(value) => {
+                let value = match value {
+                    Some(value) => self.expression(&value.data, &value.range)?,
+                    None => Value::None,
+                };
+                return Ok(Flow::Return(value));
+            }
+            Statement::Throw(value) => {
+                let value = self.expression(&value.data, &value.range)?;
+                return Err(throw(value, range));
+            }
+        }
+        Ok(Flow::Normal)
+    }
+    fn if_statement(&mut self, statement: &IfStatement) -> Result<Flow> {
+        let condition = &statement.condition;
+        if self
+            .expression(&condition.data, &condition.range)?
+            .is_truthy()
+        {
+            return self.block(&statement.met_block.data);
+        }
+        match statement.else_block.as_ref().map(|block| &block.data) {
+            Some(ElseBlock::Block(block)) => self.block(block),
+            Some(ElseBlock::If(statement)) => self.if_statement(statement),
+            None => Ok(Flow::Normal),
+        }
+    }
+    // `onbreak` runs after the loop is left with `break`, `oncontinue` after it ends because the
+    // condition is false. a `return` skips both
+    fn while_statement(&mut self, statement: &WhileStatement) -> Result<Flow> {
+        let condition = &statement.condition;
+        let handler = loop {
+            if !self
+                .expression(&condition.data, &condition.range)?
+                .is_truthy()
+            {
+                break &statement.on_continue;
+            }
+            match self.block(&statement.loop_block.data)? {
+                Flow::Normal | Flow::Continue => {}
+                Flow::Break => break &statement.on_break,
+                flow @ Flow::Return(_) => return Ok(flow),
+            }
+        };
+        match handler {
+            Some(block) => self.block(&block.data),
+            None => Ok(Flow::Normal),
+        }
+    }
+    // `finally` runs however the other blocks end. a jump or error in it replaces how they ended
+    fn try_statement(&mut self, statement: &TryStatement) -> Result<Flow> {
+        let mut result = self.block(&statement.try_block.data);
+        if let Some(catch) = &statement.catch {
+            if let Err(err) = result {
+                let error = self.caught(err);
+                let catch = &catch.data;
+                result = self.scope(|i| {
+                    i.declare(&catch.name.range, Value::Error(error));
+                    i.statements(&catch.block.data.statements)
+                });
+            }
+        }
+        if let Some(block) = &statement.finally {
+            match self.block(&block.data)? {
+                Flow::Normal => {}
+                flow => return Ok(flow),
+            }
+        }
+        result
+    }
+    // the value a `catch` block gets for the error
+    fn caught(&self, mut err: RuntimeError) -> Rc<ErrorValue> {
+        self.trace(&mut err);
+        match err.error {
+            // an error thrown again keeps the trace of where it was first raised
+            RuntimeErrorType::Thrown(error) if !error.trace.is_empty() => error,
+            RuntimeErrorType::Thrown(error) => {
+                let mut error = Rc::unwrap_or_clone(error);
+                error.trace = err.trace;
+                Rc::new(error)
+            }
+            error => Rc::new(ErrorValue {
+                kind: error.kind().into(),
+                message: error.to_string().into(),
+                value: Value::None,
+                trace: err.trace,
+            }),
+        }
+    }
+    fn func_decl(&mut self, decl: &FuncDecl, range: &Range<usize>) {
+        let decl = self
+            .program
+            .functions
+            .borrow_mut()
+            .entry(range.clone())
+            .or_insert_with(|| Rc::new(decl.clone()))
+            .clone();
+        let function = Function {
+            program: self.program.clone(),
+            closure: self.env.clone(),
+            decl: decl.clone(),
+        };
+        self.declare(&decl.name.range, Value::Function(Rc::new(function)));
+    }
+
+    // assigns the value to the names of the pattern, `range` is the range of the declaration.
+    // an array pattern takes the first and last items of an array at least as long as it
+    fn unpack(
+        &mut self,
+        pattern: &ParseNode<VarNameType>,
+        value: Value,
+        range: &Range<usize>,
+    ) -> Result<()> {
+        let error = |error| Err(RuntimeError::new(range.clone(), error));
+        match (&pattern.data, value) {
+            (VarNameType::Ident(_), value) => self.declare(&pattern.range, value),
+            (
+                VarNameType::Array {
+                    start_names,
+                    end_names,
+                },
+                Value::Array(items),
+            ) => {
+                let items = items.borrow().clone();
+                let expected = start_names.len() + end_names.len();
+                if items.len() < expected {
+                    return error(RuntimeErrorType::NotEnoughElements(expected, items.len()));
+                }
+                let end = items.len() - end_names.len();
+                let names = start_names.iter().chain(end_names);
+                let values = items[..start_names.len()].iter().chain(&items[end..]);
+                for (name, value) in names.zip(values) {
+                    self.declare(&name.range, value.clone());
+                }
+            }
+            (VarNameType::Object(names), Value::Dictionary(items)) => {
+                let items = items.borrow();
+                for (key, name) in names {
+                    let Some(value) = items.get(&Key::from(*key)) else {
+                        let key = format!("{:?}", Value::from(Key::from(*key)));
+                        return error(RuntimeErrorType::MissingKey(key));
+                    };
+                    self.declare(&name.range, value.clone());
+                }
+            }
+            (_, value) => return error(RuntimeErrorType::CannotUnpack(value.type_name())),
+        }
+        Ok(())
+    }
+
+    fn expression(&mut self, expr: &Expression, range: &Range<usize>) -> Result<Value> {
+        let error = |error| Err(RuntimeError::new(range.clone(), error));
+        Ok(match expr {
+            Expression::None => Value::None,
+            Expression::Bool(b) => Value::Bool(*b),
+            Expression::Int(i) => match i64::try_from(*i) {
+                Ok(i) => Value::Int(i),
+                Err(_) => return error(RuntimeErrorType::IntOverflow),
+            },
+            Expression::Real(r) => Value::Real(*r),
+            Expression::String(s) => Value::String(s.as_str().into()),
+            Expression::Char(c) => Value::Char(*c),
+            Expression::Array(items) => {
+                let mut values = Vec::with_capacity(items.len());
+                for item in items {
+                    values.push(self.expression(&item.data, &item.range)?);
+                }
+                Value::array(values)
+            }
+            Expression::Dictionary(items) => {
+                let mut values = IndexMap::with_capacity(items.len());
+                for (key, value) in items {
+                    let key = match &key.data {
+                        DictionaryKey::Ident(name) => Key::from(*name),
+                        DictionaryKey::Expr(expr) => {
+                            let key_value = self.expression(&expr.data, &expr.range)?;
+                            self.key(key_value, &expr.range)?
+                        }
+                    };
+                    values.insert(key, self.expression(&value.data, &value.range)?);
+                }
+                Value::dictionary(values)
+            }
+            Expression::Variable(name) => self.variable(*name, range)?,
+            Expression::Binary(binary) => self.binary(binary, range)?,
+            Expression::Assign(assign) => self.assign(assign, range)?,
+            Expression::Unary(unary) => self.unary(unary, range)?,
+            Expression::Suffix(suffix) => self.suffix(suffix, range)?,
+            Expression::Grouping(expr) => self.expression(&expr.data, &expr.range)?,
+        })
+    }
+    fn variable(&mut self, name: StringName, range: &Range<usize>) -> Result<Value> {
+        let Some(location) = self.location(range) else {
+            let error = RuntimeErrorType::UndefinedVariable(name);
+            return Err(RuntimeError::new(range.clone(), error));
+        };
+        self.env.get(location).ok_or_else(|| {
+            RuntimeError::new(range.clone(), RuntimeErrorType::UninitializedVariable(name))
+        })
+    }
+    fn key(&self, value: Value, range: &Range<usize>) -> Result<Key> {
+        Key::try_from(value)
+            .map_err(|kind| RuntimeError::new(range.clone(), RuntimeErrorType::UnhashableKey(kind)))
+    }
+    fn binary(&mut self, binary: &Binary, range: &Range<usize>) -> Result<Value> {
+        let left = self.expression(&binary.left.data, &binary.left.range)?;
+        let operator = match binary.operator {
+            // the value of the last operand evaluated, like in lox
+            SymbolKeyword::Keyword(Keyword::And) if !left.is_truthy() => return Ok(left),
+            SymbolKeyword::Keyword(Keyword::Or) if left.is_truthy() => return Ok(left),
+            SymbolKeyword::Keyword(_) => {
+                return self.expression(&binary.right.data, &binary.right.range)
+            }
+            SymbolKeyword::Symbol(symbol) => symbol,
+        };
+        let right = self.expression(&binary.right.data, &binary.right.range)?;
+        operator::binary(operator, left, right)
+            .map_err(|error| RuntimeError::new(range.clone(), error))
+    }
+    fn unary(&mut self, unary: &Unary, range: &Range<usize>) -> Result<Value> {
+        // the int literal of `i64::MIN` is only in range when it's negated
+        if let (Symbol::Sub, Expression::Int(i)) = (unary.operator, &unary.operand.data) {
+            if *i == i64::MIN.unsigned_abs() {
+                return Ok(Value::Int(i64::MIN));
+            }
+        }
+        let operand = self.expression(&unary.operand.data, &unary.operand.range)?;
+        operator::unary(unary.operator, operand)
+            .map_err(|error| RuntimeError::new(range.clone(), error))
+    }
+    fn assign(&mut self, assign: &Assign, range: &Range<usize>) -> Result<Value> {
+        let left = &assign.left;
+        let right = &assign.right;
+        // the container and the index are evaluated once, even for compound assignments
+        let target = match &left.data {
+            Expression::Variable(name) => Target::Variable(*name),
+            Expression::Suffix(suffix) => {
+                let node = self.expression(&suffix.node.data, &suffix.node.range)?;
+                match &suffix.suffix {
+                    SuffixType::Index(index) => {
+                        let index = self.expression(&index.data, &index.range)?;
+                        Target::Index(node, index)
+                    }
+                    SuffixType::Property(name) => {
+                        Target::Index(node, Value::String(name.as_str().into()))
+                    }
+                    SuffixType::Call(_) => unreachable!("the parser rejects assigning to a call"),
+                }
+            }
+            _ => unreachable!("the parser rejects invalid assignment targets"),
+        };
+
+        let mut value = self.expression(&right.data, &right.range)?;
+        if let Some(operator) = assign.operator {
+            let current = match &target {
+                Target::Variable(name) => self.variable(*name, &left.range)?,
+                Target::Index(node, index) => self.index(node, index.clone(), &left.range)?,
+            };
+            value = operator::binary(operator, current, value)
+                .map_err(|error| RuntimeError::new(range.clone(), error))?;
+        }
+
+        match target {
+            Target::Variable(name) => {
+                let Some(location) = self.location(&left.range) else {
+                    let error = RuntimeErrorType::UndefinedVariable(name);
+                    return Err(RuntimeError::new(left.range.clone(), error));
+                };
+                self.env.set(location, value.clone());
+            }
+            Target::Index(node, index) => {
+                self.set_index(&node, index, value.clone(), &left.range)?
+            }
+        }
+        Ok(value)
+    }
+    fn suffix(&mut self, suffix: &Suffix, range: &Range<usize>) -> Result<Value> {
+        // `value.name(args)` is a method call
+        if let (SuffixType::Call(args), Expression::Suffix(property)) =
+            (&suffix.suffix, &suffix.node.data)
+        {
+            if let SuffixType::Property(name) = property.suffix {
+                let receiver = self.expression(&property.node.data, &property.node.range)?;
+                let args = self.arguments(args)?;
+                return self.method(receiver, name, args, range);
+            }
+        }
+
+        let node = self.expression(&suffix.node.data, &suffix.node.range)?;
+        match &suffix.suffix {
+            SuffixType::Call(args) => {
+                let args = self.arguments(args)?;
+                self.call(node, args, range)
+            }
+            SuffixType::Index(index) => {
+                let index = self.expression(&index.data, &index.range)?;
+                self.index(&node, index, range)
+            }
+            SuffixType::Property(name) => match &node {
+                Value::Dictionary(_) => {
+                    self.index(&node, Value::String(name.as_str().into()), range)
+                }
+                Value::Error(error) => self.error_property(error, *name, range),
+                _ => {
+                    let error = RuntimeErrorType::NoProperty(node.type_name(), *name);
+                    Err(RuntimeError::new(range.clone(), error))
+                }
+            },
+        }
+    }
+    // `trace` is the frames written as `name (file:line:column)`, the innermost first
+    fn error_property(
+        &self,
+        error: &ErrorValue,
+        name: StringName,
+        range: &Range<usize>,
+    ) -> Result<Value> {
+        Ok(match name.as_str() {
+            "kind" => Value::String(error.kind.clone()),
+            "message" => Value::String(error.message.clone()),
+            "value" => error.value.clone(),
+            "trace" => {
+                let frames = error.trace.iter().map(|frame| {
+                    let Some(file) = self.files.get(&frame.span.file) else {
+                        return Value::String(frame.to_string().into());
+                    };
+                    let (line, column) = file.line_column(frame.span.range.start);
+                    let location = format!("{} ({}:{}:{})", frame, file.name(), line, column);
+                    Value::String(location.into())
+                });
+                Value::array(frames.collect())
+            }
+            _ => {
+                let error = RuntimeErrorType::NoProperty("error", name);
+                return Err(RuntimeError::new(range.clone(), error));
+            }
+        })
+    }
+    fn arguments(&mut self, args: &[ParseNode<Expression>]) -> Result<Vec<Value>> {
+        let mut values = Vec::with_capacity(args.len());
+        for arg in args {
+            values.push(self.expression(&arg.data, &arg.range)?);
+        }
+        Ok(values)
+    }
+    // arrays are indexed by ints from 0, strings by chars (not bytes or graphemes), dictionaries
+    // by their keys
+    fn index(&self, node: &Value, index: Value, range: &Range<usize>) -> Result<Value> {
+        let error = |error| Err(RuntimeError::new(range.clone(), error));
+        match (node, &index) {
+            (Value::Array(items), Value::Int(i)) => {
+                let items = items.borrow();
+                match usize::try_from(*i).ok().and_then(|i| items.get(i)) {
+                    Some(item) => Ok(item.clone()),
+                    None => error(RuntimeErrorType::IndexOutOfRange(*i, items.len())),
+                }
+            }
+            (Value::String(s), Value::Int(i)) => {
+                match usize::try_from(*i).ok().and_then(|i| s.chars().nth(i)) {
+                    Some(c) => Ok(Value::Char(c)),
+                    None => error(RuntimeErrorType::IndexOutOfRange(*i, s.chars().count())),
+                }
+            }
+            (Value::Dictionary(items), _) => {
+                let key = self.key(index, range)?;
+                match items.borrow().get(&key) {
+                    Some(value) => Ok(value.clone()),
+                    None => error(RuntimeErrorType::MissingKey(format!(
+                        "{:?}",
+                        Value::from(key)
+                    ))),
+                }
+            }
+            (Value::Array(_) | Value::String(_), _) => error(RuntimeErrorType::InvalidIndex(
+                node.type_name(),
+                index.type_name(),
+            )),
+            _ => error(RuntimeErrorType::NotIndexable(node.type_name())),
+        }
+    }
+    fn set_index(
+        &self,
+        node: &Value,
+        index: Value,
+        value: Value,
+        range: &Range<usize>,
+    ) -> Result<()> {
+        let error = |error| Err(RuntimeError::new(range.clone(), error));
+        match (node, &index) {
+            (Value::Array(items), Value::Int(i)) => {
+                let mut items = items.borrow_mut();
+                let len = items.len();
+                match usize::try_from(*i).ok().and_then(|i| items.get_mut(i)) {
+                    Some(item) => *item = value,
+                    None => return error(RuntimeErrorType::IndexOutOfRange(*i, len)),
+                }
+            }
+            (Value::Dictionary(items), _) => {
+                let key = self.key(index, range)?;
+                items.borrow_mut().insert(key, value);
+            }
+            (Value::Array(_), _) => {
+                return error(RuntimeErrorType::InvalidIndex(
+                    node.type_name(),
+                    index.type_name(),
+                ))
+            }
+            _ => return error(RuntimeErrorType::NotIndexable(node.type_name())),
+        }
+        Ok(())
+    }
+
+    /// Calls the function with the arguments, `range` is the range of the call.
+    pub(crate) fn call(
+        &mut self,
+        callee: Value,
+        args: Vec<Value>,
+        range: &Range<usize>,
+    ) -> Result<Value> {
+        let error = |error| Err(RuntimeError::new(range.clone(), error));
+        let function = match callee {
+            Value::Function(function) => function,
+            Value::Native(native) => {
+                if args.len() < native.min || native.max.is_some_and(|max| args.len() > max) {
+                    return error(RuntimeErrorType::ArgumentCount {
+                        min: native.min,
+                        max: native.max,
+                        found: args.len(),
+                    });
+                }
+                let args = Args {
+                    name: native.name,
+                    values: &args,
+                };
+                return (native.function)(args).or_else(error);
+            }
+            _ => return error(RuntimeErrorType::NotCallable(callee.type_name())),
+        };
+        let params = &function.decl.params;
+        // parameters after the last one without a default value can be left out
+        let min = params
+            .iter()
+            .rposition(|param| param.data.value.is_none())
+            .map_or(0, |i| i + 1);
+        if args.len() < min || args.len() > params.len() {
+            return error(RuntimeErrorType::ArgumentCount {
+                min,
+                max: Some(params.len()),
+                found: args.len(),
+            });
+        }
+        if self.stack.len() >= MAX_CALL_DEPTH {
+            return error(RuntimeErrorType::StackOverflow);
+        }
+
+        let program = std::mem::replace(&mut self.program, function.program.clone());
+        let env = std::mem::replace(
+            &mut self.env,
+            Environment::new(Some(function.closure.clone())),
+        );
+        self.stack.push(Frame {
+            function: self.function,
+            span: Span::new(program.file, range.clone()),
+        });
+        let caller = self.function.replace(function.name());
+        let mut result = self.function_body(&function.decl, args);
+        if let Err(err) = &mut result {
+            self.trace(err);
+        }
+        self.function = caller;
+        self.stack.pop();
+        self.program = program;
+        self.env = env;
+        result
+    }
+    // the parameters and the body share the same scope
+    fn function_body(&mut self, decl: &FuncDecl, args: Vec<Value>) -> Result<Value> {
+        let mut args = args.into_iter();
+        for param in &decl.params {
+            let value = match (args.next(), &param.data.value) {
+                (Some(value), _) => value,
+                (None, Some(default)) => self.expression(&default.data, &default.range)?,
+                (None, None) => unreachable!("the number of arguments was checked"),
+            };
+            self.unpack(&param.data.pattern, value, &param.range)?;
+        }
+        match &decl.block.data {
+            FuncBlock::Block(block) => match self.statements(&block.statements)? {
+                Flow::Return(value) => Ok(value),
+                _ => Ok(Value::None),
+            },
+            FuncBlock::ReturnExpr(expr) => self.expression(expr, &decl.block.range),
+        }
+    }
+}
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// the error raised by `throw value`. an error that was caught is raised again where it was first
+// raised, with the same trace
+fn throw(value: Value, range: &Range<usize>) -> RuntimeError {
+    let error = match value {
+        Value::Error(error) if !error.trace.is_empty() => {
+            return RuntimeError {
+                range: error.trace[0].span.range.clone(),
+                trace: error.trace.clone(),
+                error: RuntimeErrorType::Thrown(error),
+            };
+        }
+        Value::Error(error) => error,
+        value => Rc::new(ErrorValue::new("Error", &value.to_string(), value)),
+    };
+    RuntimeError::new(range.clone(), RuntimeErrorType::Thrown(error))
+}
+
+// what an assignment assigns to
+enum Target {
+    Variable(StringName),
+    Index(Value, Value),
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    // runs the program and returns the value of its last expression statement
+    pub(in crate::interpreter) fn eval(source: &str) -> Result<Value> {
+        let mut sources = SourceMap::new();
+        let file = sources.add("test.lox", source);
+        let statements = Parser::new(sources.get(file).source()).parse().unwrap();
+        Interpreter::new().run(&statements, &sources, file)
+    }
+
+    #[test]
+    fn finally_runs_after_every_jump() {
+        let source = r#"
+            let log = [];
+            func f() {
+                try { return "try"; } finally { log.push("finally"); }
+            }
+            log.push(f());
+            while true {
+                try { break; } finally { log.push("break"); }
+            }
+            try {
+                try { throw "error"; } finally { log.push("uncaught"); }
+            } catch e {
+                log.push(e.message);
+            }
+            log;
+        "#;
+        assert_eq!(
+            eval(source).unwrap().to_string(),
+            r#"["finally", "try", "break", "uncaught", "error"]"#
+        );
+    }
+
+    #[test]
+    fn jumps_in_finally_replace_the_result() {
+        // a return in `finally` replaces the return of `try`, and discards its error
+        let source = r#"
+            func returned() { try { return 1; } finally { return 2; } }
+            func thrown() { try { throw "lost"; } finally { return 3; } }
+            [returned(), thrown()];
+        "#;
+        assert_eq!(eval(source).unwrap().to_string(), "[2, 3]");
+
+        // a break in `finally` replaces the continue of `try`
+        let source = r#"
+            let i = 0;
+            while true {
+                i += 1;
+                try { continue; } finally { break; }
+            }
+            i;
+        "#;
+        assert_eq!(eval(source).unwrap().to_string(), "1");
+
+        // an error in `finally` replaces the error of `catch`
+        let source = r#"
+            let message = none;
+            try {
+                try { throw "first"; } catch e { throw "second"; } finally { throw "third"; }
+            } catch e {
+                message = e.message;
+            }
+            message;
+        "#;
+        assert_eq!(eval(source).unwrap().to_string(), "third");
+    }
+
+    #[test]
+    fn caught_error_values() {
+        let source = r#"
+            let caught = [];
+            try { [1][5]; } catch e { caught.push([e.kind, e.message, e.value]); }
+            try { throw [1, 2]; } catch e { caught.push([e.kind, e.message, e.value]); }
+            try { throw error("bad", "Custom"); } catch e { caught.push([e.kind, e.message]); }
+            caught;
+        "#;
+        assert_eq!(
+            eval(source).unwrap().to_string(),
+            r#"[["IndexOutOfRange", "Index 5 is out of range for length 1", none], ["Error", "[1, 2]", [1, 2]], ["Custom", "bad"]]"#
+        );
+    }
+
+    #[test]
+    fn uncaught_errors_leave_the_program() {
+        let err = eval("try { throw 1; } finally { }").unwrap_err();
+        assert!(matches!(err.error, RuntimeErrorType::Thrown(_)));
+    }
+}
@@ -0,0 +1,315 @@
+// functions written in rust, and the standard library made of them
+
+use std::cell::Cell;
+use std::io::Write;
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::error::RuntimeErrorType;
+use super::operator;
+use super::value::{ErrorValue, Value};
+use super::Interpreter;
+use crate::parser::parse_node::Symbol;
+use crate::string_name::StringName;
+
+/// What a native function returns. The error is raised at the call.
+pub type NativeResult = std::result::Result<Value, RuntimeErrorType>;
+
+/// A function written in rust, defined with `Interpreter::define_native`.
+pub struct NativeFunction {
+    pub(super) name: StringName,
+    pub(super) min: usize,
+    // `None` takes any number of arguments after `min`
+    pub(super) max: Option<usize>,
+    pub(super) function: Box<dyn Fn(Args) -> NativeResult>,
+}
+impl NativeFunction {
+    pub fn name(&self) -> StringName {
+        self.name
+    }
+}
+
+/// The arguments of a call to a native function. The number of arguments was already checked.
+pub struct Args<'a> {
+    pub(super) name: StringName,
+    pub(super) values: &'a [Value],
+}
+impl<'a> Args<'a> {
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+    pub fn values(&self) -> &'a [Value] {
+        self.values
+    }
+    pub fn get(&self, index: usize) -> &'a Value {
+        &self.values[index]
+    }
+    // checks the number of arguments of a method, which has no `NativeFunction` to check it
+    pub(super) fn arity(&self, min: usize, max: usize) -> Result<(), RuntimeErrorType> {
+        if self.len() < min || self.len() > max {
+            return Err(RuntimeErrorType::ArgumentCount {
+                min,
+                max: Some(max),
+                found: self.len(),
+            });
+        }
+        Ok(())
+    }
+    /// The error for the argument at `index` not being of the `expected` type, such as "an int".
+    pub fn invalid(&self, index: usize, expected: &'static str) -> RuntimeErrorType {
+        RuntimeErrorType::ArgumentType {
+            function: self.name,
+            index,
+            expected,
+            found: self.get(index).type_name(),
+        }
+    }
+    pub fn int(&self, index: usize) -> Result<i64, RuntimeErrorType> {
+        match self.get(index) {
+            Value::Int(i) => Ok(*i),
+            _ => Err(self.invalid(index, "an int")),
+        }
+    }
+    /// An int or real argument, as a real.
+    pub fn number(&self, index: usize) -> Result<f64, RuntimeErrorType> {
+        match self.get(index) {
+            Value::Int(i) => Ok(*i as f64),
+            Value::Real(r) => Ok(*r),
+            _ => Err(self.invalid(index, "a number")),
+        }
+    }
+    pub fn string(&self, index: usize) -> Result<&'a str, RuntimeErrorType> {
+        match self.get(index) {
+            Value::String(s) => Ok(s),
+            _ => Err(self.invalid(index, "a string")),
+        }
+    }
+    pub fn char(&self, index: usize) -> Result<char, RuntimeErrorType> {
+        match self.get(index) {
+            Value::Char(c) => Ok(*c),
+            _ => Err(self.invalid(index, "a char")),
+        }
+    }
+}
+
+// defines the functions every program can use
+pub(super) fn define_stdlib(interpreter: &mut Interpreter) {
+    interpreter.define_native("print", 0.., |args| {
+        print!("{}", join(args.values()));
+        let _ = std::io::stdout().flush();
+        Ok(Value::None)
+    });
+    interpreter.define_native("println", 0.., |args| {
+        println!("{}", join(args.values()));
+        Ok(Value::None)
+    });
+    // strings count chars, like indexing does, where their `len` method counts graphemes
+    interpreter.define_native("len", 1..=1, |args| {
+        let len = match args.get(0) {
+            Value::String(s) => s.chars().count(),
+            Value::Array(items) => items.borrow().len(),
+            Value::Dictionary(items) => items.borrow().len(),
+            _ => return Err(args.invalid(0, "a string, array or dictionary")),
+        };
+        Ok(Value::Int(len as i64))
+    });
+    interpreter.define_native("type_of", 1..=1, |args| {
+        Ok(Value::String(args.get(0).type_name().into()))
+    });
+    // an error value to throw, `error(message)` is an `Error`
+    interpreter.define_native("error", 1..=2, |args| {
+        let kind = if args.len() > 1 {
+            args.string(1)?
+        } else {
+            "Error"
+        };
+        let message = args.get(0).to_string();
+        let error = ErrorValue::new(kind, &message, args.get(0).clone());
+        Ok(Value::Error(Rc::new(error)))
+    });
+
+    // conversions
+    interpreter.define_native("str", 1..=1, |args| {
+        Ok(Value::String(args.get(0).to_string().into()))
+    });
+    interpreter.define_native("int", 1..=1, |args| {
+        let value = args.get(0);
+        let int = match value {
+            Value::Int(i) => Some(*i),
+            Value::Real(r) => real_to_int(r.trunc()),
+            Value::Bool(b) => Some(*b as i64),
+            Value::String(s) => s.trim().parse().ok(),
+            _ => None,
+        };
+        int.map(Value::Int)
+            .ok_or_else(|| RuntimeErrorType::InvalidConversion(format!("{:?}", value), "int"))
+    });
+    interpreter.define_native("real", 1..=1, |args| {
+        let value = args.get(0);
+        let real = match value {
+            Value::Int(i) => Some(*i as f64),
+            Value::Real(r) => Some(*r),
+            Value::String(s) => s.trim().parse().ok(),
+            _ => None,
+        };
+        real.map(Value::Real)
+            .ok_or_else(|| RuntimeErrorType::InvalidConversion(format!("{:?}", value), "real"))
+    });
+    interpreter.define_native("char", 1..=1, |args| {
+        let code = args.int(0)?;
+        u32::try_from(code)
+            .ok()
+            .and_then(char::from_u32)
+            .map(Value::Char)
+            .ok_or_else(|| RuntimeErrorType::InvalidConversion(code.to_string(), "char"))
+    });
+    interpreter.define_native("ord", 1..=1, |args| Ok(Value::Int(args.char(0)? as i64)));
+
+    // math
+    interpreter.define_native("abs", 1..=1, |args| match args.get(0) {
+        Value::Int(i) => i
+            .checked_abs()
+            .map(Value::Int)
+            .ok_or(RuntimeErrorType::IntOverflow),
+        Value::Real(r) => Ok(Value::Real(r.abs())),
+        _ => Err(args.invalid(0, "a number")),
+    });
+    interpreter.define_native("min", 1.., |args| extreme(args, std::cmp::Ordering::Less));
+    interpreter.define_native("max", 1.., |args| {
+        extreme(args, std::cmp::Ordering::Greater)
+    });
+    interpreter.define_native("floor", 1..=1, |args| rounded(args, f64::floor));
+    interpreter.define_native("ceil", 1..=1, |args| rounded(args, f64::ceil));
+    interpreter.define_native("round", 1..=1, |args| rounded(args, f64::round));
+    interpreter.define_native("pow", 2..=2, |args| {
+        operator::binary(Symbol::Pow, args.get(0).clone(), args.get(1).clone())
+    });
+    let reals = [
+        ("sqrt", f64::sqrt as fn(f64) -> f64),
+        ("sin", f64::sin),
+        ("cos", f64::cos),
+        ("tan", f64::tan),
+        ("asin", f64::asin),
+        ("acos", f64::acos),
+        ("atan", f64::atan),
+    ];
+    for (name, function) in reals {
+        interpreter.define_native(name, 1..=1, move |args| {
+            Ok(Value::Real(function(args.number(0)?)))
+        });
+    }
+    interpreter.define_native("atan2", 2..=2, |args| {
+        Ok(Value::Real(args.number(0)?.atan2(args.number(1)?)))
+    });
+
+    // seconds since the unix epoch
+    interpreter.define_native("clock", 0..=0, |_| {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        Ok(Value::Real(time.as_secs_f64()))
+    });
+
+    // the same seed gives the same numbers, the first seed is the time
+    let state = Rc::new(Cell::new(
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_nanos() as u64),
+    ));
+    let seed = state.clone();
+    interpreter.define_native("seed", 1..=1, move |args| {
+        seed.set(args.int(0)? as u64);
+        Ok(Value::None)
+    });
+    // `random()` is a real from 0 up to 1, `random(a, b)` an int from a to b, both included
+    interpreter.define_native("random", 0..=2, move |args| {
+        let random = splitmix64(&state);
+        match args.len() {
+            0 => Ok(Value::Real((random >> 11) as f64 / (1u64 << 53) as f64)),
+            2 => {
+                let (low, high) = (args.int(0)?, args.int(1)?);
+                if low > high {
+                    return Err(RuntimeErrorType::InvalidArgument(format!(
+                        "The range {} to {} of 'random' is empty",
+                        low, high
+                    )));
+                }
+                let span = (high as i128 - low as i128 + 1) as u128;
+                let offset = (random as u128 * span) >> 64;
+                Ok(Value::Int((low as i128 + offset as i128) as i64))
+            }
+            _ => Err(RuntimeErrorType::ArgumentCount {
+                min: 2,
+                max: Some(2),
+                found: 1,
+            }),
+        }
+    });
+}
+
+// the arguments written the way `print` writes them, separated by spaces
+fn join(values: &[Value]) -> String {
+    let values: Vec<_> = values.iter().map(Value::to_string).collect();
+    values.join(" ")
+}
+
+fn real_to_int(real: f64) -> Option<i64> {
+    // the range of i64 can't be written exactly as reals, but its bounds are powers of two
+    (real >= i64::MIN as f64 && real < i64::MAX as f64).then_some(real as i64)
+}
+
+// `floor`, `ceil` and `round` of a number, as an int
+fn rounded(args: Args, function: fn(f64) -> f64) -> NativeResult {
+    match args.get(0) {
+        Value::Int(i) => Ok(Value::Int(*i)),
+        Value::Real(r) if r.is_nan() => Err(RuntimeErrorType::InvalidConversion(
+            format!("{:?}", r),
+            "int",
+        )),
+        Value::Real(r) => real_to_int(function(*r))
+            .map(Value::Int)
+            .ok_or(RuntimeErrorType::IntOverflow),
+        _ => Err(args.invalid(0, "a number")),
+    }
+}
+
+// the smallest or largest of the arguments, or of the items of a single array argument
+fn extreme(args: Args, wanted: std::cmp::Ordering) -> NativeResult {
+    let items = match args.values() {
+        [Value::Array(items)] => items.borrow().clone(),
+        values => values.to_vec(),
+    };
+    let mut items = items.into_iter();
+    let Some(mut result) = items.next() else {
+        return Err(RuntimeErrorType::InvalidArgument(format!(
+            "'{}' of an empty array",
+            args.name
+        )));
+    };
+    for item in items {
+        let Some(ordering) = operator::compare(&item, &result) else {
+            return Err(RuntimeErrorType::InvalidOperands(
+                "<",
+                item.type_name(),
+                result.type_name(),
+            ));
+        };
+        if ordering == wanted {
+            result = item;
+        }
+    }
+    Ok(result)
+}
+
+// a small generator that is good enough for games and tests, not for anything secret
+fn splitmix64(state: &Cell<u64>) -> u64 {
+    let next = state.get().wrapping_add(0x9e3779b97f4a7c15);
+    state.set(next);
+    let mut z = next;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
@@ -0,0 +1,148 @@
+use std::cmp::Ordering;
+
+use super::error::RuntimeErrorType;
+use super::value::Value;
+use crate::parser::parse_node::Symbol;
+
+type Result<T> = std::result::Result<T, RuntimeErrorType>;
+
+// ints are 64 bit and every operation on them that overflows is an error, except for shifts,
+// which drop the bits shifted out. an operation on an int and a real converts the int to a real
+
+/// Applies a binary symbol operator. `and` and `or` are not here, since they don't always
+/// evaluate their right operand.
+pub(crate) fn binary(operator: Symbol, left: Value, right: Value) -> Result<Value> {
+    let invalid = |left: &Value, right: &Value| {
+        RuntimeErrorType::InvalidOperands(operator.as_str(), left.type_name(), right.type_name())
+    };
+
+    Ok(match (operator, &left, &right) {
+        (Symbol::Eq, _, _) => Value::Bool(left == right),
+        (Symbol::NotEq, _, _) => Value::Bool(left != right),
+        (Symbol::Less | Symbol::Greater | Symbol::LessEq | Symbol::GreaterEq, _, _) => {
+            let Some(ordering) = compare(&left, &right) else {
+                return Err(invalid(&left, &right));
+            };
+            Value::Bool(match operator {
+                Symbol::Less => ordering.is_lt(),
+                Symbol::Greater => ordering.is_gt(),
+                Symbol::LessEq => ordering.is_le(),
+                _ => ordering.is_ge(),
+            })
+        }
+
+        (Symbol::Add, Value::String(a), Value::String(b)) => {
+            Value::String(format!("{}{}", a, b).into())
+        }
+        (Symbol::Add, Value::String(a), Value::Char(b)) => {
+            Value::String(format!("{}{}", a, b).into())
+        }
+        (Symbol::Add, Value::Char(a), Value::String(b)) => {
+            Value::String(format!("{}{}", a, b).into())
+        }
+        (Symbol::Add, Value::Array(a), Value::Array(b)) => {
+            let mut items = a.borrow().clone();
+            items.extend(b.borrow().iter().cloned());
+            Value::array(items)
+        }
+
+        (Symbol::And | Symbol::Or | Symbol::Xor, Value::Bool(a), Value::Bool(b)) => {
+            Value::Bool(match operator {
+                Symbol::And => a & b,
+                Symbol::Or => a | b,
+                _ => a ^ b,
+            })
+        }
+
+        // an int to the power of a negative int is a real
+        (Symbol::Pow, Value::Int(a), Value::Int(b)) if *b < 0 => {
+            Value::Real((*a as f64).powf(*b as f64))
+        }
+        (_, Value::Int(a), Value::Int(b)) => match int_binary(operator, *a, *b)? {
+            Some(result) => Value::Int(result),
+            None => return Err(invalid(&left, &right)),
+        },
+        (_, Value::Int(_) | Value::Real(_), Value::Int(_) | Value::Real(_)) => {
+            let (a, b) = (as_real(&left), as_real(&right));
+            Value::Real(match operator {
+                Symbol::Add => a + b,
+                Symbol::Sub => a - b,
+                Symbol::Mul => a * b,
+                Symbol::Div => a / b,
+                Symbol::Mod => a % b,
+                Symbol::Pow => a.powf(b),
+                _ => return Err(invalid(&left, &right)),
+            })
+        }
+        _ => return Err(invalid(&left, &right)),
+    })
+}
+
+/// Applies a unary operator. `!` is the logical not of a bool and the bitwise not of an int.
+pub(crate) fn unary(operator: Symbol, operand: Value) -> Result<Value> {
+    Ok(match (operator, &operand) {
+        (Symbol::Sub, Value::Int(i)) => {
+            Value::Int(i.checked_neg().ok_or(RuntimeErrorType::IntOverflow)?)
+        }
+        (Symbol::Sub, Value::Real(r)) => Value::Real(-r),
+        (Symbol::Add, Value::Int(_) | Value::Real(_)) => operand,
+        (Symbol::Not, Value::Bool(b)) => Value::Bool(!b),
+        (Symbol::Not, Value::Int(i)) => Value::Int(!i),
+        _ => {
+            return Err(RuntimeErrorType::InvalidOperand(
+                operator.as_str(),
+                operand.type_name(),
+            ))
+        }
+    })
+}
+
+/// Order of two numbers, strings or chars, `None` for other values and `nan`.
+pub(crate) fn compare(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (Value::Int(a), Value::Int(b)) => Some(a.cmp(b)),
+        (Value::Int(_) | Value::Real(_), Value::Int(_) | Value::Real(_)) => {
+            as_real(left).partial_cmp(&as_real(right))
+        }
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        (Value::Char(a), Value::Char(b)) => Some(a.cmp(b)),
+        _ => None,
+    }
+}
+
+fn as_real(value: &Value) -> f64 {
+    match value {
+        Value::Int(i) => *i as f64,
+        Value::Real(r) => *r,
+        _ => unreachable!("{} is not a number", value.type_name()),
+    }
+}
+
+// `None` if the operator doesn't apply to ints
+fn int_binary(operator: Symbol, a: i64, b: i64) -> Result<Option<i64>> {
+    let overflow = RuntimeErrorType::IntOverflow;
+    Ok(Some(match operator {
+        Symbol::Add => a.checked_add(b).ok_or(overflow)?,
+        Symbol::Sub => a.checked_sub(b).ok_or(overflow)?,
+        Symbol::Mul => a.checked_mul(b).ok_or(overflow)?,
+        // rounds towards zero, the only overflow is `i64::MIN / -1`
+        Symbol::Div | Symbol::Mod if b == 0 => return Err(RuntimeErrorType::DivisionByZero),
+        Symbol::Div => a.checked_div(b).ok_or(overflow)?,
+        Symbol::Mod => a.checked_rem(b).ok_or(overflow)?,
+        Symbol::Pow => match (a, u32::try_from(b)) {
+            (_, Ok(b)) => a.checked_pow(b).ok_or(overflow)?,
+            (0 | 1, _) => a,
+            (-1, _) => 1 - (b & 1) * 2,
+            _ => return Err(overflow),
+        },
+        Symbol::And => a & b,
+        Symbol::Or => a | b,
+        Symbol::Xor => a ^ b,
+        Symbol::LeftShift | Symbol::RightShift if !(0..64).contains(&b) => {
+            return Err(RuntimeErrorType::ShiftOutOfRange(b))
+        }
+        Symbol::LeftShift => a << b,
+        Symbol::RightShift => a >> b,
+        _ => return Ok(None),
+    }))
+}
@@ -0,0 +1,297 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use indexmap::IndexMap;
+
+use super::environment::Environment;
+use super::error::Frame;
+use super::native::NativeFunction;
+use super::Program;
+use crate::parser::parse_node::FuncDecl;
+use crate::string_name::StringName;
+
+pub type Array = Rc<RefCell<Vec<Value>>>;
+// keeps the insertion order of the keys
+pub type Dictionary = Rc<RefCell<IndexMap<Key, Value>>>;
+
+/// A value of a running program. Arrays and dictionaries are shared, assigning one to another
+/// variable doesn't copy it.
+#[derive(Clone)]
+pub enum Value {
+    None,
+    Bool(bool),
+    Int(i64),
+    Real(f64),
+    String(Rc<str>),
+    Char(char),
+    Array(Array),
+    Dictionary(Dictionary),
+    Function(Rc<Function>),
+    Native(Rc<NativeFunction>),
+    Error(Rc<ErrorValue>),
+}
+impl Value {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::None => "none",
+            Value::Bool(_) => "bool",
+            Value::Int(_) => "int",
+            Value::Real(_) => "real",
+            Value::String(_) => "string",
+            Value::Char(_) => "char",
+            Value::Array(_) => "array",
+            Value::Dictionary(_) => "dictionary",
+            Value::Function(_) | Value::Native(_) => "function",
+            Value::Error(_) => "error",
+        }
+    }
+    /// `none` and `false` are false, every other value is true.
+    pub fn is_truthy(&self) -> bool {
+        !matches!(self, Value::None | Value::Bool(false))
+    }
+    pub fn array(items: Vec<Value>) -> Self {
+        Value::Array(Rc::new(RefCell::new(items)))
+    }
+    pub fn dictionary(items: IndexMap<Key, Value>) -> Self {
+        Value::Dictionary(Rc::new(RefCell::new(items)))
+    }
+    // writes the value, `nested` inside of an array or dictionary where strings and chars are
+    // quoted. `visiting` holds the containers being written, a container inside of itself is
+    // written as `[...]` or `{...}`
+    fn write(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        nested: bool,
+        visiting: &mut Vec<*const ()>,
+    ) -> fmt::Result {
+        match self {
+            Value::None => write!(f, "none"),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Int(i) => write!(f, "{}", i),
+            // always with a decimal point or exponent, so it can't be mistaken for an int
+            Value::Real(r) => write!(f, "{:?}", r),
+            Value::String(s) if nested => write!(f, "{:?}", s),
+            Value::String(s) => write!(f, "{}", s),
+            Value::Char(c) if nested => write!(f, "{:?}", c),
+            Value::Char(c) => write!(f, "{}", c),
+            Value::Array(items) => {
+                let pointer = Rc::as_ptr(items) as *const ();
+                if visiting.contains(&pointer) {
+                    return write!(f, "[...]");
+                }
+                visiting.push(pointer);
+                write!(f, "[")?;
+                for (i, item) in items.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    item.write(f, true, visiting)?;
+                }
+                visiting.pop();
+                write!(f, "]")
+            }
+            Value::Dictionary(items) => {
+                let pointer = Rc::as_ptr(items) as *const ();
+                if visiting.contains(&pointer) {
+                    return write!(f, "{{...}}");
+                }
+                visiting.push(pointer);
+                write!(f, "{{")?;
+                for (i, (key, value)) in items.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{} = ", key)?;
+                    value.write(f, true, visiting)?;
+                }
+                visiting.pop();
+                write!(f, "}}")
+            }
+            Value::Function(function) => write!(f, "<func {}>", function.name()),
+            Value::Native(function) => write!(f, "<native func {}>", function.name()),
+            Value::Error(error) => write!(f, "{}", error),
+        }
+    }
+    // `visiting` holds the pairs of containers being compared. a pair that is compared again
+    // inside of itself is equal, unless another part of the containers differs
+    fn equals(&self, other: &Value, visiting: &mut Vec<(*const (), *const ())>) -> bool {
+        match (self, other) {
+            (Value::None, Value::None) => true,
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::Int(a), Value::Int(b)) => a == b,
+            (Value::Real(a), Value::Real(b)) => a == b,
+            (Value::Int(a), Value::Real(b)) | (Value::Real(b), Value::Int(a)) => *a as f64 == *b,
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::Char(a), Value::Char(b)) => a == b,
+            (Value::Array(a), Value::Array(b)) => {
+                let pair = (Rc::as_ptr(a) as *const (), Rc::as_ptr(b) as *const ());
+                if Rc::ptr_eq(a, b) || visiting.contains(&pair) {
+                    return true;
+                }
+                visiting.push(pair);
+                let (a, b) = (a.borrow(), b.borrow());
+                let equal = a.len() == b.len()
+                    && a.iter().zip(b.iter()).all(|(a, b)| a.equals(b, visiting));
+                visiting.pop();
+                equal
+            }
+            (Value::Dictionary(a), Value::Dictionary(b)) => {
+                let pair = (Rc::as_ptr(a) as *const (), Rc::as_ptr(b) as *const ());
+                if Rc::ptr_eq(a, b) || visiting.contains(&pair) {
+                    return true;
+                }
+                visiting.push(pair);
+                let (a, b) = (a.borrow(), b.borrow());
+                let equal = a.len() == b.len()
+                    && a.iter()
+                        .all(|(key, a)| b.get(key).is_some_and(|b| a.equals(b, visiting)));
+                visiting.pop();
+                equal
+            }
+            (Value::Function(a), Value::Function(b)) => Rc::ptr_eq(a, b),
+            (Value::Native(a), Value::Native(b)) => Rc::ptr_eq(a, b),
+            (Value::Error(a), Value::Error(b)) => Rc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, false, &mut vec![])
+    }
+}
+impl fmt::Debug for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, true, &mut vec![])
+    }
+}
+// ints and reals are equal when they are the same number, arrays and dictionaries when their
+// items are. functions and errors are only equal to themselves
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        self.equals(other, &mut vec![])
+    }
+}
+
+/// An error as a value: a thrown value, or an error raised by the interpreter or a native
+/// function, as a `catch` block gets it.
+#[derive(Debug, Clone)]
+pub struct ErrorValue {
+    /// The name of the `RuntimeErrorType` for errors of the interpreter, such as
+    /// `IndexOutOfRange`. Thrown values are an `Error`, unless made with `error(message, kind)`.
+    pub kind: Rc<str>,
+    pub message: Rc<str>,
+    /// The thrown value, `none` for errors of the interpreter.
+    pub value: Value,
+    /// Where the error was raised, empty until it's thrown.
+    pub trace: Vec<Frame>,
+}
+impl ErrorValue {
+    /// An error that wasn't thrown yet.
+    pub fn new(kind: &str, message: &str, value: Value) -> Self {
+        Self {
+            kind: kind.into(),
+            message: message.into(),
+            value,
+            trace: vec![],
+        }
+    }
+}
+impl fmt::Display for ErrorValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.kind, self.message)
+    }
+}
+
+/// A dictionary key. Only values compared by their contents can be keys, so reals (which have
+/// `nan`), arrays, dictionaries and functions can't.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Key {
+    None,
+    Bool(bool),
+    Int(i64),
+    String(Rc<str>),
+    Char(char),
+}
+impl TryFrom<Value> for Key {
+    type Error = &'static str; // the type name of the value
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        Ok(match value {
+            Value::None => Key::None,
+            Value::Bool(b) => Key::Bool(b),
+            Value::Int(i) => Key::Int(i),
+            Value::String(s) => Key::String(s),
+            Value::Char(c) => Key::Char(c),
+            _ => return Err(value.type_name()),
+        })
+    }
+}
+impl From<Key> for Value {
+    fn from(key: Key) -> Self {
+        match key {
+            Key::None => Value::None,
+            Key::Bool(b) => Value::Bool(b),
+            Key::Int(i) => Value::Int(i),
+            Key::String(s) => Value::String(s),
+            Key::Char(c) => Value::Char(c),
+        }
+    }
+}
+impl From<StringName> for Key {
+    fn from(name: StringName) -> Self {
+        Key::String(name.as_str().into())
+    }
+}
+// written the way it is in a dictionary literal, `name` or `[key]`
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Key::String(s) if is_identifier(s) => write!(f, "{}", s),
+            key => write!(f, "[{:?}]", Value::from(key.clone())),
+        }
+    }
+}
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .is_some_and(|c| c == '_' || unicode_xid::UnicodeXID::is_xid_start(c))
+        && chars.all(unicode_xid::UnicodeXID::is_xid_continue)
+}
+
+/// A function declared in a program, with the scope it was declared in.
+pub struct Function {
+    pub(super) decl: Rc<FuncDecl>,
+    pub(super) program: Rc<Program>,
+    pub(super) closure: Rc<Environment>,
+}
+impl Function {
+    pub fn name(&self) -> StringName {
+        self.decl.name.data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::eval;
+
+    #[test]
+    fn containers_inside_of_themselves_are_written_once() {
+        let source = "let a = []; a.push(a); a;";
+        assert_eq!(eval(source).unwrap().to_string(), "[[...]]");
+        let source = "let d = {}; d.self = d; d.list = [d, \"x\"]; d;";
+        assert_eq!(
+            eval(source).unwrap().to_string(),
+            "{self = {...}, list = [{...}, \"x\"]}"
+        );
+    }
+
+    #[test]
+    fn containers_inside_of_themselves_are_compared() {
+        let source = "let a = []; a.push(a); let b = []; b.push(b); [a == a, a == b];";
+        assert_eq!(eval(source).unwrap().to_string(), "[true, true]");
+        let source = "let a = [1]; a.push(a); let b = [2]; b.push(b); a == b;";
+        assert_eq!(eval(source).unwrap().to_string(), "false");
+    }
+}
@@ -0,0 +1,14 @@
+pub mod ast_json;
+pub mod check;
+pub mod error_json;
+pub mod explain;
+pub mod formatter;
+pub mod interpreter;
+pub mod lint;
+pub mod optimize;
+pub mod parser;
+pub mod resolver;
+pub mod source;
+pub mod source_map;
+pub mod string_name;
+pub mod typecheck;
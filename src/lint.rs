@@ -0,0 +1,735 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use unicode_security::{skeleton, MixedScript};
+
+use crate::optimize::constant_errors;
+use crate::parser::{error::*, parse_node::*, visit::*, Parser};
+use crate::string_name::StringName;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Level {
+    Allow,
+    Warn,
+    Deny,
+}
+impl Level {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "allow" => Level::Allow,
+            "warn" => Level::Warn,
+            "deny" => Level::Deny,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lint {
+    UnusedVariables,
+    Shadowing,
+    UnreachableCode,
+    WhileTrue,
+    EmptyLoopHandlers,
+    SelfComparison,
+    MixedScriptIdentifiers,
+    ConfusableIdentifiers,
+    ConstantErrors,
+    InvisibleJoiners,
+}
+impl Lint {
+    pub const ALL: [Lint; 10] = [
+        Lint::UnusedVariables,
+        Lint::Shadowing,
+        Lint::UnreachableCode,
+        Lint::WhileTrue,
+        Lint::EmptyLoopHandlers,
+        Lint::SelfComparison,
+        Lint::MixedScriptIdentifiers,
+        Lint::ConfusableIdentifiers,
+        Lint::ConstantErrors,
+        Lint::InvisibleJoiners,
+    ];
+
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Lint::UnusedVariables => "unused_variables",
+            Lint::Shadowing => "shadowing",
+            Lint::UnreachableCode => "unreachable_code",
+            Lint::WhileTrue => "while_true",
+            Lint::EmptyLoopHandlers => "empty_loop_handlers",
+            Lint::SelfComparison => "self_comparison",
+            Lint::MixedScriptIdentifiers => "mixed_script_identifiers",
+            Lint::ConfusableIdentifiers => "confusable_identifiers",
+            Lint::ConstantErrors => "constant_errors",
+            Lint::InvisibleJoiners => "invisible_joiners",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|lint| lint.name() == name)
+    }
+    pub const fn default_level(&self) -> Level {
+        match self {
+            // the expression fails every time it runs, which is almost never what was meant
+            Lint::ConstantErrors => Level::Deny,
+            _ => Level::Warn,
+        }
+    }
+    // the lint reporting the error, `None` for errors that can't be configured
+    fn of(error: &ErrorType) -> Option<Self> {
+        Some(match error {
+            ErrorType::UnusedVariable(_) => Lint::UnusedVariables,
+            ErrorType::ShadowedVariable(_) => Lint::Shadowing,
+            ErrorType::UnreachableCode => Lint::UnreachableCode,
+            ErrorType::InfiniteLoop => Lint::WhileTrue,
+            ErrorType::EmptyLoopHandler(_) => Lint::EmptyLoopHandlers,
+            ErrorType::SelfComparison => Lint::SelfComparison,
+            ErrorType::MixedScriptIdent(_) => Lint::MixedScriptIdentifiers,
+            ErrorType::ConfusableIdent(..) => Lint::ConfusableIdentifiers,
+            ErrorType::ConstantError(_) => Lint::ConstantErrors,
+            ErrorType::InvisibleJoiner(_) => Lint::InvisibleJoiners,
+            _ => return None,
+        })
+    }
+}
+
+/// Levels of the lints, such as the ones given on the command line.
+/// Lints that aren't set use their default level.
+#[derive(Debug, Clone, Default)]
+pub struct LintConfig {
+    levels: HashMap<Lint, Level>,
+}
+impl LintConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn set(&mut self, lint: Lint, level: Level) {
+        self.levels.insert(lint, level);
+    }
+    /// Sets the level of the lint, unless it was already set.
+    pub fn set_default(&mut self, lint: Lint, level: Level) {
+        self.levels.entry(lint).or_insert(level);
+    }
+    pub fn level(&self, lint: Lint) -> Level {
+        self.levels
+            .get(&lint)
+            .copied()
+            .unwrap_or(lint.default_level())
+    }
+}
+
+/// Runs the lints over the program, and returns their diagnostics as warnings, or as errors for
+/// the denied lints.
+///
+/// The levels can be changed inside the source with comments:
+/// `// lint: allow(unused_variables, shadowing)` sets them for the statement after the comment,
+/// `// lint-file: deny(unreachable_code)` for the whole file. These take priority over `config`.
+pub fn lint(statements: &[ParseNode<Statement>], source: &str, config: &LintConfig) -> Vec<Error> {
+    let mut linter = Linter {
+        scopes: vec![Scope {
+            locals: vec![],
+            global: true,
+        }],
+        warnings: vec![],
+        skeletons: HashMap::new(),
+    };
+    linter.visit_statements(statements);
+    linter.warnings.extend(constant_errors(statements));
+
+    let mut ranges = StatementRanges(vec![]);
+    ranges.visit_statements(statements);
+    let mut statement_ranges = ranges.0;
+    statement_ranges.sort_by_key(|range| range.start);
+
+    let (attributes, mut diagnostics) = attributes(source, &statement_ranges);
+
+    for warning in linter.warnings {
+        let Some(lint) = Lint::of(&warning.error) else {
+            diagnostics.push(warning);
+            continue;
+        };
+        // the innermost attribute around the warning wins
+        let level = attributes
+            .iter()
+            .filter(|attr| attr.lint == lint && attr.scope.contains(&warning.range.start))
+            .min_by_key(|attr| attr.scope.len())
+            .map_or(config.level(lint), |attr| attr.level);
+        match level {
+            Level::Allow => {}
+            Level::Warn => diagnostics.push(warning.with_severity(Severity::Warning)),
+            Level::Deny => diagnostics.push(warning.with_severity(Severity::Error)),
+        }
+    }
+
+    diagnostics.sort_by_key(|e| e.range.start);
+    diagnostics
+}
+
+struct Attribute {
+    lint: Lint,
+    level: Level,
+    scope: Range<usize>,
+}
+
+// reads the lint attributes in the comments, unknown lint names are reported as warnings
+fn attributes(source: &str, statement_ranges: &[Range<usize>]) -> (Vec<Attribute>, Vec<Error>) {
+    let mut attributes = vec![];
+    let mut warnings = vec![];
+
+    for comment in Parser::new(source).comments() {
+        let text = &source[comment.clone()];
+        let text = text
+            .strip_prefix("//")
+            .or_else(|| text.strip_prefix("/*").and_then(|t| t.strip_suffix("*/")))
+            .unwrap_or(text)
+            .trim();
+
+        let (scope, text) = if let Some(text) = text.strip_prefix("lint-file:") {
+            (0..source.len(), text)
+        } else if let Some(text) = text.strip_prefix("lint:") {
+            // the statement right after the comment, which is the outermost one starting there
+            let next = statement_ranges
+                .iter()
+                .find(|range| range.start >= comment.end);
+            (next.cloned().unwrap_or(0..0), text)
+        } else {
+            continue;
+        };
+
+        let Some((level, names)) = text.trim().split_once('(') else {
+            continue;
+        };
+        let Some(level) = Level::from_name(level.trim()) else {
+            continue;
+        };
+        for name in names.trim_end().trim_end_matches(')').split(',') {
+            let name = name.trim();
+            match Lint::from_name(name) {
+                Some(lint) => attributes.push(Attribute {
+                    lint,
+                    level,
+                    scope: scope.clone(),
+                }),
+                None => warnings.push(
+                    Error::new(comment.clone(), ErrorType::UnknownLint(name.to_string()))
+                        .with_severity(Severity::Warning)
+                        .with_note(format!(
+                            "the lints are: {}",
+                            Lint::ALL.map(|lint| lint.name()).join(", ")
+                        )),
+                ),
+            }
+        }
+    }
+
+    (attributes, warnings)
+}
+
+// ranges of every statement, at any depth
+struct StatementRanges(Vec<Range<usize>>);
+impl Visitor for StatementRanges {
+    fn visit_statements(&mut self, statements: &[ParseNode<Statement>]) {
+        self.0
+            .extend(statements.iter().map(|stmt| stmt.range.clone()));
+        walk_statements(self, statements);
+    }
+}
+
+struct Local {
+    name: StringName,
+    range: Range<usize>,
+    used: bool,
+}
+struct Scope {
+    locals: Vec<Local>,
+    // variables of the outermost scope can be used by other files, so they are never unused
+    global: bool,
+}
+
+struct Linter {
+    scopes: Vec<Scope>,
+    warnings: Vec<Error>,
+    // the first declared name of every confusable skeleton
+    skeletons: HashMap<String, (StringName, Range<usize>)>,
+}
+impl Linter {
+    fn warn(&mut self, error: Error) {
+        self.warnings.push(error);
+    }
+    fn lookup(&mut self, name: StringName) -> Option<&mut Local> {
+        self.scopes
+            .iter_mut()
+            .rev()
+            .find_map(|scope| scope.locals.iter_mut().rev().find(|l| l.name == name))
+    }
+    fn declare(&mut self, name: StringName, range: &Range<usize>, used: bool) {
+        self.identifier(name, range);
+        if let Some(shadowed) = self.lookup(name) {
+            let shadowed = shadowed.range.clone();
+            if !name.as_str().starts_with('_') {
+                self.warn(
+                    Error::new(range.clone(), ErrorType::ShadowedVariable(name))
+                        .with_label(shadowed, "previously declared here"),
+                );
+            }
+        }
+        self.scopes.last_mut().unwrap().locals.push(Local {
+            name,
+            range: range.clone(),
+            used,
+        });
+    }
+    // names that can be mistaken for other ones
+    fn identifier(&mut self, name: StringName, range: &Range<usize>) {
+        let ident = name.as_str();
+        if !ident.is_ascii() && !ident.is_single_script() {
+            self.warn(
+                Error::new(range.clone(), ErrorType::MixedScriptIdent(name)).with_note(
+                    "letters of different scripts can look the same, such as the latin 'a' and \
+                     the cyrillic 'а'",
+                ),
+            );
+        }
+
+        if has_stray_joiner(ident) {
+            self.warn(
+                Error::new(range.clone(), ErrorType::InvisibleJoiner(name)).with_note(
+                    "a zero-width joiner or non-joiner only changes how the letters around it \
+                     are drawn, so the name looks like the one without it",
+                ),
+            );
+        }
+
+        let skeleton: String = skeleton(ident).collect();
+        match self.skeletons.get(&skeleton) {
+            // ascii names that look alike (such as `l1` and `ll`) are left alone
+            Some((first, first_range))
+                if *first != name && !(ident.is_ascii() && first.as_str().is_ascii()) =>
+            {
+                let error = Error::new(range.clone(), ErrorType::ConfusableIdent(name, *first))
+                    .with_label(first_range.clone(), format!("'{}' is declared here", first));
+                self.warn(error);
+            }
+            Some(_) => {}
+            None => {
+                self.skeletons.insert(skeleton, (name, range.clone()));
+            }
+        }
+    }
+    fn scope(&mut self, lint_fn: impl FnOnce(&mut Self)) {
+        self.scopes.push(Scope {
+            locals: vec![],
+            global: false,
+        });
+        lint_fn(self);
+        let scope = self.scopes.pop().unwrap();
+
+        for local in scope.locals {
+            if local.used || local.name.as_str().starts_with('_') {
+                continue;
+            }
+            let replacement = format!("_{}", local.name);
+            self.warn(
+                Error::new(local.range.clone(), ErrorType::UnusedVariable(local.name))
+                    .with_suggestion(
+                        local.range,
+                        replacement,
+                        "if this is intentional, prefix it with an underscore",
+                    ),
+            );
+        }
+    }
+    fn function(&mut self, decl: &FuncDecl, range: &Range<usize>) {
+        // the parameters and the body share the same scope
+        self.scope(|l| match &decl.block.data {
+            FuncBlock::Block(block) => {
+                for param in &decl.params {
+                    l.visit_var_decl(&param.data, &param.range);
+                }
+                walk_block(l, block, &decl.block.range)
+            }
+            FuncBlock::ReturnExpr(_) => walk_func_decl(l, decl, range),
+        });
+    }
+    fn unreachable_code(&mut self, statements: &[ParseNode<Statement>]) {
+        let jump = statements.iter().position(|stmt| {
+            matches!(
+                stmt.data,
+                Statement::Break | Statement::Continue | Statement::Return(_) | Statement::Throw(_)
+            )
+        });
+        let Some(jump) = jump else {
+            return;
+        };
+        let (Some(first), Some(last)) = (statements.get(jump + 1), statements.last()) else {
+            return;
+        };
+        self.warn(
+            Error::new(first.start()..last.end(), ErrorType::UnreachableCode).with_label(
+                statements[jump].range.clone(),
+                "any code after this is never run",
+            ),
+        );
+    }
+    fn loop_handlers(&mut self, statement: &WhileStatement) {
+        let handlers = [
+            (Keyword::OnBreak, &statement.on_break),
+            (Keyword::OnContinue, &statement.on_continue),
+        ];
+        for (keyword, handler) in handlers {
+            let Some(handler) = handler else {
+                continue;
+            };
+            if !handler.data.statements.is_empty() {
+                continue;
+            }
+            // removes the handler up to the end of what is before it
+            let previous_end = handlers
+                .iter()
+                .filter_map(|(_, other)| other.as_ref().map(|o| o.end()))
+                .chain([statement.loop_block.end()])
+                .filter(|end| *end <= handler.start())
+                .max()
+                .unwrap_or(handler.start());
+            self.warn(
+                Error::new(handler.range.clone(), ErrorType::EmptyLoopHandler(keyword))
+                    .with_suggestion(
+                        previous_end..handler.end(),
+                        "",
+                        format!("remove the '{}' block", keyword),
+                    ),
+            );
+        }
+    }
+}
+
+// a zero-width joiner or non-joiner that can't change how the name is drawn. they're needed
+// between the letters of some scripts, such as Persian or Devanagari, but never next to an ascii
+// character, another joiner or the end of the name
+fn has_stray_joiner(ident: &str) -> bool {
+    let is_joiner = |c: char| matches!(c, '\u{200C}' | '\u{200D}');
+    let is_letter = |c: Option<char>| c.is_some_and(|c| !c.is_ascii() && !is_joiner(c));
+    // every char with the ones around it, `None` past the ends
+    let chars: Vec<Option<char>> = std::iter::once(None)
+        .chain(ident.chars().map(Some))
+        .chain(std::iter::once(None))
+        .collect();
+    chars
+        .windows(3)
+        .any(|w| w[1].is_some_and(is_joiner) && !(is_letter(w[0]) && is_letter(w[2])))
+}
+
+impl Visitor for Linter {
+    fn visit_statements(&mut self, statements: &[ParseNode<Statement>]) {
+        self.unreachable_code(statements);
+
+        // functions are hoisted, and their bodies can use anything declared in the same scope
+        let mut functions = vec![];
+        for stmt in statements {
+            if let Statement::Declaration(Declaration::Func(decl)) = &stmt.data {
+                self.declare(decl.name.data, &decl.name.range, true);
+                functions.push((decl, &stmt.range));
+            } else {
+                self.visit_statement(&stmt.data, &stmt.range);
+            }
+        }
+        for (decl, range) in functions {
+            self.function(decl, range);
+        }
+    }
+    fn visit_func_decl(&mut self, decl: &FuncDecl, range: &Range<usize>) {
+        // declarations in statement lists are hoisted by `visit_statements` instead
+        self.declare(decl.name.data, &decl.name.range, true);
+        self.function(decl, range);
+    }
+    fn visit_block(&mut self, block: &Block, range: &Range<usize>) {
+        self.scope(|l| walk_block(l, block, range));
+    }
+    // the caught error and the block share the same scope
+    fn visit_catch_block(&mut self, block: &CatchBlock, _range: &Range<usize>) {
+        self.scope(|l| {
+            l.visit_var_name(block.name.data, &block.name.range);
+            walk_block(l, &block.block.data, &block.block.range);
+        });
+    }
+    fn visit_var_name(&mut self, name: Option<StringName>, range: &Range<usize>) {
+        if let Some(name) = name {
+            let global = self.scopes.last().unwrap().global;
+            self.declare(name, range, global);
+        }
+    }
+    fn visit_variable(&mut self, name: StringName, _range: &Range<usize>) {
+        if let Some(local) = self.lookup(name) {
+            local.used = true;
+        }
+    }
+    fn visit_assign(&mut self, assign: &Assign, range: &Range<usize>) {
+        // a plain assignment only writes to the variable
+        match (&assign.left.data, assign.operator) {
+            (Expression::Variable(_), None) => {
+                self.visit_expression(&assign.right.data, &assign.right.range)
+            }
+            _ => walk_assign(self, assign, range),
+        }
+    }
+    fn visit_while_statement(&mut self, statement: &WhileStatement, range: &Range<usize>) {
+        let mut condition = &statement.condition;
+        while let Expression::Grouping(inner) = &condition.data {
+            condition = inner;
+        }
+        if matches!(condition.data, Expression::Bool(true)) {
+            let mut exits = LoopExits {
+                loop_depth: 0,
+                found: false,
+            };
+            exits.visit_block(&statement.loop_block.data, &statement.loop_block.range);
+            if !exits.found {
+                self.warn(
+                    Error::new(
+                        range.start..statement.condition.end(),
+                        ErrorType::InfiniteLoop,
+                    )
+                    .with_note("the loop never ends, unless an error stops the program"),
+                );
+            }
+        }
+        self.loop_handlers(statement);
+        walk_while_statement(self, statement, range);
+    }
+    fn visit_binary(&mut self, binary: &Binary, range: &Range<usize>) {
+        let comparison = matches!(
+            binary.operator,
+            SymbolKeyword::Symbol(
+                Symbol::Eq
+                    | Symbol::NotEq
+                    | Symbol::Less
+                    | Symbol::LessEq
+                    | Symbol::Greater
+                    | Symbol::GreaterEq
+            )
+        );
+        if comparison
+            && is_pure(&binary.left.data)
+            && binary.left.data.to_string() == binary.right.data.to_string()
+        {
+            self.warn(
+                Error::new(range.clone(), ErrorType::SelfComparison)
+                    .with_label(binary.left.range.clone(), "this value")
+                    .with_label(binary.right.range.clone(), "is compared with itself")
+                    .with_note("the result is always the same, unless the value is a NaN real"),
+            );
+        }
+        walk_binary(self, binary, range);
+    }
+}
+
+// whether evaluating the expression twice gives the same value, without side effects
+fn is_pure(expr: &Expression) -> bool {
+    match expr {
+        Expression::None
+        | Expression::Bool(_)
+        | Expression::Int(_)
+        | Expression::Real(_)
+        | Expression::String(_)
+        | Expression::Char(_)
+        | Expression::Variable(_) => true,
+        Expression::Grouping(expr) => is_pure(&expr.data),
+        Expression::Unary(unary) => is_pure(&unary.operand.data),
+        Expression::Binary(binary) => is_pure(&binary.left.data) && is_pure(&binary.right.data),
+        Expression::Suffix(suffix) => match &suffix.suffix {
+            SuffixType::Property(_) => is_pure(&suffix.node.data),
+            SuffixType::Index(index) => is_pure(&suffix.node.data) && is_pure(&index.data),
+            SuffixType::Call(_) => false,
+        },
+        Expression::Array(_) | Expression::Dictionary(_) | Expression::Assign(_) => false,
+    }
+}
+
+// finds a `break`, `return` or `throw` that leaves the loop
+struct LoopExits {
+    loop_depth: usize, // loops inside the checked one
+    found: bool,
+}
+impl Visitor for LoopExits {
+    fn visit_statement(&mut self, statement: &Statement, range: &Range<usize>) {
+        match statement {
+            Statement::Break if self.loop_depth == 0 => self.found = true,
+            Statement::Return(_) | Statement::Throw(_) => self.found = true,
+            _ => walk_statement(self, statement, range),
+        }
+    }
+    fn visit_while_statement(&mut self, statement: &WhileStatement, _range: &Range<usize>) {
+        self.loop_depth += 1;
+        self.visit_block(&statement.loop_block.data, &statement.loop_block.range);
+        self.loop_depth -= 1;
+
+        for block in [&statement.on_break, &statement.on_continue]
+            .into_iter()
+            .flatten()
+        {
+            self.visit_block(&block.data, &block.range);
+        }
+    }
+    // a return inside of another function doesn't leave the loop
+    fn visit_func_decl(&mut self, _decl: &FuncDecl, _range: &Range<usize>) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn codes(source: &str) -> Vec<&'static str> {
+        diagnostics(source, &LintConfig::new())
+            .into_iter()
+            .map(|(code, _)| code)
+            .collect()
+    }
+    fn diagnostics(source: &str, config: &LintConfig) -> Vec<(&'static str, Severity)> {
+        let statements = Parser::new(source).parse().unwrap();
+        lint(&statements, source, config)
+            .iter()
+            .map(|warning| (warning.error.code(), warning.severity))
+            .collect()
+    }
+    const NONE: [&str; 0] = [];
+
+    #[test]
+    fn unused_variables() {
+        assert_eq!(codes("{ let a = 1; }"), ["W0001"]);
+        assert_eq!(codes("func f(a) { return 1; }"), ["W0001"]);
+        assert_eq!(codes("{ let a = 1; a = 2; }"), ["W0001"]);
+
+        assert_eq!(codes("{ let a = 1; println(a); }"), NONE);
+        assert_eq!(codes("{ let _a = 1; }"), NONE);
+        assert_eq!(codes("func f(_a) { return 1; }"), NONE);
+        // other files can use the top level variables
+        assert_eq!(codes("let a = 1;"), NONE);
+        // the function is hoisted
+        assert_eq!(codes("{ func f() -> g() func g() -> f() f(); }"), NONE);
+    }
+
+    #[test]
+    fn shadowing() {
+        assert_eq!(codes("{ let a = 1; { let a = 2; a; } a; }"), ["W0002"]);
+        assert_eq!(codes("let a = 1;\nfunc f(a) -> a"), ["W0002"]);
+
+        assert_eq!(codes("{ let a = 1; a; } { let a = 2; a; }"), NONE);
+        assert_eq!(codes("let _a = 1;\nfunc f(_a) -> _a"), NONE);
+    }
+
+    #[test]
+    fn unreachable_code() {
+        assert_eq!(codes("func f() { return 1; f(); }"), ["W0003"]);
+        assert_eq!(codes("while x { break;\nx;\nx; }"), ["W0003"]);
+
+        assert_eq!(codes("func f() { if true { return 1; } f(); }"), NONE);
+        assert_eq!(codes("func f() { f(); return 1; }"), NONE);
+    }
+
+    #[test]
+    fn while_true() {
+        assert_eq!(codes("while true { }"), ["W0004"]);
+        assert_eq!(codes("while (true) { }"), ["W0004"]);
+        // the break only leaves the inner loop
+        assert_eq!(codes("while true { while x { break; } }"), ["W0004"]);
+        // the return only leaves the inner function
+        assert_eq!(
+            codes("while true { func f() { return 1; } f(); }"),
+            ["W0004"]
+        );
+
+        assert_eq!(codes("while true { break; }"), NONE);
+        assert_eq!(codes("func f() { while true { return 1; } }"), NONE);
+        assert_eq!(codes("while true { throw 1; }"), NONE);
+        assert_eq!(codes("while x { }"), NONE);
+    }
+
+    #[test]
+    fn empty_loop_handlers() {
+        assert_eq!(codes("while x { } onbreak { }"), ["W0005"]);
+        assert_eq!(
+            codes("while x { } onbreak { } oncontinue { }"),
+            ["W0005", "W0005"]
+        );
+
+        assert_eq!(codes("while x { } onbreak { x; } oncontinue { x; }"), NONE);
+    }
+
+    #[test]
+    fn self_comparison() {
+        assert_eq!(codes("x == x;"), ["W0006"]);
+        assert_eq!(codes("a.b[1] < (a.b[1]);"), ["W0006"]);
+
+        assert_eq!(codes("x == y;"), NONE);
+        assert_eq!(codes("x + x;"), NONE);
+        // the calls can give different values
+        assert_eq!(codes("f() == f();"), NONE);
+    }
+
+    #[test]
+    fn config_levels() {
+        let source = "{ let a = 1; }";
+        let mut config = LintConfig::new();
+        assert_eq!(diagnostics(source, &config), [("W0001", Severity::Warning)]);
+        config.set(Lint::UnusedVariables, Level::Deny);
+        assert_eq!(diagnostics(source, &config), [("W0001", Severity::Error)]);
+        config.set(Lint::UnusedVariables, Level::Allow);
+        assert_eq!(diagnostics(source, &config), []);
+
+        // a set level isn't replaced by a default one
+        config.set_default(Lint::UnusedVariables, Level::Deny);
+        assert_eq!(diagnostics(source, &config), []);
+    }
+
+    #[test]
+    fn comment_levels() {
+        // only the statement after the comment
+        let source = "// lint: allow(unused_variables)\n{ let a = 1; }\n{ let b = 1; }";
+        assert_eq!(codes(source), ["W0001"]);
+
+        let source = "// lint-file: deny(unused_variables, shadowing)\n\
+                      { let a = 1; { let a = 2; a; } }";
+        assert_eq!(
+            diagnostics(source, &LintConfig::new()),
+            [("W0001", Severity::Error), ("W0002", Severity::Error)]
+        );
+
+        // the comments take priority over the config, and the innermost one wins
+        let source = "/* lint-file: allow(unused_variables) */\n\
+                      {\n\
+                          // lint: warn(unused_variables)\n\
+                          { let a = 1; }\n\
+                          { let b = 1; }\n\
+                      }";
+        let mut config = LintConfig::new();
+        config.set(Lint::UnusedVariables, Level::Deny);
+        assert_eq!(diagnostics(source, &config), [("W0001", Severity::Warning)]);
+
+        assert_eq!(
+            codes("// lint: allow(unused_vars)\n{ let a = 1; }"),
+            ["W0007", "W0001"]
+        );
+    }
+
+    #[test]
+    fn joiners_between_letters_are_allowed() {
+        // "mi\u{200C}khaham", persian for "I want"
+        assert_eq!(
+            codes("let \u{645}\u{6CC}\u{200C}\u{62E}\u{648}\u{627}\u{647}\u{645} = 1;"),
+            Vec::<&str>::new()
+        );
+    }
+
+    #[test]
+    fn stray_joiners_are_reported() {
+        assert_eq!(codes("let total\u{200D}count = 1;"), ["W0011"]);
+        assert_eq!(codes("let total\u{200C} = 1;"), ["W0011"]);
+        assert_eq!(codes("let \u{645}\u{200C}\u{200C}\u{62E} = 1;"), ["W0011"]);
+    }
+
+    #[test]
+    fn joiners_outside_of_identifiers_are_errors() {
+        let error = Parser::new("let a = 1 \u{200D}+ 2;").parse().unwrap_err();
+        assert_eq!(error.error.code(), "E0039");
+    }
+}
@@ -1,63 +1,612 @@
-use std::{env, process::exit};
+use std::{
+    env, fs,
+    io::{self, IsTerminal},
+    process::exit,
+    thread,
+};
 
-use parser::error::*;
-use parser::*;
+use lox_rs::ast_json::*;
+use lox_rs::check::*;
+use lox_rs::error_json::*;
+use lox_rs::explain::*;
+use lox_rs::formatter::*;
+use lox_rs::interpreter::{error::RuntimeError, value::Value, Interpreter};
+use lox_rs::lint::*;
+use lox_rs::optimize::optimize;
+use lox_rs::parser::error::*;
+use lox_rs::parser::parse_node::{ParseNode, Statement};
+use lox_rs::source::*;
+use lox_rs::source_map::*;
+use lox_rs::string_name::StringName;
 
-mod parser;
-mod source;
-mod string_name;
+mod repl;
+use repl::repl;
 
-fn main() {
-    let mut args = env::args();
-    let _ = args.next();
+const USAGE: &str = "\
+Usage: lox-rs [command] [options] [file] [-- args...]
+
+Commands:
+    run [file] [args...]    run a program, the default command. starts the REPL without one
+    build [file] -o <path>  check a program and write its --emit output
+    check [files...]        report the errors and lints of the programs without running them
+    fmt [files...]          format the files in place, or stdin to stdout
+    repl                    read and run lines interactively
+    parse [file]            print the syntax tree of a program
+    explain <code>          print the description of an error code, such as E0007
+
+The file is '-' for stdin. The arguments after the file of 'run' (or after '--') are passed to
+the program in the 'args' array.
+
+Options:
+    -e <code>                       use the code as the program instead of a file
+    --emit <ast|ast-json>           what 'build' and 'parse' write, 'ast-json' by default for
+                                    'build' and 'ast' for 'parse'
+    -o <path>                       where 'build' writes to, stdout by default
+    --error-format=<format>         'human', 'json' or 'sarif'
+    --allow, --warn, --deny <lint>  set the level of a lint
+    --check                         with 'fmt', only report the unformatted files
+    --width <n>                     with 'fmt', the maximum line width
+    -h, --help                      print this help
+    -V, --version                   print the version
+
+Exit codes:
+    0   success
+    1   'fmt --check' found unformatted files
+    64  invalid command line
+    65  the program has syntax or compile errors
+    70  the program failed at runtime
+    74  a file couldn't be read or written
+    101 lox-rs itself failed, which is a bug
+";
+
+// exit codes, the ones for errors are from BSD `sysexits.h`
+const EXIT_UNFORMATTED: i32 = 1;
+const EXIT_USAGE: i32 = 64;
+const EXIT_COMPILE: i32 = 65;
+const EXIT_RUNTIME: i32 = 70;
+const EXIT_IO: i32 = 74;
+// the code of a panic in rust
+const EXIT_INTERNAL: i32 = 101;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Run,
+    Build,
+    Check,
+    Fmt,
+    Repl,
+    Parse,
+    Explain,
+}
+impl Command {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "run" => Command::Run,
+            "build" | "b" => Command::Build,
+            "check" => Command::Check,
+            "fmt" => Command::Fmt,
+            "repl" => Command::Repl,
+            "parse" => Command::Parse,
+            "explain" => Command::Explain,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Emit {
+    Ast,
+    AstJson,
+}
+
+struct Options {
+    command: Command,
+    // file paths, or the error code of `explain`
+    positional: Vec<String>,
+    code: Option<String>,
+    script_args: Vec<String>,
+    emit: Option<Emit>,
+    out_path: Option<String>,
+    error_format: ErrorFormat,
+    lints: LintConfig,
+    check: bool,
+    width: Option<usize>,
+}
+impl Options {
+    fn parse(args: impl Iterator<Item = String>) -> Self {
+        let mut args = args.peekable();
+        let command = args.peek().and_then(|arg| Command::from_name(arg));
+        if command.is_some() {
+            args.next();
+        }
+        let mut options = Options {
+            command: command.unwrap_or(Command::Run),
+            positional: vec![],
+            code: None,
+            script_args: vec![],
+            emit: None,
+            out_path: None,
+            error_format: ErrorFormat::Human,
+            lints: LintConfig::new(),
+            check: false,
+            width: None,
+        };
+        let command = options.command;
+        let only = |commands: &[Command], flag: &str| {
+            if !commands.contains(&command) {
+                usage_error(&format!("'{}' can't be used with this command", flag));
+            }
+        };
 
-    let mut file: Option<String> = None;
-    let mut out_path: Option<String> = None;
-    let mut build = false;
-
-    while let Some(arg) = args.next() {
-        match arg.as_str() {
-            "build" | "b" => build = true,
-            "-o" => {
-                out_path = match args.next() {
-                    Some(path) => Some(path),
-                    None => {
-                        println!("No output path specified");
-                        exit(-1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-h" | "--help" => {
+                    print!("{}", USAGE);
+                    exit(0);
+                }
+                "-V" | "--version" => {
+                    println!("lox-rs {}", env!("CARGO_PKG_VERSION"));
+                    exit(0);
+                }
+                "--" => {
+                    only(&[Command::Run], &arg);
+                    options.script_args.extend(args);
+                    break;
+                }
+                "-e" => {
+                    only(&[Command::Run, Command::Build, Command::Parse], &arg);
+                    let Some(code) = args.next() else {
+                        usage_error("Expected code after '-e'");
+                    };
+                    options.code = Some(code);
+                }
+                "--emit" => {
+                    only(&[Command::Build, Command::Parse], &arg);
+                    options.emit = match args.next().as_deref() {
+                        Some("ast") => Some(Emit::Ast),
+                        Some("ast-json") => Some(Emit::AstJson),
+                        Some(kind) => usage_error(&format!(
+                            "Unknown emit kind '{}', expected 'ast' or 'ast-json'",
+                            kind
+                        )),
+                        None => usage_error("Expected an emit kind after '--emit'"),
                     }
                 }
+                "-o" => {
+                    only(&[Command::Build], &arg);
+                    let Some(path) = args.next() else {
+                        usage_error("Expected an output path after '-o'");
+                    };
+                    options.out_path = Some(path);
+                }
+                "--allow" | "--warn" | "--deny" => {
+                    lint_level(&arg, args.next(), &mut options.lints)
+                }
+                "--check" => {
+                    only(&[Command::Fmt], &arg);
+                    options.check = true;
+                }
+                "--width" => {
+                    only(&[Command::Fmt], &arg);
+                    let Some(width) = args.next().and_then(|w| w.parse().ok()) else {
+                        usage_error("Expected a line width after '--width'");
+                    };
+                    options.width = Some(width);
+                }
+                _ if arg.starts_with("--error-format=") => {
+                    options.error_format = error_format(&arg);
+                }
+                _ if arg.starts_with('-') && arg != "-" => {
+                    usage_error(&format!("Unknown option '{}'", arg))
+                }
+                _ => options.positional.push(arg),
             }
-            _ => {
-                file = Some(arg);
+            // everything after the program to run is passed to it
+            if command == Command::Run && (options.code.is_some() || !options.positional.is_empty())
+            {
+                if args.peek().map(String::as_str) == Some("--") {
+                    args.next();
+                }
+                options.script_args.extend(args);
                 break;
             }
         }
+        options
+    }
+    // the program of `run`, `build` and `parse`
+    fn input(&self) -> SourceInput {
+        match (&self.code, self.positional.as_slice()) {
+            (Some(code), []) => SourceInput::Inline(code.clone()),
+            (None, [path]) => SourceInput::from_arg(path),
+            (None, []) => usage_error("No input file specified"),
+            _ => usage_error("Expected a single program"),
+        }
+    }
+}
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("{}", message);
+    eprintln!("Run 'lox-rs --help' for usage");
+    exit(EXIT_USAGE);
+}
+
+// the interpreter recurses for every call and nested expression, so it gets a larger stack than
+// the main thread has
+const STACK_SIZE: usize = 64 * 1024 * 1024;
+
+fn main() {
+    let mut args = env::args();
+    let _ = args.next();
+    let options = Options::parse(args);
+
+    let command = thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(|| run(options))
+        .expect("failed to start the interpreter thread");
+    // a panic is a bug of lox-rs, not an error of the program, and its message is already printed
+    if command.join().is_err() {
+        eprintln!(
+            "Internal error: lox-rs crashed. This is a bug, please report it with the program \
+             that caused it"
+        );
+        exit(EXIT_INTERNAL);
+    }
+}
+
+fn run(options: Options) {
+    match options.command {
+        Command::Run if options.code.is_none() && options.positional.is_empty() => repl(options),
+        Command::Run => run_command(options),
+        Command::Build | Command::Parse => build_command(options),
+        Command::Check => check_command(options),
+        Command::Fmt => format_command(options),
+        Command::Repl => repl(options),
+        Command::Explain => explain_command(options),
+    }
+}
+
+// the interpreter with the globals of every program, `args` holds the arguments of the script
+fn interpreter(script_args: &[String]) -> Interpreter {
+    let mut interpreter = Interpreter::new();
+    let args = script_args
+        .iter()
+        .map(|arg| Value::String(arg.as_str().into()))
+        .collect();
+    interpreter.define("args", Value::array(args));
+    interpreter
+}
+
+fn load(input: &SourceInput) -> String {
+    match input.load() {
+        Ok(source) => source,
+        Err(err) => {
+            eprintln!("{}", err);
+            exit(EXIT_IO);
+        }
     }
+}
 
-    let source = if let Some(path) = file {
-        if let Ok(s) = std::fs::read_to_string(&path) {
-            s
-        } else {
-            println!("Failed to read file '{}'", path);
-            exit(-1);
+// parses and checks the program, exits if it has errors
+fn compile(
+    sources: &SourceMap,
+    file: FileId,
+    reporter: &mut Reporter,
+    options: &Options,
+    globals: &[StringName],
+) -> Vec<ParseNode<Statement>> {
+    let nodes = match parse_file(sources, file) {
+        Ok(nodes) => nodes,
+        Err(err) => {
+            reporter.report(&err, sources);
+            reporter.finish();
+            exit(EXIT_COMPILE);
         }
+    };
+    let errors = check(&nodes, sources, file, &options.lints, globals);
+    for err in &errors {
+        reporter.report(err, sources);
+    }
+    reporter.finish();
+    if errors.iter().any(|err| err.severity == Severity::Error) {
+        exit(EXIT_COMPILE);
+    }
+    nodes
+}
+
+// `run [file | -e <code>] [args...]`
+fn run_command(options: Options) {
+    let input = options.input();
+    let mut sources = SourceMap::new();
+    let file_id = sources.add(input.name(), load(&input));
+
+    let mut interpreter = interpreter(&options.script_args);
+    let mut reporter = Reporter::new(options.error_format);
+    let nodes = compile(
+        &sources,
+        file_id,
+        &mut reporter,
+        &options,
+        interpreter.globals(),
+    );
+    let nodes = optimize(nodes);
+
+    if let Err(err) = interpreter.run(&nodes, &sources, file_id) {
+        print_runtime_error(&err, &sources);
+        exit(EXIT_RUNTIME);
+    }
+}
+
+// the source of the error is highlighted, followed by the trace
+fn print_runtime_error(err: &RuntimeError, sources: &SourceMap) {
+    let file = err
+        .trace
+        .first()
+        .map_or(FileId::default(), |frame| frame.span.file);
+    let mut message = String::new();
+    err.display_trace(sources, &mut message).unwrap();
+    print_error(err, sources.get(file));
+    eprint!("{}", message);
+}
+
+// `build [file] [-o <path>] [--emit <kind>]` and `parse [file] [--emit <kind>]`
+// `build` checks the program before writing it, `parse` only needs it to be syntactically valid
+fn build_command(options: Options) {
+    let input = options.input();
+    let mut sources = SourceMap::new();
+    let file = sources.add(input.name(), load(&input));
+    let mut reporter = Reporter::new(options.error_format);
+
+    let (nodes, default_emit) = if options.command == Command::Build {
+        let globals = interpreter(&[]).globals().to_vec();
+        (
+            compile(&sources, file, &mut reporter, &options, &globals),
+            Emit::AstJson,
+        )
     } else {
-        println!("No input file specified");
-        exit(-1);
+        match parse_file(&sources, file) {
+            Ok(nodes) => (nodes, Emit::Ast),
+            Err(err) => {
+                reporter.report(&err, &sources);
+                reporter.finish();
+                exit(EXIT_COMPILE);
+            }
+        }
     };
 
-    let mut parser = Parser::new(&source);
-    let nodes = parser.parse();
-    match nodes {
-        Ok(nodes) => {
-            for node in nodes {
-                println!("{:?}", node);
+    let output = match options.emit.unwrap_or(default_emit) {
+        Emit::Ast => nodes
+            .iter()
+            .map(|node| format!("{:?}\n", node))
+            .collect::<String>(),
+        Emit::AstJson => format!("{:#}\n", ast_to_json(&nodes, sources.get(file))),
+    };
+    match &options.out_path {
+        Some(path) => {
+            if let Err(err) = fs::write(path, output) {
+                eprintln!("Failed to write '{}': {}", path, err);
+                exit(EXIT_IO);
             }
         }
-        Err(err) => {
-            let mut message = String::new();
-            err.display(&source, &mut message).unwrap();
+        None => print!("{}", output),
+    }
+}
+
+// colored when stderr is a terminal, unless `NO_COLOR` is set
+fn print_error(err: &impl DisplayError, file: &SourceFile) {
+    let color = io::stderr().is_terminal() && env::var_os("NO_COLOR").is_none();
+    let mut message = String::new();
+    err.display_styled(file, color, &mut message).unwrap();
+    eprintln!("{}", message);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ErrorFormat {
+    Human,
+    Json,
+    Sarif,
+}
+
+// parses `--error-format=<human|json|sarif>`
+fn error_format(arg: &str) -> ErrorFormat {
+    match arg.trim_start_matches("--error-format=") {
+        "human" => ErrorFormat::Human,
+        "json" => ErrorFormat::Json,
+        "sarif" => ErrorFormat::Sarif,
+        format => {
+            usage_error(&format!(
+                "Unknown error format '{}', expected 'human', 'json' or 'sarif'",
+                format
+            ));
+        }
+    }
+}
+
+// writes the diagnostics to stderr in the chosen format.
+// json prints one object per line, sarif collects them into one log printed by `finish`
+struct Reporter {
+    format: ErrorFormat,
+    sarif: SarifLog,
+}
+impl Reporter {
+    fn new(format: ErrorFormat) -> Self {
+        Self {
+            format,
+            sarif: SarifLog::new(),
+        }
+    }
+    // the error is shown in its file of `sources`
+    fn report(&mut self, err: &Error, sources: &SourceMap) {
+        match self.format {
+            ErrorFormat::Human => print_error(err, sources.get(err.file)),
+            ErrorFormat::Json => eprintln!("{}", error_to_json(err, sources)),
+            ErrorFormat::Sarif => self.sarif.add(err, sources),
+        }
+    }
+    // only human readable output has the headers between files
+    fn header(&self, message: &str) {
+        if self.format == ErrorFormat::Human {
             eprintln!("{}", message);
         }
     }
+    fn finish(&self) {
+        if self.format == ErrorFormat::Sarif {
+            eprintln!("{:#}", self.sarif.to_json());
+        }
+    }
+}
+
+// `fmt [--check] [--width <n>] [files...]`
+// formats the files in place, or stdin to stdout when no files (or `-`) are given.
+// with `--check` nothing is written, and it exits with 1 if any input isn't formatted
+fn format_command(options: Options) {
+    let mut format_options = FormatOptions::default();
+    if let Some(width) = options.width {
+        format_options.max_width = width;
+    }
+    let mut reporter = Reporter::new(options.error_format);
+    let files = options.positional;
+
+    if files.is_empty() || files == ["-"] {
+        let mut sources = SourceMap::new();
+        let file = sources.add("<stdin>", load(&SourceInput::Stdin));
+        let source = sources.get(file).source();
+        let formatted = match format(source, &format_options) {
+            Ok(formatted) => formatted,
+            Err(err) => {
+                reporter.report(&err.in_file(file), &sources);
+                reporter.finish();
+                exit(EXIT_COMPILE);
+            }
+        };
+        if options.check {
+            if formatted != source {
+                eprintln!("stdin is not formatted");
+                exit(EXIT_UNFORMATTED);
+            }
+        } else {
+            print!("{}", formatted);
+        }
+        return;
+    }
+
+    // the exit code of the first kind of failure, io errors before syntax errors
+    let mut io_failed = false;
+    let mut syntax_failed = false;
+    let mut unformatted = false;
+    let mut sources = SourceMap::new();
+
+    for path in files {
+        let source = match SourceInput::from_arg(&path).load() {
+            Ok(source) => source,
+            Err(err) => {
+                eprintln!("{}", err);
+                io_failed = true;
+                continue;
+            }
+        };
+        let formatted = match format(&source, &format_options) {
+            Ok(formatted) => formatted,
+            Err(err) => {
+                reporter.header(&format!("Failed to format '{}'", path));
+                let file = sources.add(path, source);
+                reporter.report(&err.in_file(file), &sources);
+                syntax_failed = true;
+                continue;
+            }
+        };
+        if formatted == source {
+            continue;
+        }
+        if options.check {
+            println!("{}", path);
+            unformatted = true;
+        } else if let Err(err) = fs::write(&path, formatted) {
+            eprintln!("Failed to write '{}': {}", path, err);
+            io_failed = true;
+        }
+    }
+
+    reporter.finish();
+    if io_failed {
+        exit(EXIT_IO);
+    }
+    if syntax_failed {
+        exit(EXIT_COMPILE);
+    }
+    if unformatted {
+        exit(EXIT_UNFORMATTED);
+    }
+}
+
+// `--allow <lint>`, `--warn <lint>` or `--deny <lint>`
+fn lint_level(flag: &str, name: Option<String>, lints: &mut LintConfig) {
+    let Some(name) = name else {
+        usage_error(&format!("Expected a lint name after '{}'", flag));
+    };
+    let Some(lint) = Lint::from_name(&name) else {
+        usage_error(&format!(
+            "Unknown lint '{}', expected one of: {}",
+            name,
+            Lint::ALL.map(|lint| lint.name()).join(", ")
+        ));
+    };
+    let level = Level::from_name(flag.trim_start_matches("--")).unwrap();
+    lints.set(lint, level);
+}
+
+// `check [files...]`
+// parses and runs the static analysis of every file without executing them.
+// prints all the diagnostics, and exits with 65 if any of them is an error
+fn check_command(options: Options) {
+    if options.positional.is_empty() {
+        usage_error("No input file specified");
+    }
+    let mut reporter = Reporter::new(options.error_format);
+    let globals = interpreter(&[]).globals().to_vec();
+    let mut sources = SourceMap::new();
+    let mut failed = false;
+    let mut has_errors = false;
+
+    for path in &options.positional {
+        let input = SourceInput::from_arg(path);
+        let source = match input.load() {
+            Ok(source) => source,
+            Err(err) => {
+                eprintln!("{}", err);
+                failed = true;
+                continue;
+            }
+        };
+        let file_id = sources.add(input.name(), source);
+        let file = sources.get(file_id);
+        let errors = check_file(&sources, file_id, &options.lints, &globals);
+        if !errors.is_empty() {
+            reporter.header(&format!("In '{}':", file.name()));
+        }
+        has_errors |= errors.iter().any(|err| err.severity == Severity::Error);
+        for err in &errors {
+            reporter.report(err, &sources);
+        }
+    }
+
+    reporter.finish();
+    if failed {
+        exit(EXIT_IO);
+    }
+    if has_errors {
+        exit(EXIT_COMPILE);
+    }
+}
+
+// `explain <code>`
+// prints the longer description of an error code, such as `E0007`
+fn explain_command(options: Options) {
+    let [code] = options.positional.as_slice() else {
+        usage_error("Expected one error code");
+    };
+    let Some(explanation) = explain(code) else {
+        eprintln!("Unknown error code '{}'", code);
+        exit(EXIT_USAGE);
+    };
+    println!("{}", explanation);
 }
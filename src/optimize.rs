@@ -0,0 +1,301 @@
+// constant folding, run on the syntax tree between `check` and the interpreter.
+// operators are applied with the functions of the interpreter, so a folded expression has the
+// same value it would have had at runtime. an expression that would fail is left as it is, and
+// fails when it runs like before
+
+use std::ops::Range;
+
+use crate::interpreter::error::RuntimeErrorType;
+use crate::interpreter::operator;
+use crate::interpreter::value::Value;
+use crate::parser::{error::*, parse_node::*, visit::*};
+
+/// Folds the operators applied to literals into their value, removes the groupings that don't
+/// change how the program runs, and removes the branches of `if` and `while` statements that
+/// never run.
+pub fn optimize(statements: Vec<ParseNode<Statement>>) -> Vec<ParseNode<Statement>> {
+    Folder { errors: vec![] }.fold_statements(statements)
+}
+
+/// The expressions of operators applied to literals that always fail when they run, such as an
+/// int overflow. Expressions in branches that never run are left out.
+pub fn constant_errors(statements: &[ParseNode<Statement>]) -> Vec<Error> {
+    let mut folder = Folder { errors: vec![] };
+    folder.fold_statements(statements.to_vec());
+    folder.errors
+}
+
+struct Folder {
+    errors: Vec<Error>,
+}
+impl Folder {
+    fn error(&mut self, range: &Range<usize>, error: RuntimeErrorType) {
+        self.errors.push(Error::new(
+            range.clone(),
+            ErrorType::ConstantError(error.to_string().into()),
+        ));
+    }
+    // the branch of the `else` block that runs, `None` when no branch does. the conditions that
+    // aren't constant, and the blocks after them, are kept
+    fn else_block(&mut self, block: Option<ParseNode<ElseBlock>>) -> Option<ParseNode<ElseBlock>> {
+        let ParseNode { range, data } = block?;
+        let statement = match data {
+            ElseBlock::Block(block) => {
+                let block = self.fold_block(block, &range);
+                return Some(ParseNode::new(range, ElseBlock::Block(block)));
+            }
+            ElseBlock::If(statement) => *statement,
+        };
+        let condition = fold_expr_node(self, statement.condition);
+        match constant(&condition.data) {
+            Some(value) if value.is_truthy() => {
+                let block = fold_block_node(self, statement.met_block);
+                Some(ParseNode::new(range, ElseBlock::Block(block.data)))
+            }
+            Some(_) => self.else_block(statement.else_block),
+            None => {
+                let statement = IfStatement {
+                    condition,
+                    met_block: fold_block_node(self, statement.met_block),
+                    else_block: self.else_block(statement.else_block),
+                };
+                Some(ParseNode::new(range, ElseBlock::If(statement.into())))
+            }
+        }
+    }
+    // the loop is only kept if its condition isn't always false. if it is, only `oncontinue`
+    // runs
+    fn while_statement(&mut self, statement: WhileStatement) -> Option<Statement> {
+        let condition = fold_expr_node(self, statement.condition);
+        if constant(&condition.data).is_some_and(|value| !value.is_truthy()) {
+            let block = fold_block_node(self, statement.on_continue?);
+            return Some(Statement::Block(block.data));
+        }
+        Some(Statement::While(WhileStatement {
+            condition,
+            loop_block: fold_block_node(self, statement.loop_block),
+            on_break: statement.on_break.map(|block| fold_block_node(self, block)),
+            on_continue: statement
+                .on_continue
+                .map(|block| fold_block_node(self, block)),
+        }))
+    }
+}
+
+impl Fold for Folder {
+    fn fold_statements(
+        &mut self,
+        statements: Vec<ParseNode<Statement>>,
+    ) -> Vec<ParseNode<Statement>> {
+        statements
+            .into_iter()
+            .filter_map(|stmt| {
+                let data = match stmt.data {
+                    // an `if` is the `else` block of nothing
+                    Statement::If(statement) => {
+                        let block =
+                            ParseNode::new(stmt.range.clone(), ElseBlock::If(statement.into()));
+                        match self.else_block(Some(block))?.data {
+                            ElseBlock::Block(block) => Statement::Block(block),
+                            ElseBlock::If(statement) => Statement::If(*statement),
+                        }
+                    }
+                    Statement::While(statement) => self.while_statement(statement)?,
+                    statement => self.fold_statement(statement, &stmt.range),
+                };
+                Some(ParseNode::new(stmt.range, data))
+            })
+            .collect()
+    }
+    fn fold_expression(&mut self, expr: Expression, range: &Range<usize>) -> Expression {
+        match expr {
+            // `(value.name)(args)` calls the property, where `value.name(args)` calls the method.
+            // `-(9223372036854775808)` overflows, where `-9223372036854775808` is in range
+            Expression::Grouping(inner)
+                if !matches!(
+                    &inner.data,
+                    Expression::Suffix(Suffix {
+                        suffix: SuffixType::Property(_),
+                        ..
+                    })
+                ) && !matches!(inner.data, Expression::Int(i) if i64::try_from(i).is_err()) =>
+            {
+                self.fold_expression(inner.data, &inner.range)
+            }
+            expr => fold_expression(self, expr, range),
+        }
+    }
+    fn fold_binary(&mut self, binary: Binary, range: &Range<usize>) -> Expression {
+        let left = fold_expr_box(self, binary.left);
+        let operator = match binary.operator {
+            SymbolKeyword::Symbol(symbol) => symbol,
+            // `and` and `or` are their left operand when it decides the result, and their right
+            // operand otherwise
+            SymbolKeyword::Keyword(keyword) => {
+                if let Some(value) = constant(&left.data) {
+                    let decided = match keyword {
+                        Keyword::And => !value.is_truthy(),
+                        _ => value.is_truthy(),
+                    };
+                    return if decided {
+                        left.data
+                    } else {
+                        self.fold_expression(binary.right.data, &binary.right.range)
+                    };
+                }
+                return Expression::Binary(Binary {
+                    left,
+                    right: fold_expr_box(self, binary.right),
+                    operator: binary.operator,
+                });
+            }
+        };
+        let right = fold_expr_box(self, binary.right);
+
+        if let (Some(a), Some(b)) = (constant(&left.data), constant(&right.data)) {
+            match operator::binary(operator, a, b) {
+                Ok(value) => {
+                    if let Some(expr) = literal(value, range) {
+                        return expr;
+                    }
+                }
+                Err(error) => self.error(range, error),
+            }
+        }
+        Expression::Binary(Binary {
+            left,
+            right,
+            operator: binary.operator,
+        })
+    }
+    fn fold_unary(&mut self, unary: Unary, range: &Range<usize>) -> Expression {
+        let operand = fold_expr_box(self, unary.operand);
+        // the operand of `-9223372036854775808` is out of range on its own, so it isn't constant
+        if let Some(value) = constant(&operand.data) {
+            match operator::unary(unary.operator, value) {
+                Ok(value) => {
+                    if let Some(expr) = literal(value, range) {
+                        return expr;
+                    }
+                }
+                Err(error) => self.error(range, error),
+            }
+        }
+        Expression::Unary(Unary {
+            operand,
+            operator: unary.operator,
+        })
+    }
+}
+
+// the value of a literal, or of a negated number literal, which is how negative numbers are
+// written. `None` for every other expression
+fn constant(expr: &Expression) -> Option<Value> {
+    Some(match expr {
+        Expression::None => Value::None,
+        Expression::Bool(b) => Value::Bool(*b),
+        Expression::Int(i) => Value::Int(i64::try_from(*i).ok()?),
+        Expression::Real(r) => Value::Real(*r),
+        Expression::String(s) => Value::String(s.as_str().into()),
+        Expression::Char(c) => Value::Char(*c),
+        Expression::Unary(Unary {
+            operator: Symbol::Sub,
+            operand,
+        }) => match operand.data {
+            Expression::Int(i) if i == i64::MIN.unsigned_abs() => Value::Int(i64::MIN),
+            Expression::Int(i) => Value::Int(-i64::try_from(i).ok()?),
+            Expression::Real(r) => Value::Real(-r),
+            _ => return None,
+        },
+        _ => return None,
+    })
+}
+
+// the expression of a value, negative numbers are negated literals.
+// `None` for values that have no literal
+fn literal(value: Value, range: &Range<usize>) -> Option<Expression> {
+    let negative = |operand| {
+        Expression::Unary(Unary {
+            operand: ParseNode::new(range.clone(), operand).into(),
+            operator: Symbol::Sub,
+        })
+    };
+    Some(match value {
+        Value::None => Expression::None,
+        Value::Bool(b) => Expression::Bool(b),
+        Value::Int(i) if i < 0 => negative(Expression::Int(i.unsigned_abs())),
+        Value::Int(i) => Expression::Int(i as u64),
+        Value::Real(r) if r.is_sign_negative() && !r.is_nan() => negative(Expression::Real(-r)),
+        Value::Real(r) => Expression::Real(r),
+        Value::String(s) => Expression::String(s.to_string()),
+        Value::Char(c) => Expression::Char(c),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::Interpreter;
+    use crate::parser::{unparse, Parser};
+    use crate::source_map::SourceMap;
+
+    fn optimized(source: &str) -> String {
+        unparse(&optimize(Parser::new(source).parse().unwrap()))
+    }
+    fn error_codes(source: &str) -> Vec<&'static str> {
+        let statements = Parser::new(source).parse().unwrap();
+        constant_errors(&statements)
+            .iter()
+            .map(|error| error.error.code())
+            .collect()
+    }
+
+    #[test]
+    fn folds_operators_on_literals() {
+        assert_eq!(optimized("let x = 1 + 2 * 3;"), "let x = 7;\n");
+        assert_eq!(optimized("let x = -(5) - 1;"), "let x = -6;\n");
+        assert_eq!(optimized("let s = \"a\" + \"b\";"), "let s = \"ab\";\n");
+        assert_eq!(optimized("let x = 7 / 2;"), "let x = 3;\n");
+        assert_eq!(optimized("let b = !(1 < 2);"), "let b = false;\n");
+    }
+
+    #[test]
+    fn removes_branches_that_never_run() {
+        assert_eq!(
+            optimized("if false { println(1); } else { println(2); }"),
+            "{\n    println(2);\n}\n"
+        );
+        assert_eq!(optimized("while false { println(1); }"), "");
+    }
+
+    #[test]
+    fn keeps_expressions_that_fail() {
+        assert_eq!(
+            optimized("let x = 9223372036854775807 + 1;"),
+            "let x = 9223372036854775807 + 1;\n"
+        );
+        assert_eq!(error_codes("let x = 9223372036854775807 + 1;"), ["W0010"]);
+        assert_eq!(error_codes("let x = 1 / 0;"), ["W0010"]);
+        assert_eq!(
+            error_codes("if false { let x = 1 / 0; }"),
+            Vec::<&str>::new()
+        );
+    }
+
+    #[test]
+    fn negated_grouping_of_the_minimum_int_overflows() {
+        let mut sources = SourceMap::new();
+        let file = sources.add("test.lox", "-(9223372036854775808);");
+        let statements = optimize(Parser::new(sources.get(file).source()).parse().unwrap());
+        assert!(matches!(
+            &statements[0].data,
+            Statement::Expression(Expression::Unary(unary))
+                if matches!(unary.operand.data, Expression::Grouping(_))
+        ));
+        let error = Interpreter::new()
+            .run(&statements, &sources, file)
+            .unwrap_err();
+        assert!(matches!(error.error, RuntimeErrorType::IntOverflow));
+    }
+}
@@ -29,10 +29,19 @@ impl<'a> Parser<'a> {
         };
         let params = self.arguments(|p| p.func_param())?;
         let Some(rparen) = self.symbol_eq(Symbol::RParenthesis) else {
-            return Err(Error::new(
-                lparen.start()..params.last().map_or(lparen.end(), |p| p.end()),
-                ErrorType::ExpectedRParen,
-            ));
+            let end = params.last().map_or(lparen.end(), |p| p.end());
+            return Err(Error::unclosed(Symbol::RParenthesis, lparen.range, end));
+        };
+        let return_type = if let Some(colon) = self.symbol_eq(Symbol::Colon) {
+            let Some(return_type) = self.type_annotation()? else {
+                return Err(Error::new(
+                    decl_keyword.start()..colon.end(),
+                    ErrorType::ExpectedType,
+                ));
+            };
+            Some(return_type)
+        } else {
+            None
         };
         let block = if let Some(block) = self.block()? {
             block.convert(FuncBlock::Block)
@@ -42,8 +51,9 @@ impl<'a> Parser<'a> {
             };
             expr.convert(FuncBlock::ReturnExpr)
         } else {
+            let end = return_type.as_ref().map_or(rparen.end(), |t| t.end());
             return Err(Error::new(
-                decl_keyword.start()..rparen.end(),
+                decl_keyword.start()..end,
                 ErrorType::ExpectedFuncBlock,
             ));
         };
@@ -52,10 +62,67 @@ impl<'a> Parser<'a> {
             FuncDecl {
                 name,
                 params,
+                return_type,
                 block,
             },
         )))
     }
+    // `int`, `[int]` or `{name: string, age: int}`
+    pub(super) fn type_annotation(&mut self) -> ParseResultOption<TypeAnnotation> {
+        if let Some(lsquare) = self.symbol_eq(Symbol::LSquareBracket) {
+            let Some(element) = self.type_annotation()? else {
+                return Err(Error::new(lsquare.range, ErrorType::ExpectedType));
+            };
+            let Some(rsquare) = self.symbol_eq(Symbol::RSquareBracket) else {
+                return Err(Error::unclosed(
+                    Symbol::RSquareBracket,
+                    lsquare.range,
+                    element.end(),
+                ));
+            };
+            return Ok(Some(ParseNode::new(
+                lsquare.start()..rsquare.end(),
+                TypeAnnotation::Array(element.into()),
+            )));
+        }
+        if let Some(lcurly) = self.symbol_eq(Symbol::LCurlyBracket) {
+            let fields = self.arguments(|p| p.field_type())?;
+            let Some(rcurly) = self.symbol_eq(Symbol::RCurlyBracket) else {
+                let end = fields.last().map_or(lcurly.end(), |f| f.end());
+                return Err(Error::unclosed(Symbol::RCurlyBracket, lcurly.range, end));
+            };
+            return Ok(Some(ParseNode::new(
+                lcurly.start()..rcurly.end(),
+                TypeAnnotation::Dictionary(fields.into_iter().map(|f| f.data).collect()),
+            )));
+        }
+        // `none` is a keyword, unlike the names of the other types
+        if let Some(none) = self.keyword_eq(Keyword::None) {
+            return Ok(Some(none.replace(TypeAnnotation::Name(StringName::NONE))));
+        }
+        Ok(self.ident().map(|name| name.convert(TypeAnnotation::Name)))
+    }
+    // `name: type` in a dictionary type
+    fn field_type(
+        &mut self,
+    ) -> ParseResultOption<(ParseNode<StringName>, ParseNode<TypeAnnotation>)> {
+        let Some(name) = self.ident() else {
+            return Ok(None);
+        };
+        let Some(colon) = self.symbol_eq(Symbol::Colon) else {
+            return Err(Error::new(name.range, ErrorType::ExpectedColon));
+        };
+        let Some(annotation) = self.type_annotation()? else {
+            return Err(Error::new(
+                name.start()..colon.end(),
+                ErrorType::ExpectedType,
+            ));
+        };
+        Ok(Some(ParseNode::new(
+            name.start()..annotation.end(),
+            (name, annotation),
+        )))
+    }
     // pretty much variable declaration without the var keyword
     pub(super) fn func_param(&mut self) -> ParseResultOption<VarDecl> {
         let name = if let Some(lsquare) = self.symbol_eq(Symbol::LSquareBracket) {
@@ -90,7 +157,7 @@ impl<'a> Parser<'a> {
             }
 
             let Some(rsquare) = self.symbol_eq(Symbol::RSquareBracket) else {
-                return Err(Error::new(lsquare.start()..end, ErrorType::ExpectedRSquare));
+                return Err(Error::unclosed(Symbol::RSquareBracket, lsquare.range, end));
             };
 
             ParseNode::new(
@@ -101,7 +168,7 @@ impl<'a> Parser<'a> {
                 },
             )
         } else if let Some(lcurly) = self.symbol_eq(Symbol::LCurlyBracket) {
-            todo!()
+            return Err(Error::new(lcurly.range, ErrorType::DictionaryPattern));
         } else {
             let Some(name) = self.var_name()? else {
                 return Ok(None);
@@ -109,6 +176,19 @@ impl<'a> Parser<'a> {
             name.convert(VarNameType::Ident)
         };
 
+        let annotation = if let Some(colon) = self.symbol_eq(Symbol::Colon) {
+            let Some(annotation) = self.type_annotation()? else {
+                return Err(Error::new(
+                    name.start()..colon.end(),
+                    ErrorType::ExpectedType,
+                ));
+            };
+            Some(annotation)
+        } else {
+            None
+        };
+        let end = annotation.as_ref().map_or(name.end(), |a| a.end());
+
         let value = if let Some(eq) = self.symbol_eq(Symbol::Assign) {
             let Some(value) = self.expression()? else {
                 return Err(Error::new(name.start()..eq.end(), ErrorType::ExpectedExpr));
@@ -119,9 +199,10 @@ impl<'a> Parser<'a> {
         };
 
         Ok(Some(ParseNode::new(
-            name.start()..value.as_ref().map(|v| v.end()).unwrap_or(name.end()),
+            name.start()..value.as_ref().map(|v| v.end()).unwrap_or(end),
             VarDecl {
                 pattern: name,
+                annotation,
                 value,
             },
         )))
@@ -132,7 +213,7 @@ impl<'a> Parser<'a> {
         };
         Ok(Some(ParseNode::new(
             name.range,
-            if name.data.as_str() == "_" {
+            if name.data == StringName::UNDERSCORE {
                 None
             } else {
                 Some(name.data)
@@ -150,3 +231,59 @@ impl<'a> Parser<'a> {
         Ok(Some(ParseNode::new(decl_keyword.start()..decl.end(), decl.data)))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::super::Parser;
+    use super::*;
+
+    fn parse(source: &str) -> Vec<ParseNode<Statement>> {
+        Parser::new(source).parse().unwrap()
+    }
+    fn func_decl(statement: &Statement) -> &FuncDecl {
+        match statement {
+            Statement::Declaration(Declaration::Func(decl)) => decl,
+            _ => panic!("expected a function declaration, found {:?}", statement),
+        }
+    }
+    fn return_type(decl: &FuncDecl) -> Option<&str> {
+        match decl.return_type.as_ref().map(|t| &t.data) {
+            Some(TypeAnnotation::Name(name)) => Some(name.as_str()),
+            None => None,
+            Some(return_type) => panic!("expected a type name, found {:?}", return_type),
+        }
+    }
+
+    #[test]
+    fn return_type_follows_a_colon() {
+        let statements = parse("func f(): int { return 1; }");
+        let decl = func_decl(&statements[0].data);
+        assert_eq!(return_type(decl), Some("int"));
+        assert!(matches!(decl.block.data, FuncBlock::Block(_)));
+
+        let statements = parse("func double(n: int): int -> n * 2");
+        let decl = func_decl(&statements[0].data);
+        assert_eq!(return_type(decl), Some("int"));
+        assert!(matches!(decl.block.data, FuncBlock::ReturnExpr(_)));
+    }
+
+    #[test]
+    fn arrow_always_starts_the_body() {
+        // `x` is the returned expression, and the block is a statement of its own
+        let statements = parse("func f() -> x { println(1); }");
+        assert_eq!(statements.len(), 2);
+        let decl = func_decl(&statements[0].data);
+        assert_eq!(return_type(decl), None);
+        assert!(matches!(
+            decl.block.data,
+            FuncBlock::ReturnExpr(Expression::Variable(_))
+        ));
+        assert!(matches!(statements[1].data, Statement::Block(_)));
+    }
+
+    #[test]
+    fn colon_without_a_type() {
+        let error = Parser::new("func f(): -> 1").parse().unwrap_err();
+        assert!(matches!(error.error, ErrorType::ExpectedType));
+    }
+}
@@ -2,18 +2,115 @@ use std::fmt;
 use std::fmt::Write;
 use std::ops::Range;
 
-use crate::source::SourceIter;
+use super::parse_node::{Keyword, Symbol};
+use super::render::render_error;
+use crate::source_map::{FileId, SourceFile, Span};
+use crate::string_name::StringName;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// A diagnostic of the parser or of a pass after it. Its range, labels and suggestions are all in
+/// `file`, which is the first file of the `SourceMap` until set with `in_file`.
 #[derive(Debug, Clone)]
 pub struct Error {
+    pub file: FileId,
     pub range: Range<usize>,
     pub error: ErrorType,
+    pub severity: Severity,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+    pub suggestions: Vec<Suggestion>,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+impl Severity {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+// a secondary span shown next to the error, such as the opening bracket of an unclosed block
+#[derive(Debug, Clone)]
+pub struct Label {
+    pub range: Range<usize>,
+    pub message: String,
+}
+// a fix that replaces the source in `range` with `replacement`
+#[derive(Debug, Clone)]
+pub struct Suggestion {
+    pub range: Range<usize>,
+    pub replacement: String,
+    pub message: String,
 }
 impl Error {
     pub fn new(range: Range<usize>, error: ErrorType) -> Self {
-        Self { range, error }
+        Self {
+            file: FileId::default(),
+            range,
+            error,
+            severity: Severity::Error,
+            labels: vec![],
+            notes: vec![],
+            suggestions: vec![],
+        }
+    }
+    /// Error for a missing closing bracket. It points at where the bracket was expected
+    /// (right after `end`), labels the opening bracket and suggests inserting the closing one.
+    pub fn unclosed(closing: Symbol, open: Range<usize>, end: usize) -> Self {
+        let (opening, error) = match closing {
+            Symbol::RParenthesis => (Symbol::LParenthesis, ErrorType::ExpectedRParen),
+            Symbol::RSquareBracket => (Symbol::LSquareBracket, ErrorType::ExpectedRSquare),
+            Symbol::RCurlyBracket => (Symbol::LCurlyBracket, ErrorType::ExpectedRCurly),
+            _ => unreachable!("{} is not a closing bracket", closing),
+        };
+        Self::new(end..end, error)
+            .with_label(open, format!("unclosed '{}' opened here", opening))
+            .with_suggestion(
+                end..end,
+                closing.as_str(),
+                format!("add the missing '{}'", closing),
+            )
+    }
+    /// The error with its ranges in `file` of the `SourceMap` instead.
+    pub fn in_file(mut self, file: FileId) -> Self {
+        self.file = file;
+        self
+    }
+    pub fn span(&self) -> Span {
+        Span::new(self.file, self.range.clone())
+    }
+    pub fn with_severity(mut self, severity: Severity) -> Self {
+        self.severity = severity;
+        self
+    }
+    pub fn with_label(mut self, range: Range<usize>, message: impl Into<String>) -> Self {
+        self.labels.push(Label {
+            range,
+            message: message.into(),
+        });
+        self
+    }
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+    pub fn with_suggestion(
+        mut self,
+        range: Range<usize>,
+        replacement: impl Into<String>,
+        message: impl Into<String>,
+    ) -> Self {
+        self.suggestions.push(Suggestion {
+            range,
+            replacement: replacement.into(),
+            message: message.into(),
+        });
+        self
     }
 }
 impl fmt::Display for Error {
@@ -22,90 +119,14 @@ impl fmt::Display for Error {
     }
 }
 
-fn highlight_line(
-    source: &str,
-    line_range: Range<usize>,
-    error_range: Range<usize>,
-    line_number: usize,
-    message: &mut impl Write,
-) -> fmt::Result {
-    // checking for collision, if there isn't then there's no need to do anything
-    if usize::max(error_range.start, line_range.start)
-        >= usize::min(error_range.end, line_range.end)
-    {
-        return Ok(());
-    }
-
-    let line_number_string = format!(" {:3} | ", line_number + 1);
-    writeln!(
-        message,
-        "{}{}",
-        line_number_string,
-        &source[line_range.clone()]
-    )?;
-
-    // print spaces to compensate for the line number string
-    for _ in 0..line_number_string.len() {
-        write!(message, " ")?;
-    }
-
-    // print spaces until it reaches the highlight
-    let highlight_start = usize::max(line_range.start, error_range.start);
-    for _ in line_range.start..highlight_start {
-        write!(message, " ")?;
-    }
-
-    // print '^' until it reaches error range end or line range end
-    for _ in highlight_start..(usize::min(line_range.end, error_range.end)) {
-        write!(message, "^")?;
-    }
-
-    // finally newline
-    writeln!(message)
-}
 impl DisplayError for Error {
-    fn display(&self, source: &str, message: &mut impl Write) -> fmt::Result {
-        let mut iter = SourceIter::from(source);
-        let mut column = 0;
-        let mut line = 0;
-        let mut line_start = 0;
-        let mut line_end = 0; // index past the last iterated character (for the last line ending without newline)
-
-        for (i, ch) in iter.by_ref() {
-            line_end = i + ch.len_utf8();
-
-            if ch == '\n' {
-                // reached end of line, highlight the line
-                highlight_line(source, line_start..i, self.range.clone(), line, message)?;
-
-                // update line, column and line_start
-                line += 1;
-                column = 0;
-                line_start = i + 1; // '\n' will always have length 1
-            } else {
-                column += 1;
-            }
-            // check if it just reached the range
-            if i == self.range.start {
-                // print the error location
-                write!(
-                    message,
-                    "Error at line: {}, column: {}\n{}\n",
-                    line + 1,
-                    column,
-                    self.error
-                )?;
-            }
-        }
-
-        // this is the last line, highlight it if range exists
-        highlight_line(
-            source,
-            line_start..line_end,
-            self.range.clone(),
-            line,
-            message,
-        )
+    fn display_styled(
+        &self,
+        file: &SourceFile,
+        color: bool,
+        message: &mut impl Write,
+    ) -> fmt::Result {
+        render_error(self, file, color, message)
     }
 }
 
@@ -131,6 +152,8 @@ pub enum ErrorType {
     ExpectedBlock,
     ExpectedFuncBlock,
     ExpectedEOF,
+    ExpectedCatch,
+    ExpectedType,
 
     IncompleteString,
     IncompleteCharCode,
@@ -146,8 +169,100 @@ pub enum ErrorType {
     UnexpectedSymbol,
 
     ExtraDots,
+    DictionaryPattern,
 
     UnderscoreVariable,
+
+    InvalidAssignTarget,
+
+    UndefinedVariable(StringName),
+    ReadInOwnInitializer(StringName),
+    DuplicateParameter(StringName),
+    OutsideLoop(Keyword),
+    ReturnOutsideFunction,
+    UnknownKeyword(StringName),
+    UnknownOperator(&'static str),
+    InvisibleChar(char),
+    UnknownType(StringName),
+    MismatchedTypes(Box<str>),
+    InvalidOperandTypes(Box<str>),
+
+    // lints, reported as warnings by default, apart from `ConstantError`
+    UnusedVariable(StringName),
+    ShadowedVariable(StringName),
+    UnreachableCode,
+    InfiniteLoop,
+    EmptyLoopHandler(Keyword),
+    SelfComparison,
+    UnknownLint(String),
+    MixedScriptIdent(StringName),
+    ConfusableIdent(StringName, StringName),
+    ConstantError(Box<str>),
+    InvisibleJoiner(StringName),
+}
+impl ErrorType {
+    /// Stable code of the error, shown next to its message and used by `explain`.
+    /// Codes are never reused or renumbered, new errors take the next free code.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ErrorType::IntOverflow => "E0001",
+            ErrorType::ExpectedLParen => "E0002",
+            ErrorType::ExpectedRParen => "E0003",
+            ErrorType::ExpectedLCurly => "E0004",
+            ErrorType::ExpectedRCurly => "E0005",
+            ErrorType::ExpectedLSquare => "E0006",
+            ErrorType::ExpectedRSquare => "E0007",
+            ErrorType::ExpectedNumber => "E0008",
+            ErrorType::ExpectedInteger => "E0009",
+            ErrorType::ExpectedIdent => "E0010",
+            ErrorType::ExpectedExpr => "E0011",
+            ErrorType::ExpectedVarName => "E0012",
+            ErrorType::ExpectedFuncName => "E0013",
+            ErrorType::ExpectedSemicolon => "E0014",
+            ErrorType::ExpectedColon => "E0015",
+            ErrorType::ExpectedAssign => "E0016",
+            ErrorType::ExpectedBlock => "E0017",
+            ErrorType::ExpectedFuncBlock => "E0018",
+            ErrorType::ExpectedEOF => "E0019",
+            ErrorType::IncompleteString => "E0020",
+            ErrorType::IncompleteCharCode => "E0021",
+            ErrorType::IncompleteEscape => "E0022",
+            ErrorType::IncompleteChar => "E0023",
+            ErrorType::InvalidEscape => "E0024",
+            ErrorType::InvalidCharCode => "E0025",
+            ErrorType::EmptyChar => "E0026",
+            ErrorType::TooManyChars => "E0027",
+            ErrorType::UnexpectedSymbol => "E0028",
+            ErrorType::ExtraDots => "E0029",
+            ErrorType::UnderscoreVariable => "E0030",
+            ErrorType::InvalidAssignTarget => "E0031",
+            ErrorType::UndefinedVariable(_) => "E0032",
+            ErrorType::ReadInOwnInitializer(_) => "E0033",
+            ErrorType::DuplicateParameter(_) => "E0034",
+            ErrorType::OutsideLoop(_) => "E0035",
+            ErrorType::ReturnOutsideFunction => "E0036",
+            ErrorType::UnknownKeyword(_) => "E0037",
+            ErrorType::UnknownOperator(_) => "E0038",
+            ErrorType::InvisibleChar(_) => "E0039",
+            ErrorType::ExpectedCatch => "E0040",
+            ErrorType::ExpectedType => "E0041",
+            ErrorType::UnknownType(_) => "E0042",
+            ErrorType::MismatchedTypes(_) => "E0043",
+            ErrorType::InvalidOperandTypes(_) => "E0044",
+            ErrorType::DictionaryPattern => "E0045",
+            ErrorType::UnusedVariable(_) => "W0001",
+            ErrorType::ShadowedVariable(_) => "W0002",
+            ErrorType::UnreachableCode => "W0003",
+            ErrorType::InfiniteLoop => "W0004",
+            ErrorType::EmptyLoopHandler(_) => "W0005",
+            ErrorType::SelfComparison => "W0006",
+            ErrorType::UnknownLint(_) => "W0007",
+            ErrorType::MixedScriptIdent(_) => "W0008",
+            ErrorType::ConfusableIdent(..) => "W0009",
+            ErrorType::ConstantError(_) => "W0010",
+            ErrorType::InvisibleJoiner(_) => "W0011",
+        }
+    }
 }
 impl fmt::Display for ErrorType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -175,6 +290,13 @@ impl fmt::Display for ErrorType {
             ErrorType::ExpectedFuncBlock => {
                 write!(f, "Expected a block or expression ('-> [expr]')")
             }
+            ErrorType::ExpectedCatch => {
+                write!(
+                    f,
+                    "Expected a 'catch' or 'finally' block after the 'try' block"
+                )
+            }
+            ErrorType::ExpectedType => write!(f, "Expected a type"),
 
             ErrorType::IncompleteString => write!(f, "Incomplete string"),
             ErrorType::IncompleteEscape => write!(f, "Incomplete escape sequence"),
@@ -192,13 +314,75 @@ impl fmt::Display for ErrorType {
             ErrorType::ExtraDots => {
                 write!(f, "Cannot have multiple '...' symbol in array unpacking")
             }
+            ErrorType::DictionaryPattern => {
+                write!(f, "Cannot unpack a dictionary. Only arrays can be unpacked")
+            }
             ErrorType::UnderscoreVariable => {
                 write!(f, "Cannot read from '_'. You can only assign to it")
             }
+            ErrorType::InvalidAssignTarget => {
+                write!(f, "Can only assign to a variable, property or index")
+            }
+            ErrorType::UndefinedVariable(name) => write!(f, "Undefined variable '{}'", name),
+            ErrorType::ReadInOwnInitializer(name) => {
+                write!(f, "Cannot read '{}' in its own initializer", name)
+            }
+            ErrorType::DuplicateParameter(name) => {
+                write!(f, "Duplicate parameter name '{}'", name)
+            }
+            ErrorType::OutsideLoop(keyword) => {
+                write!(f, "Cannot use '{}' outside of a loop", keyword)
+            }
+            ErrorType::ReturnOutsideFunction => {
+                write!(f, "Cannot use 'return' outside of a function")
+            }
+            ErrorType::UnknownKeyword(word) => write!(f, "Unknown keyword '{}'", word),
+            ErrorType::UnknownOperator(operator) => write!(f, "Unknown operator '{}'", operator),
+            ErrorType::InvisibleChar(c) => {
+                write!(f, "Invisible character U+{:04X} in the code", *c as u32)
+            }
+            ErrorType::UnknownType(name) => write!(f, "Unknown type '{}'", name),
+            ErrorType::MismatchedTypes(message) => write!(f, "Mismatched types: {}", message),
+            ErrorType::InvalidOperandTypes(message) => write!(f, "{}", message),
+            ErrorType::UnusedVariable(name) => write!(f, "Unused variable '{}'", name),
+            ErrorType::ShadowedVariable(name) => {
+                write!(f, "'{}' shadows a variable with the same name", name)
+            }
+            ErrorType::UnreachableCode => write!(f, "Unreachable code"),
+            ErrorType::InfiniteLoop => write!(f, "Infinite loop, 'while true' has no 'break'"),
+            ErrorType::EmptyLoopHandler(keyword) => write!(f, "Empty '{}' block", keyword),
+            ErrorType::SelfComparison => write!(f, "Comparing a value with itself"),
+            ErrorType::UnknownLint(name) => write!(f, "Unknown lint '{}'", name),
+            ErrorType::MixedScriptIdent(name) => {
+                write!(
+                    f,
+                    "Identifier '{}' mixes letters of different scripts",
+                    name
+                )
+            }
+            ErrorType::ConfusableIdent(name, other) => {
+                write!(f, "Identifier '{}' looks like '{}'", name, other)
+            }
+            ErrorType::ConstantError(message) => {
+                write!(f, "This expression always fails: {}", message)
+            }
+            ErrorType::InvisibleJoiner(name) => {
+                write!(f, "Identifier '{}' contains an invisible joiner", name)
+            }
         }
     }
 }
 
 pub trait DisplayError: fmt::Display {
-    fn display(&self, source: &str, message: &mut impl Write) -> fmt::Result;
+    fn display(&self, source: &str, message: &mut impl Write) -> fmt::Result {
+        self.display_styled(&SourceFile::new("", source), false, message)
+    }
+    /// Same as `display`, for a file of a `SourceMap`, and colored with ansi escape codes when
+    /// `color` is set.
+    fn display_styled(
+        &self,
+        file: &SourceFile,
+        color: bool,
+        message: &mut impl Write,
+    ) -> fmt::Result;
 }
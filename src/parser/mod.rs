@@ -1,11 +1,17 @@
 pub mod error;
 pub mod parse_node;
+pub mod visit;
 
 mod declaration;
 mod operator;
+#[allow(clippy::module_inception)]
 mod parser;
 mod primary;
+pub(crate) mod render;
 mod statements;
+pub(crate) mod suggest;
 mod tokenizer;
+mod unparse;
 
 pub use parser::Parser;
+pub use unparse::unparse;
@@ -6,18 +6,55 @@ use super::parser::*;
 
 impl<'a> Parser<'a> {
     pub(super) fn operator(&mut self) -> ParseResultOption<Expression> {
-        self.logical_or()
-    }
-    fn is_assign_operator(symbol: Symbol) -> bool {
-        matches!(symbol, Symbol::Add | Symbol::Sub | Symbol::Mul | Symbol::Div | Symbol::Mod | Symbol::LeftShift | Symbol::RightShift)
+        self.assign()
+    }
+    // maps compound assignment symbols (`+=`, `-=` etc.) to their binary operator
+    fn assign_operator(symbol: Symbol) -> Option<Symbol> {
+        Some(match symbol {
+            Symbol::AddAssign => Symbol::Add,
+            Symbol::SubAssign => Symbol::Sub,
+            Symbol::MulAssign => Symbol::Mul,
+            Symbol::DivAssign => Symbol::Div,
+            Symbol::ModAssign => Symbol::Mod,
+            Symbol::LeftShiftAssign => Symbol::LeftShift,
+            Symbol::RightShiftAssign => Symbol::RightShift,
+            _ => return None,
+        })
+    }
+    fn is_assignable(expr: &Expression) -> bool {
+        match expr {
+            Expression::Variable(_) => true,
+            Expression::Suffix(suffix) => !matches!(suffix.suffix, SuffixType::Call(_)),
+            _ => false,
+        }
     }
+    // assignment is right associative, `a = b = c` assigns `c` to both `b` and `a`
     pub(super) fn assign(&mut self) -> ParseResultOption<Expression> {
-        let Some(mut left) = self.logical_or()? else {
+        let Some(left) = self.logical_or()? else {
             return Ok(None);
         };
-        loop {
-            if let Some(assignable_operator) 
+        let Some(symbol) =
+            self.symbol_if(|s| s == Symbol::Assign || Self::assign_operator(s).is_some())
+        else {
+            return Ok(Some(left));
+        };
+        if !Self::is_assignable(&left.data) {
+            return Err(Error::new(
+                left.start()..symbol.end(),
+                ErrorType::InvalidAssignTarget,
+            ));
         }
+        let Some(right) = self.assign()? else {
+            return Err(Error::new(left.start()..symbol.end(), ErrorType::ExpectedExpr));
+        };
+        Ok(Some(ParseNode::new(
+            left.start()..right.end(),
+            Expression::Assign(Assign {
+                left: left.into(),
+                right: right.into(),
+                operator: Self::assign_operator(symbol.data),
+            }),
+        )))
     }
     pub(super) fn logical_or(&mut self) -> ParseResultOption<Expression> {
         self.binary_kw(|p| p.logical_and(), |k| matches!(k, Keyword::Or))
@@ -105,8 +142,7 @@ impl<'a> Parser<'a> {
             Some(l) => l,
             None => return Ok(None),
         };
-        let mut parser = self.clone();
-        while let Some(kw) = parser.keyword_if(kw_check) {
+        while let Some(kw) = self.keyword_if(&kw_check) {
             let Some(right) = lower_fn(self)? else {
                 return Err(Error::new(left.start()..kw.end(), ErrorType::ExpectedExpr));
             };
@@ -130,7 +166,8 @@ impl<'a> Parser<'a> {
             Some(l) => l,
             None => return Ok(None),
         };
-        while let Some(op) = self.symbol_if(op_check) {
+        while let Some(op) = self.symbol_if(&op_check) {
+            self.doubled_operator(&op)?;
             let Some(right) = lower_fn(self)? else {
                 return Err(Error::new(left.start()..op.end(), ErrorType::ExpectedExpr));
             };
@@ -164,7 +201,7 @@ impl<'a> Parser<'a> {
                     let Some(ident) = self.ident() else {
                         return Err(Error::new(symbol.range, ErrorType::ExpectedIdent));
                     };
-                    range = symbol.start()..ident.end();
+                    range = node.start()..ident.end();
                     suffix = SuffixType::Property(ident.data);
                 }
                 Symbol::LSquareBracket => {
@@ -172,23 +209,22 @@ impl<'a> Parser<'a> {
                         return Err(Error::new(symbol.range, ErrorType::ExpectedExpr));
                     };
                     let Some(closing) = self.symbol_eq(Symbol::RSquareBracket) else {
-                        return Err(Error::new(
-                            symbol.start()..expr.end(),
-                            ErrorType::ExpectedRSquare,
+                        return Err(Error::unclosed(
+                            Symbol::RSquareBracket,
+                            symbol.range,
+                            expr.end(),
                         ));
                     };
-                    range = symbol.start()..closing.end();
+                    range = node.start()..closing.end();
                     suffix = SuffixType::Index(expr.into());
                 }
                 Symbol::LParenthesis => {
                     let args = self.arguments(|p| p.expression())?;
                     let Some(closing) = self.symbol_eq(Symbol::RParenthesis) else {
-                        return Err(Error::new(
-                            symbol.start()..args.last().map_or(symbol.end(), |i| i.end()),
-                            ErrorType::ExpectedRParen,
-                        ));
+                        let end = args.last().map_or(symbol.end(), |i| i.end());
+                        return Err(Error::unclosed(Symbol::RParenthesis, symbol.range, end));
                     };
-                    range = symbol.start()..closing.end();
+                    range = node.start()..closing.end();
                     suffix = SuffixType::Call(args);
                 }
                 _ => unreachable!(),
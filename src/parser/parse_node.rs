@@ -12,6 +12,11 @@ impl<T: fmt::Debug> fmt::Debug for ParseNode<T> {
         self.data.fmt(f)
     }
 }
+impl<T: fmt::Display> fmt::Display for ParseNode<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.data.fmt(f)
+    }
+}
 impl<T> ParseNode<T> {
     pub const fn new(range: Range<usize>, data: T) -> Self {
         Self { data, range }
@@ -47,6 +52,7 @@ impl fmt::Debug for Declaration {
 pub struct FuncDecl {
     pub name: ParseNode<StringName>,
     pub params: Vec<ParseNode<VarDecl>>,
+    pub return_type: Option<ParseNode<TypeAnnotation>>, // the `int` of `func f(): int { }`
     pub block: ParseNode<FuncBlock>,
 }
 impl fmt::Debug for FuncDecl {
@@ -55,6 +61,9 @@ impl fmt::Debug for FuncDecl {
         for param in &self.params {
             write!(f, " {:?}", param.data)?;
         }
+        if let Some(return_type) = &self.return_type {
+            write!(f, "; returns: {:?}", return_type.data)?;
+        }
         write!(f, "; block: {:?})", self.block.data)?;
 
         Ok(())
@@ -76,11 +85,15 @@ impl fmt::Debug for FuncBlock {
 #[derive(Clone)]
 pub struct VarDecl {
     pub pattern: ParseNode<VarNameType>,
+    pub annotation: Option<ParseNode<TypeAnnotation>>, // `let x: int`
     pub value: Option<ParseNode<Expression>>,
 }
 impl fmt::Debug for VarDecl {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "(vardecl: {:?}", self.pattern.data)?;
+        if let Some(annotation) = &self.annotation {
+            write!(f, "; type: {:?}", annotation.data)?;
+        }
         if let Some(value) = &self.value {
             write!(f, "; assign: {:?}", value.data)?;
         }
@@ -129,13 +142,44 @@ impl fmt::Debug for VarNameType {
         }
     }
 }
+// the type a variable, parameter or function return is declared with. types are optional and
+// only read by the type checker, the interpreter ignores them
+#[derive(Clone)]
+pub enum TypeAnnotation {
+    Name(StringName), // `int`, `string`, `none`, ...
+    Array(Box<ParseNode<TypeAnnotation>>), // `[int]`, an array of ints
+    Dictionary(Vec<(ParseNode<StringName>, ParseNode<TypeAnnotation>)>), // `{name: string}`, a dictionary with at least these keys
+}
+impl fmt::Debug for TypeAnnotation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Name(name) => write!(f, "{}", name),
+            Self::Array(element) => write!(f, "[{:?}]", element.data),
+            Self::Dictionary(fields) => {
+                write!(f, "{{")?;
+                for (i, (name, annotation)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {:?}", name.data, annotation.data)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
 #[derive(Clone)]
 pub enum Statement {
     Declaration(Declaration),
     Expression(Expression),
     If(IfStatement),
     While(WhileStatement),
+    Try(TryStatement),
     Block(Block),
+    Break,
+    Continue,
+    Return(Option<ParseNode<Expression>>),
+    Throw(ParseNode<Expression>),
 }
 impl fmt::Debug for Statement {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -144,7 +188,13 @@ impl fmt::Debug for Statement {
             Self::Expression(expr) => expr.fmt(f),
             Self::If(if_stmt) => if_stmt.fmt(f),
             Self::While(while_stmt) => while_stmt.fmt(f),
+            Self::Try(try_stmt) => try_stmt.fmt(f),
             Self::Block(block) => block.fmt(f),
+            Self::Break => write!(f, "(break)"),
+            Self::Continue => write!(f, "(continue)"),
+            Self::Return(Some(expr)) => write!(f, "(return: {:?})", expr),
+            Self::Return(None) => write!(f, "(return)"),
+            Self::Throw(expr) => write!(f, "(throw: {:?})", expr),
         }
     }
 }
@@ -206,6 +256,7 @@ impl fmt::Debug for Expression {
             }
             Self::Variable(name) => write!(f, "{}", name),
             Self::Binary(bin) => bin.fmt(f),
+            Self::Assign(assign) => assign.fmt(f),
             Self::Unary(un) => un.fmt(f),
             Self::Suffix(suf) => suf.fmt(f),
             Self::Grouping(expr) => expr.fmt(f),
@@ -241,6 +292,36 @@ impl fmt::Debug for WhileStatement {
     }
 }
 #[derive(Clone)]
+pub struct TryStatement {
+    pub try_block: ParseNode<Block>,
+    pub catch: Option<ParseNode<CatchBlock>>,
+    pub finally: Option<ParseNode<Block>>,
+}
+impl fmt::Debug for TryStatement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(try: {:?}", self.try_block.data)?;
+        if let Some(catch) = &self.catch {
+            write!(f, "; {:?}", catch.data)?;
+        }
+        if let Some(block) = &self.finally {
+            write!(f, "; finally: {:?}", block.data)?;
+        }
+        write!(f, ")")
+    }
+}
+// `catch name { }`, the caught error is assigned to the name
+#[derive(Clone)]
+pub struct CatchBlock {
+    pub name: ParseNode<Option<StringName>>,
+    pub block: ParseNode<Block>,
+}
+impl fmt::Debug for CatchBlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = self.name.data.map_or("_", |name| name.as_str());
+        write!(f, "catch {}: {:?}", name, self.block.data)
+    }
+}
+#[derive(Clone)]
 pub struct IfStatement {
     pub condition: ParseNode<Expression>,
     pub met_block: ParseNode<Block>,
@@ -276,13 +357,13 @@ pub type Dictionary = Vec<(ParseNode<DictionaryKey>, ParseNode<Expression>)>;
 #[derive(Clone)]
 pub enum DictionaryKey {
     Ident(StringName),
-    Expr(Expression),
+    Expr(ParseNode<Expression>), // `[expr]`, the range of the key includes the brackets
 }
 impl fmt::Debug for DictionaryKey {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             DictionaryKey::Ident(name) => write!(f, "{}", *name),
-            DictionaryKey::Expr(expr) => write!(f, "{:?}", *expr),
+            DictionaryKey::Expr(expr) => write!(f, "{:?}", expr.data),
         }
     }
 }
@@ -317,6 +398,17 @@ impl fmt::Debug for Suffix {
 #[derive(Clone)]
 pub struct Assign {
     pub left: Box<ParseNode<Expression>>,
+    pub right: Box<ParseNode<Expression>>,
+    pub operator: Option<Symbol>, // binary operator of compound assignments. eg. `a += 1` has `Symbol::Add`
+}
+impl fmt::Debug for Assign {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(assign")?;
+        if let Some(operator) = &self.operator {
+            write!(f, " {:?}", operator)?;
+        }
+        write!(f, ": {:?} {:?})", self.left.data, self.right.data)
+    }
 }
 #[derive(Clone)]
 pub struct Binary {
@@ -361,6 +453,29 @@ pub enum Number {
     Int(u64),
     Real(f64),
 }
+/// A token of the source, the way the parser reads it.
+#[derive(Clone)]
+pub enum Token {
+    Ident(StringName),
+    Keyword(Keyword),
+    Symbol(Symbol),
+    Number(Number),
+    String(String),
+    Char(char),
+}
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Ident(name) => write!(f, "ident {}", name),
+            Token::Keyword(keyword) => write!(f, "keyword {}", keyword),
+            Token::Symbol(symbol) => write!(f, "symbol {}", symbol),
+            Token::Number(Number::Int(i)) => write!(f, "int {}", i),
+            Token::Number(Number::Real(r)) => write!(f, "real {:?}", r),
+            Token::String(s) => write!(f, "string {:?}", s),
+            Token::Char(c) => write!(f, "char {:?}", c),
+        }
+    }
+}
 #[derive(Clone, Copy)]
 pub enum IdentKeyword {
     Ident(StringName),
@@ -380,8 +495,13 @@ pub enum Keyword {
     While,
     Break,
     Continue,
+    Return,
     OnBreak,
     OnContinue,
+    Throw,
+    Try,
+    Catch,
+    Finally,
     And,
     Or,
 }
@@ -401,14 +521,74 @@ impl TryFrom<&str> for Keyword {
             "for" => Keyword::For,
             "break" => Keyword::Break,
             "continue" => Keyword::Continue,
+            "return" => Keyword::Return,
             "onbreak" => Keyword::OnBreak,
             "oncontinue" => Keyword::OnContinue,
+            "throw" => Keyword::Throw,
+            "try" => Keyword::Try,
+            "catch" => Keyword::Catch,
+            "finally" => Keyword::Finally,
             "and" => Keyword::And,
             "or" => Keyword::Or,
             _ => return Err(()),
         })
     }
 }
+impl Keyword {
+    pub const ALL: [Keyword; 21] = [
+        Keyword::None,
+        Keyword::True,
+        Keyword::False,
+        Keyword::Var,
+        Keyword::Func,
+        Keyword::If,
+        Keyword::Elif,
+        Keyword::Else,
+        Keyword::For,
+        Keyword::While,
+        Keyword::Break,
+        Keyword::Continue,
+        Keyword::Return,
+        Keyword::OnBreak,
+        Keyword::OnContinue,
+        Keyword::Throw,
+        Keyword::Try,
+        Keyword::Catch,
+        Keyword::Finally,
+        Keyword::And,
+        Keyword::Or,
+    ];
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Keyword::None => "none",
+            Keyword::True => "true",
+            Keyword::False => "false",
+            Keyword::Var => "let",
+            Keyword::Func => "func",
+            Keyword::If => "if",
+            Keyword::Elif => "elif",
+            Keyword::Else => "else",
+            Keyword::While => "while",
+            Keyword::For => "for",
+            Keyword::Break => "break",
+            Keyword::Continue => "continue",
+            Keyword::Return => "return",
+            Keyword::OnBreak => "onbreak",
+            Keyword::OnContinue => "oncontinue",
+            Keyword::Throw => "throw",
+            Keyword::Try => "try",
+            Keyword::Catch => "catch",
+            Keyword::Finally => "finally",
+            Keyword::And => "and",
+            Keyword::Or => "or",
+        }
+    }
+}
+impl fmt::Display for Keyword {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Symbol {
     Add,
@@ -443,6 +623,13 @@ pub enum Symbol {
     Colon,
     LeftArrow,
     RightArrow,
+    AddAssign,
+    SubAssign,
+    MulAssign,
+    DivAssign,
+    ModAssign,
+    LeftShiftAssign,
+    RightShiftAssign,
 }
 impl TryFrom<&str> for Symbol {
     type Error = ();
@@ -480,8 +667,65 @@ impl TryFrom<&str> for Symbol {
             ":" => Symbol::Colon,
             "<-" => Symbol::LeftArrow,
             "->" => Symbol::RightArrow,
+            "+=" => Symbol::AddAssign,
+            "-=" => Symbol::SubAssign,
+            "*=" => Symbol::MulAssign,
+            "/=" => Symbol::DivAssign,
+            "%=" => Symbol::ModAssign,
+            "<<=" => Symbol::LeftShiftAssign,
+            ">>=" => Symbol::RightShiftAssign,
             _ => return Err(()),
         })
     }
 }
+impl Symbol {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Symbol::Add => "+",
+            Symbol::Sub => "-",
+            Symbol::Mul => "*",
+            Symbol::Div => "/",
+            Symbol::Mod => "%",
+            Symbol::Pow => "**",
+            Symbol::Not => "!",
+            Symbol::And => "&",
+            Symbol::Or => "|",
+            Symbol::Xor => "^",
+            Symbol::LeftShift => "<<",
+            Symbol::RightShift => ">>",
+            Symbol::Eq => "==",
+            Symbol::NotEq => "!=",
+            Symbol::Greater => ">",
+            Symbol::Less => "<",
+            Symbol::GreaterEq => ">=",
+            Symbol::LessEq => "<=",
+            Symbol::LParenthesis => "(",
+            Symbol::RParenthesis => ")",
+            Symbol::LSquareBracket => "[",
+            Symbol::RSquareBracket => "]",
+            Symbol::LCurlyBracket => "{",
+            Symbol::RCurlyBracket => "}",
+            Symbol::Dot => ".",
+            Symbol::Dots => "...",
+            Symbol::Assign => "=",
+            Symbol::Comma => ",",
+            Symbol::Semicolon => ";",
+            Symbol::Colon => ":",
+            Symbol::LeftArrow => "<-",
+            Symbol::RightArrow => "->",
+            Symbol::AddAssign => "+=",
+            Symbol::SubAssign => "-=",
+            Symbol::MulAssign => "*=",
+            Symbol::DivAssign => "/=",
+            Symbol::ModAssign => "%=",
+            Symbol::LeftShiftAssign => "<<=",
+            Symbol::RightShiftAssign => ">>=",
+        }
+    }
+}
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
 pub const MAX_SYMBOL_LENGTH: usize = 5;
@@ -23,14 +23,15 @@ impl<'a> Parser<'a> {
         }
     }
     pub fn parse(&mut self) -> Result<Vec<ParseNode<Statement>>> {
-        if let Some(stmts) = self.statements()? {
-            return Ok(stmts.data.statements);
+        if let Some(err) = self.clone().invisible_char() {
+            return Err(err);
         }
+        let stmts = self.statements()?;
         self.skip();
         if let Some((i, c)) = self.iter.next() {
             return Err(Error::new(i..i + c.len_utf8(), ErrorType::ExpectedEOF));
         }
-        Ok(vec![])
+        Ok(stmts.map_or(vec![], |stmts| stmts.data.statements))
     }
     pub(super) fn expression(&mut self) -> ParseResultOption<Expression> {
         self.operator()
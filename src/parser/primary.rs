@@ -1,3 +1,5 @@
+use crate::string_name::StringName;
+
 use super::error::*;
 use super::parse_node::*;
 use super::parser::*;
@@ -25,7 +27,7 @@ impl<'a> Parser<'a> {
             return Ok(Some(none.convert(|_| Expression::None)));
         }
         if let Some(ident) = self.ident() {
-            if ident.data.as_str() == "_" {
+            if ident.data == StringName::UNDERSCORE {
                 return Err(Error::new(ident.range, ErrorType::UnderscoreVariable));
             }
             return Ok(Some(ident.convert(Expression::Variable)));
@@ -55,14 +57,15 @@ impl<'a> Parser<'a> {
                     return Err(Error::new(lsquare.range, ErrorType::ExpectedExpr));
                 };
                 let Some(rsquare) = p.symbol_eq(Symbol::RSquareBracket) else {
-                    return Err(Error::new(
-                        lsquare.start()..key.end(),
-                        ErrorType::ExpectedRSquare,
+                    return Err(Error::unclosed(
+                        Symbol::RSquareBracket,
+                        lsquare.range,
+                        key.end(),
                     ));
                 };
                 ParseNode::new(
                     lsquare.start()..rsquare.end(),
-                    DictionaryKey::Expr(key.data),
+                    DictionaryKey::Expr(key),
                 )
             } else {
                 let Some(ident) = p.ident() else {
@@ -86,10 +89,8 @@ impl<'a> Parser<'a> {
         })?;
 
         let Some(rcurly) = self.symbol_eq(Symbol::RCurlyBracket) else {
-            return Err(Error::new(
-                lcurly.start()..items.last().map_or(lcurly.end(), |i| i.end()),
-                ErrorType::ExpectedRCurly,
-            ));
+            let end = items.last().map_or(lcurly.end(), |i| i.end());
+            return Err(Error::unclosed(Symbol::RCurlyBracket, lcurly.range, end));
         };
 
         Ok(Some(ParseNode::new(
@@ -107,9 +108,10 @@ impl<'a> Parser<'a> {
         };
 
         let Some(rparen) = self.symbol_eq(Symbol::RParenthesis) else {
-            return Err(Error::new(
-                lparen.start()..expr.end(),
-                ErrorType::ExpectedRParen,
+            return Err(Error::unclosed(
+                Symbol::RParenthesis,
+                lparen.range,
+                expr.end(),
             ));
         };
 
@@ -123,10 +125,8 @@ impl<'a> Parser<'a> {
         let args = self.arguments(|p| p.expression())?;
 
         let Some(rbracket) = self.symbol_eq(Symbol::RSquareBracket) else {
-            return Err(Error::new(
-                lbracket.start()..(args.last().map_or(lbracket.end(), |a| a.end())),
-                ErrorType::ExpectedRSquare,
-            ));
+            let end = args.last().map_or(lbracket.end(), |a| a.end());
+            return Err(Error::unclosed(Symbol::RSquareBracket, lbracket.range, end));
         };
 
         Ok(Some(ParseNode::new(lbracket.start()..rbracket.end(), args)))
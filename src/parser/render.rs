@@ -0,0 +1,334 @@
+use std::collections::BTreeSet;
+use std::fmt::{self, Write};
+use std::ops::Range;
+
+use unicode_width::UnicodeWidthChar;
+
+use super::error::{Error, Label, Severity, Suggestion};
+use crate::source_map::SourceFile;
+
+const TAB_WIDTH: usize = 4;
+// lines shown before and after the highlighted ones
+const CONTEXT_LINES: usize = 1;
+
+// ansi escape codes, only written when colored
+const RED: &str = "1;31";
+const BLUE: &str = "1;34";
+const GREEN: &str = "1;32";
+const YELLOW: &str = "1;33";
+const CYAN: &str = "1;36";
+const BOLD: &str = "1";
+
+// what is shown of an error, parse and runtime errors alike
+pub(crate) struct Diagnostic<'a> {
+    pub severity: Severity,
+    pub code: &'static str,
+    pub message: String,
+    pub range: &'a Range<usize>,
+    pub labels: &'a [Label],
+    pub notes: &'a [String],
+    pub suggestions: &'a [Suggestion],
+}
+
+struct Renderer<'a> {
+    file: &'a SourceFile,
+    source: &'a str,
+    gutter_width: usize,
+    color: bool,
+}
+impl<'a> Renderer<'a> {
+    fn new(file: &'a SourceFile, color: bool) -> Self {
+        Self {
+            file,
+            source: file.source(),
+            gutter_width: usize::max(3, file.line_count().to_string().len()),
+            color,
+        }
+    }
+    fn paint(&self, text: &str, style: &str) -> String {
+        if self.color && !text.is_empty() {
+            format!("\x1b[{}m{}\x1b[0m", style, text)
+        } else {
+            text.to_string()
+        }
+    }
+    fn gutter(&self, line_number: Option<usize>) -> String {
+        let number = line_number.map_or(String::new(), |n| (n + 1).to_string());
+        let gutter = format!(" {:>width$} | ", number, width = self.gutter_width);
+        self.paint(&gutter, BLUE)
+    }
+
+    // display column of the offset in the line, with tabs expanded to the next tab stop and
+    // wide and zero-width characters taken into account
+    fn column(&self, line: &Range<usize>, offset: usize) -> usize {
+        let offset = offset.clamp(line.start, line.end);
+        self.source[line.start..offset]
+            .chars()
+            .fold(0, |column, ch| column + char_width(ch, column))
+    }
+    fn expand_tabs(text: &str) -> String {
+        let mut expanded = String::new();
+        let mut column = 0;
+        for ch in text.chars() {
+            let width = char_width(ch, column);
+            if ch == '\t' {
+                expanded.push_str(&" ".repeat(width));
+            } else {
+                expanded.push(ch);
+            }
+            column += width;
+        }
+        expanded
+    }
+
+    fn write_line(&self, line_number: usize, message: &mut impl Write) -> fmt::Result {
+        let line = &self.source[self.file.line_range(line_number)];
+        writeln!(
+            message,
+            "{}{}",
+            self.gutter(Some(line_number)),
+            Self::expand_tabs(line)
+        )
+    }
+    // writes the marks as runs of the same character, colored by the kind of mark
+    fn write_marks(&self, marks: &[char], message: &mut impl Write) -> fmt::Result {
+        let mut i = 0;
+        while i < marks.len() {
+            let mark = marks[i];
+            let run = marks[i..].iter().take_while(|m| **m == mark).count();
+            let text: String = marks[i..i + run].iter().collect();
+            let style = match mark {
+                ' ' => "",
+                '^' => RED,
+                '+' => GREEN,
+                _ => BLUE,
+            };
+            if style.is_empty() {
+                write!(message, "{}", text)?;
+            } else {
+                write!(message, "{}", self.paint(&text, style))?;
+            }
+            i += run;
+        }
+        Ok(())
+    }
+
+    // writes the line, and marks the parts of it covered by the spans below it.
+    // the messages of the spans ending on this line are written after or below the marks
+    fn highlight_line(
+        &self,
+        line_number: usize,
+        spans: &[(&Range<usize>, char, &str)],
+        message: &mut impl Write,
+    ) -> fmt::Result {
+        self.write_line(line_number, message)?;
+
+        let line = &self.file.line_range(line_number);
+        let mut marks = vec![' '; self.column(line, line.end) + 1];
+        let mut labels = vec![];
+
+        // later spans are drawn first, so the primary span (which comes first) is drawn over them
+        for (span, mark, label) in spans.iter().rev() {
+            if !touches_line(span, line) {
+                continue;
+            }
+            let start = self.column(line, span.start);
+            let end = usize::max(self.column(line, span.end), start + 1);
+            for m in &mut marks[start..end] {
+                *m = *mark;
+            }
+            if span.end <= line.end && !label.is_empty() {
+                labels.insert(0, (start, end, *label));
+            }
+        }
+        while marks.last() == Some(&' ') {
+            marks.pop();
+        }
+
+        // a label that ends the marks is written right after them, the others below their span
+        write!(message, "{}", self.gutter(None))?;
+        self.write_marks(&marks, message)?;
+        let mut below = vec![];
+        for (start, end, label) in labels {
+            if end >= marks.len() && below.is_empty() {
+                write!(message, " {}", label)?;
+            } else {
+                below.push((start, label));
+            }
+        }
+        writeln!(message)?;
+        for (start, label) in below {
+            writeln!(
+                message,
+                "{}{}{}",
+                self.gutter(None),
+                " ".repeat(start),
+                label
+            )?;
+        }
+        Ok(())
+    }
+
+    fn render(&self, error: &Diagnostic, message: &mut impl Write) -> fmt::Result {
+        // print the error location before the source
+        let (line, column) = self.file.line_column(error.range.start);
+        let severity = match error.severity {
+            Severity::Error => self.paint("Error", RED),
+            Severity::Warning => self.paint("Warning", YELLOW),
+        };
+        writeln!(
+            message,
+            "{} at line: {}, column: {}",
+            severity, line, column
+        )?;
+        let title = format!("[{}] {}", error.code, error.message);
+        writeln!(message, "{}", self.paint(&title, BOLD))?;
+
+        // the primary span is marked with '^', the labels with '-'
+        let mut spans = vec![(error.range, '^', "")];
+        spans.extend(
+            error
+                .labels
+                .iter()
+                .map(|label| (&label.range, '-', label.message.as_str())),
+        );
+
+        let mut highlighted = BTreeSet::new();
+        for (span, ..) in &spans {
+            let lines = self.file.line_index(span.start)..=self.file.line_index(span.end);
+            highlighted.extend(lines.filter(|i| touches_line(span, &self.file.line_range(*i))));
+        }
+        // the empty line after the last line ending isn't worth showing as context
+        let last_line = self.file.line_count() - 1;
+        let last_line = if last_line > 0 && self.file.line_range(last_line).is_empty() {
+            last_line - 1
+        } else {
+            last_line
+        };
+        let shown: BTreeSet<usize> = highlighted
+            .iter()
+            .flat_map(|i| i.saturating_sub(CONTEXT_LINES)..=i + CONTEXT_LINES)
+            .filter(|i| *i <= last_line || highlighted.contains(i))
+            .collect();
+
+        let mut previous = None;
+        for line_number in shown {
+            // skipped lines between the shown ones
+            if previous.is_some_and(|previous| previous + 1 < line_number) {
+                writeln!(
+                    message,
+                    "{}",
+                    self.paint(
+                        &format!(" {:>width$} |", "...", width = self.gutter_width),
+                        BLUE
+                    )
+                )?;
+            }
+            if highlighted.contains(&line_number) {
+                self.highlight_line(line_number, &spans, message)?;
+            } else {
+                self.write_line(line_number, message)?;
+            }
+            previous = Some(line_number);
+        }
+
+        for note in error.notes {
+            writeln!(message, " = {} {}", self.paint("note:", BOLD), note)?;
+        }
+        for suggestion in error.suggestions {
+            writeln!(
+                message,
+                " = {} {}",
+                self.paint("help:", CYAN),
+                suggestion.message
+            )?;
+            self.suggestion(&suggestion.range, &suggestion.replacement, message)?;
+        }
+        Ok(())
+    }
+    // prints the lines of the suggestion with the replacement applied, and marks a single line
+    // replacement with '+'
+    fn suggestion(
+        &self,
+        range: &Range<usize>,
+        replacement: &str,
+        message: &mut impl Write,
+    ) -> fmt::Result {
+        let first_line = self.file.line_index(range.start);
+        let last_line = self.file.line_index(range.end);
+        let prefix = &self.source[self.file.line_range(first_line).start..range.start];
+        let fixed = format!(
+            "{}{}{}",
+            prefix,
+            replacement,
+            &self.source[range.end..self.file.line_range(last_line).end]
+        );
+
+        for (i, line) in fixed.split('\n').enumerate() {
+            writeln!(
+                message,
+                "{}{}",
+                self.gutter(Some(first_line + i)),
+                Self::expand_tabs(line)
+            )?;
+        }
+        if !replacement.contains('\n') && !replacement.is_empty() {
+            let start = prefix
+                .chars()
+                .fold(0, |column, ch| column + char_width(ch, column));
+            let end = format!("{}{}", prefix, replacement)
+                .chars()
+                .fold(0, |column, ch| column + char_width(ch, column));
+            let mut marks = vec![' '; start];
+            marks.resize(usize::max(end, start + 1), '+');
+            write!(message, "{}", self.gutter(None))?;
+            self.write_marks(&marks, message)?;
+            writeln!(message)?;
+        }
+        Ok(())
+    }
+}
+
+// columns taken by the character when it starts at `column`
+fn char_width(ch: char, column: usize) -> usize {
+    if ch == '\t' {
+        TAB_WIDTH - column % TAB_WIDTH
+    } else {
+        ch.width().unwrap_or(0)
+    }
+}
+
+// whether the span shows up on the line, empty spans show up as a single '^' where they are
+fn touches_line(span: &Range<usize>, line: &Range<usize>) -> bool {
+    if span.is_empty() {
+        line.start <= span.start && span.start <= line.end
+    } else {
+        span.start < line.end && span.end > line.start
+    }
+}
+
+pub(super) fn render_error(
+    error: &Error,
+    file: &SourceFile,
+    color: bool,
+    message: &mut impl Write,
+) -> fmt::Result {
+    let diagnostic = Diagnostic {
+        severity: error.severity,
+        code: error.error.code(),
+        message: error.error.to_string(),
+        range: &error.range,
+        labels: &error.labels,
+        notes: &error.notes,
+        suggestions: &error.suggestions,
+    };
+    render_diagnostic(&diagnostic, file, color, message)
+}
+pub(crate) fn render_diagnostic(
+    diagnostic: &Diagnostic,
+    file: &SourceFile,
+    color: bool,
+    message: &mut impl Write,
+) -> fmt::Result {
+    Renderer::new(file, color).render(diagnostic, message)
+}
@@ -6,7 +6,12 @@ impl<'a> Parser<'a> {
     fn requires_semicolon(statement: &Statement) -> bool {
         matches!(
             statement,
-            Statement::Expression(_) | Statement::Declaration(Declaration::Var(_))
+            Statement::Expression(_)
+                | Statement::Declaration(Declaration::Var(_))
+                | Statement::Break
+                | Statement::Continue
+                | Statement::Return(_)
+                | Statement::Throw(_)
         )
     }
     pub(super) fn statements(&mut self) -> ParseResultOption<Block> {
@@ -14,7 +19,9 @@ impl<'a> Parser<'a> {
         while let Some(stmt) = self.statement()? {
             let end = if Self::requires_semicolon(&stmt.data) {
                 let Some(semicolon) = self.symbol_eq(Symbol::Semicolon) else {
-                    return Err(Error::new(stmt.range, ErrorType::ExpectedSemicolon));
+                    let end = stmt.end();
+                    return Err(Error::new(stmt.range, ErrorType::ExpectedSemicolon)
+                        .with_suggestion(end..end, ";", "add a ';' after the statement"));
                 };
                 semicolon.end()
             } else {
@@ -35,6 +42,7 @@ impl<'a> Parser<'a> {
         Ok(statements)
     }
     pub(super) fn statement(&mut self) -> ParseResultOption<Statement> {
+        self.unknown_keyword()?;
         if let Some(declaration) = self.declaration()? {
             return Ok(Some(declaration.convert(Statement::Declaration)));
         }
@@ -44,15 +52,43 @@ impl<'a> Parser<'a> {
         if let Some(while_stmt) = self.while_statement()? {
             return Ok(Some(while_stmt.convert(Statement::While)))
         }
+        if let Some(try_stmt) = self.try_statement()? {
+            return Ok(Some(try_stmt.convert(Statement::Try)));
+        }
         if let Some(block) = self.block()? {
             return Ok(Some(block.convert(Statement::Block)));
         }
+        if let Some(jump) = self.jump_statement()? {
+            return Ok(Some(jump));
+        }
         if let Some(expr) = self.expression()? {
             return Ok(Some(expr.convert(Statement::Expression)));
         }
 
         Ok(None)
     }
+    // `break`, `continue`, `return [expr]` and `throw expr`
+    fn jump_statement(&mut self) -> ParseResultOption<Statement> {
+        let Some(keyword) = self.keyword_if(|kw| {
+            matches!(kw, Keyword::Break | Keyword::Continue | Keyword::Return | Keyword::Throw)
+        }) else {
+            return Ok(None);
+        };
+        Ok(Some(match keyword.data {
+            Keyword::Break => keyword.replace(Statement::Break),
+            Keyword::Continue => keyword.replace(Statement::Continue),
+            Keyword::Throw => {
+                let Some(expr) = self.expression()? else {
+                    return Err(Error::new(keyword.range, ErrorType::ExpectedExpr));
+                };
+                ParseNode::new(keyword.start()..expr.end(), Statement::Throw(expr))
+            }
+            _ => match self.expression()? {
+                Some(expr) => ParseNode::new(keyword.start()..expr.end(), Statement::Return(Some(expr))),
+                None => keyword.replace(Statement::Return(None)),
+            },
+        }))
+    }
     fn onbreak_block(&mut self) -> ParseResultOption<Block> {
         let Some(onbreak_keyword) = self.keyword_eq(Keyword::OnBreak) else {
             return Ok(None);
@@ -113,6 +149,48 @@ impl<'a> Parser<'a> {
             on_continue,
         })))
     }
+    // `try {} catch e {} finally {}`, with a catch block, a finally block or both
+    pub(super) fn try_statement(&mut self) -> ParseResultOption<TryStatement> {
+        let Some(try_keyword) = self.keyword_eq(Keyword::Try) else {
+            return Ok(None);
+        };
+        let Some(try_block) = self.block()? else {
+            return Err(Error::new(try_keyword.range, ErrorType::ExpectedBlock));
+        };
+        let mut end = try_block.end();
+
+        let mut catch = None;
+        if let Some(catch_keyword) = self.keyword_eq(Keyword::Catch) {
+            let Some(name) = self.var_name()? else {
+                return Err(Error::new(catch_keyword.range, ErrorType::ExpectedVarName));
+            };
+            let Some(block) = self.block()? else {
+                return Err(Error::new(
+                    catch_keyword.start()..name.end(),
+                    ErrorType::ExpectedBlock,
+                ));
+            };
+            end = block.end();
+            catch = Some(ParseNode::new(catch_keyword.start()..end, CatchBlock { name, block }));
+        }
+        let mut finally = None;
+        if let Some(finally_keyword) = self.keyword_eq(Keyword::Finally) {
+            let Some(block) = self.block()? else {
+                return Err(Error::new(finally_keyword.range, ErrorType::ExpectedBlock));
+            };
+            end = block.end();
+            finally = Some(ParseNode::new(finally_keyword.start()..end, block.data));
+        }
+        if catch.is_none() && finally.is_none() {
+            return Err(Error::new(try_keyword.start()..end, ErrorType::ExpectedCatch));
+        }
+
+        Ok(Some(ParseNode::new(try_keyword.start()..end, TryStatement {
+            try_block,
+            catch,
+            finally,
+        })))
+    }
     pub(super) fn if_statement(&mut self) -> ParseResultOption<IfStatement> {
         let Some(if_keyword) = self.keyword_eq(Keyword::If) else {
             return Ok(None);
@@ -146,6 +224,7 @@ impl<'a> Parser<'a> {
         }
         let mut else_block: Option<ParseNode<ElseBlock>> = None;
         if let Some(else_keyword) = self.keyword_eq(Keyword::Else) {
+            self.else_if(&else_keyword)?;
             let Some(block) = self.block()? else {
                 return Err(Error::new(else_keyword.range, ErrorType::ExpectedBlock));
             };
@@ -185,13 +264,8 @@ impl<'a> Parser<'a> {
         };
         let statements = self.statements()?;
         let Some(rcurly) = self.symbol_eq(Symbol::RCurlyBracket) else {
-            return Err(Error::new(
-                statements
-                    .as_ref()
-                    .map(|s| lcurly.start()..s.end())
-                    .unwrap_or(lcurly.range.clone()),
-                ErrorType::ExpectedRCurly,
-            ));
+            let end = statements.as_ref().map_or(lcurly.end(), |s| s.end());
+            return Err(Error::unclosed(Symbol::RCurlyBracket, lcurly.range, end));
         };
         Ok(Some(ParseNode::new(
             lcurly.start()..rcurly.end(),
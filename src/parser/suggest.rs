@@ -0,0 +1,266 @@
+use std::ops::Range;
+
+use crate::string_name::StringName;
+
+use super::error::*;
+use super::parse_node::*;
+use super::parser::*;
+
+// words other languages use for a keyword of this one
+const FOREIGN_KEYWORDS: [(&str, Keyword); 6] = [
+    ("var", Keyword::Var),
+    ("fun", Keyword::Func),
+    ("function", Keyword::Func),
+    ("elseif", Keyword::Elif),
+    ("elsif", Keyword::Elif),
+    ("raise", Keyword::Throw),
+];
+// keywords starting a statement followed by an expression, checked for misspellings
+const STATEMENT_KEYWORDS: [Keyword; 6] = [
+    Keyword::Var,
+    Keyword::Func,
+    Keyword::If,
+    Keyword::While,
+    Keyword::Return,
+    Keyword::Throw,
+];
+
+/// The keyword other languages write as `name`, such as `none` for `null`.
+pub(crate) fn foreign_value(name: &str) -> Option<Keyword> {
+    match name {
+        "null" | "nil" => Some(Keyword::None),
+        _ => None,
+    }
+}
+
+/// Number of single character insertions, removals, replacements and swaps of adjacent
+/// characters needed to turn `a` into `b`.
+pub(crate) fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // distances of the prefixes of `a` to the prefixes of `b`, for the last two rows
+    let mut before: Vec<usize> = vec![0; b.len() + 1];
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for i in 1..=a.len() {
+        let mut current = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            current[j] = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(before[j - 2] + 1);
+            }
+        }
+        before = std::mem::replace(&mut previous, current);
+    }
+    previous[b.len()]
+}
+
+/// The candidate closest to `name`, if it's close enough to be a likely typo of it.
+pub(crate) fn closest<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    let max_distance = usize::max(1, name.chars().count() / 3);
+    candidates
+        .into_iter()
+        .filter(|candidate| *candidate != name)
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+impl<'a> Parser<'a> {
+    // a statement starting with a keyword of another language (`var x`) or a misspelled one
+    // (`retrun x`). these are identifiers, so it's only an error when another value follows them
+    pub(super) fn unknown_keyword(&mut self) -> Result<()> {
+        let old = self.iter.clone();
+        let Some(word) = self.ident() else {
+            return Ok(());
+        };
+        let name = word.data.as_str();
+        let keyword = FOREIGN_KEYWORDS
+            .iter()
+            .find(|(foreign, _)| *foreign == name)
+            .map(|(_, keyword)| *keyword)
+            .or_else(|| {
+                if name.chars().count() <= 2 {
+                    return None;
+                }
+                let spellings = STATEMENT_KEYWORDS.map(|keyword| keyword.as_str());
+                let spelling = closest(name, spellings)?;
+                Keyword::try_from(spelling).ok()
+            });
+        let followed_by_value = self.ident().is_some()
+            || self
+                .keyword_if(|k| matches!(k, Keyword::None | Keyword::True | Keyword::False))
+                .is_some()
+            || matches!(self.number(), Ok(Some(_)))
+            || matches!(self.string(), Ok(Some(_)))
+            || matches!(self.char(), Ok(Some(_)));
+        self.iter = old;
+
+        match keyword {
+            Some(keyword) if followed_by_value => {
+                Err(Self::keyword_error(word.data, word.range, keyword))
+            }
+            _ => Ok(()),
+        }
+    }
+    // `else if`, which is written `elif`
+    pub(super) fn else_if(&mut self, else_keyword: &ParseNode<Keyword>) -> Result<()> {
+        let old = self.iter.clone();
+        let if_keyword = self.keyword_eq(Keyword::If);
+        self.iter = old;
+
+        match if_keyword {
+            Some(if_keyword) => Err(Self::keyword_error(
+                "else if".into(),
+                else_keyword.start()..if_keyword.end(),
+                Keyword::Elif,
+            )),
+            None => Ok(()),
+        }
+    }
+    fn keyword_error(found: StringName, range: Range<usize>, keyword: Keyword) -> Error {
+        Error::new(range.clone(), ErrorType::UnknownKeyword(found)).with_suggestion(
+            range,
+            keyword.as_str(),
+            format!("did you mean '{}'?", keyword),
+        )
+    }
+    // `&&` and `||`, which are written `and` and `or`. `symbol` is the first of the two
+    pub(super) fn doubled_operator(&mut self, symbol: &ParseNode<Symbol>) -> Result<()> {
+        let (operator, keyword) = match symbol.data {
+            Symbol::And => ("&&", Keyword::And),
+            Symbol::Or => ("||", Keyword::Or),
+            _ => return Ok(()),
+        };
+        if self.iter.peek().map(|(_, c)| *c) != symbol.data.as_str().chars().next() {
+            return Ok(());
+        }
+        let range = symbol.start()..symbol.end() + 1;
+
+        // the keyword needs spaces around it, where the operator may not have them
+        let mut replacement = keyword.as_str().to_string();
+        if !self.source[..range.start].ends_with(char::is_whitespace) {
+            replacement.insert(0, ' ');
+        }
+        if !self.source[range.end..].starts_with(char::is_whitespace) {
+            replacement.push(' ');
+        }
+
+        Err(
+            Error::new(range.clone(), ErrorType::UnknownOperator(operator)).with_suggestion(
+                range,
+                replacement,
+                format!("did you mean '{}'?", keyword),
+            ),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the text of the source the parse error replaces, and what it's replaced with
+    fn suggestion(source: &str) -> (String, String) {
+        let error = Parser::new(source).parse().unwrap_err();
+        let [suggestion] = &error.suggestions[..] else {
+            panic!("expected one suggestion, found {:?}", error);
+        };
+        (
+            source[suggestion.range.clone()].to_string(),
+            suggestion.replacement.clone(),
+        )
+    }
+    fn pair(found: &str, replacement: &str) -> (String, String) {
+        (found.to_string(), replacement.to_string())
+    }
+
+    #[test]
+    fn edit_distance_counts_every_kind_of_edit() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("while", "while"), 0);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("abc", ""), 3);
+        assert_eq!(edit_distance("whle", "while"), 1);
+        assert_eq!(edit_distance("whiile", "while"), 1);
+        assert_eq!(edit_distance("whale", "while"), 1);
+        // a swap of adjacent characters is one edit, not two replacements
+        assert_eq!(edit_distance("retrun", "return"), 1);
+        assert_eq!(edit_distance("ab", "ba"), 1);
+        assert_eq!(edit_distance("abc", "ca"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        // characters, not bytes
+        assert_eq!(edit_distance("café", "cafe"), 1);
+        assert_eq!(edit_distance("日本", "本日"), 1);
+    }
+
+    #[test]
+    fn closest_picks_the_nearest_close_enough_name() {
+        let names = ["count", "counter", "total"];
+        assert_eq!(closest("coutn", names), Some("count"));
+        assert_eq!(closest("countre", names), Some("counter"));
+        assert_eq!(closest("totl", names), Some("total"));
+        // the first of the nearest ones
+        assert_eq!(closest("counte", names), Some("count"));
+        // a name isn't a typo of itself
+        assert_eq!(closest("count", ["count"]), None);
+        // a third of the length, but at least one edit
+        assert_eq!(closest("ab", ["xy"]), None);
+        assert_eq!(closest("ab", ["ax"]), Some("ax"));
+        assert_eq!(closest("abcdef", ["abcxyz"]), None);
+        assert_eq!(closest("abcdef", ["abcdxy"]), Some("abcdxy"));
+        assert_eq!(closest("x", []), None);
+    }
+
+    #[test]
+    fn foreign_values() {
+        assert_eq!(foreign_value("null"), Some(Keyword::None));
+        assert_eq!(foreign_value("nil"), Some(Keyword::None));
+        assert_eq!(foreign_value("none"), None);
+        assert_eq!(foreign_value("nul"), None);
+    }
+
+    #[test]
+    fn foreign_and_misspelled_keywords() {
+        assert_eq!(suggestion("var x = 1;"), pair("var", "let"));
+        assert_eq!(suggestion("function f() {}"), pair("function", "func"));
+        assert_eq!(suggestion("raise \"oops\";"), pair("raise", "throw"));
+        assert_eq!(
+            suggestion("func f() { retrun 1; }"),
+            pair("retrun", "return")
+        );
+        assert_eq!(suggestion("whle x {}"), pair("whle", "while"));
+        assert_eq!(suggestion("if a {} else if b {}"), pair("else if", "elif"));
+        assert_eq!(
+            suggestion("if a {} else   if b {}"),
+            pair("else   if", "elif")
+        );
+
+        // identifiers that aren't followed by a value are left alone
+        assert!(Parser::new("var;").parse().is_ok());
+        assert!(Parser::new("retrun = 1;").parse().is_ok());
+        assert!(Parser::new("whle(x);").parse().is_ok());
+        // too short to be a typo
+        let error = Parser::new("fi x {}").parse().unwrap_err();
+        assert!(!matches!(error.error, ErrorType::UnknownKeyword(_)));
+    }
+
+    #[test]
+    fn doubled_operators() {
+        assert_eq!(suggestion("a && b;"), pair("&&", "and"));
+        assert_eq!(suggestion("a || b;"), pair("||", "or"));
+        // spaces are added where the operator has none
+        assert_eq!(suggestion("a&&b;"), pair("&&", " and "));
+        assert_eq!(suggestion("a ||b;"), pair("||", "or "));
+
+        let error = Parser::new("a && b;").parse().unwrap_err();
+        assert!(matches!(error.error, ErrorType::UnknownOperator("&&")));
+    }
+}
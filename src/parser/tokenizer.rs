@@ -1,5 +1,9 @@
+use std::borrow::Cow;
 use std::ops::Range;
 
+use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+use unicode_xid::UnicodeXID;
+
 use crate::string_name::StringName;
 
 use super::error::*;
@@ -10,24 +14,69 @@ impl<'a> Parser<'a> {
     pub(super) fn ident_or_keyword(&mut self) -> ParseOption<IdentKeyword> {
         self.skip();
 
-        let mut range =
-            if let Some((i, c)) = self.iter.next_if(|(_, c)| c.is_alphabetic() || *c == '_') {
-                i..i + c.len_utf8()
-            } else {
-                return None;
-            };
+        // identifiers follow the default identifier syntax of UAX #31, with `_` as a start
+        let mut range = if let Some((i, c)) = self
+            .iter
+            .next_if(|(_, c)| (c.is_xid_start() || *c == '_') && !is_invisible(*c))
+        {
+            i..i + c.len_utf8()
+        } else {
+            return None;
+        };
 
-        while let Some((i, c)) = self.iter.next_if(|(_, c)| c.is_alphanumeric() || *c == '_') {
+        // the zero-width joiners are only valid in some contexts of a few scripts, which isn't
+        // checked here. they're allowed after the start, and the `invisible_joiners` lint warns
+        // about them
+        while let Some((i, c)) = self.iter.next_if(|(_, c)| {
+            (c.is_xid_continue() && !is_invisible(*c)) || matches!(c, '\u{200C}' | '\u{200D}')
+        }) {
             range.end = i + c.len_utf8();
         }
 
+        // the same identifier can be typed with different code points (such as 'é' and 'e'
+        // followed by a combining accent), so they're compared in their NFC form
         let ident = &self.source[range.clone()];
+        let ident: Cow<str> = match is_nfc_quick(ident.chars()) {
+            IsNormalized::Yes => ident.into(),
+            _ => ident.nfc().collect::<String>().into(),
+        };
 
-        if let Ok(k) = Keyword::try_from(ident) {
+        if let Ok(k) = Keyword::try_from(ident.as_ref()) {
             return Some(ParseNode::new(range, IdentKeyword::Keyword(k)));
         }
 
-        Some(ParseNode::new(range, IdentKeyword::Ident(ident.into())))
+        Some(ParseNode::new(
+            range,
+            IdentKeyword::Ident(ident.as_ref().into()),
+        ))
+    }
+    /// Splits the rest of the source into tokens, without the whitespace and comments.
+    pub fn tokens(&mut self) -> Result<Vec<ParseNode<Token>>> {
+        let mut tokens = vec![];
+        loop {
+            self.skip();
+            let Some(&(start, c)) = self.iter.peek() else {
+                return Ok(tokens);
+            };
+            let token = if let Some(number) = self.number()? {
+                number.convert(Token::Number)
+            } else if let Some(string) = self.string()? {
+                string.convert(Token::String)
+            } else if let Some(character) = self.char()? {
+                character.convert(Token::Char)
+            } else if let Some(word) = self.ident_or_keyword() {
+                word.convert(|word| match word {
+                    IdentKeyword::Ident(name) => Token::Ident(name),
+                    IdentKeyword::Keyword(keyword) => Token::Keyword(keyword),
+                })
+            } else if let Some(symbol) = self.symbol() {
+                symbol.convert(Token::Symbol)
+            } else {
+                let range = start..start + c.len_utf8();
+                return Err(Error::new(range, ErrorType::UnexpectedSymbol));
+            };
+            tokens.push(token);
+        }
     }
     pub(super) fn ident(&mut self) -> ParseOption<StringName> {
         let old = self.iter.clone();
@@ -40,23 +89,6 @@ impl<'a> Parser<'a> {
         self.iter = old;
         None
     }
-    pub(super) fn ident_if(
-        &mut self,
-        predicate: impl FnOnce(StringName) -> bool,
-    ) -> ParseOption<StringName> {
-        let old = self.iter.clone();
-
-        let ident = self.ident()?;
-        if predicate(ident.data) {
-            return Some(ident);
-        }
-
-        self.iter = old;
-        None
-    }
-    pub(super) fn ident_eq(&mut self, ident: StringName) -> ParseOption<StringName> {
-        self.ident_if(|i| i == ident)
-    }
     pub(super) fn keyword(&mut self) -> ParseOption<Keyword> {
         let old = self.iter.clone();
 
@@ -92,6 +124,7 @@ impl<'a> Parser<'a> {
         let mut last = self.iter.clone();
         let mut symbol = None;
         let mut range = 0..0;
+        let mut symbol_end = 0;
 
         for len in 0..MAX_SYMBOL_LENGTH {
             if let Some((i, c)) = self.iter.next() {
@@ -104,6 +137,7 @@ impl<'a> Parser<'a> {
 
                 if let Ok(s) = Symbol::try_from(symbol_str) {
                     symbol = Some(s);
+                    symbol_end = range.end;
                     last = self.iter.clone();
                 }
             } else {
@@ -112,7 +146,7 @@ impl<'a> Parser<'a> {
         }
 
         self.iter = last;
-        symbol.map(|s| ParseNode::new(range, s))
+        symbol.map(|s| ParseNode::new(range.start..symbol_end, s))
     }
     pub(super) fn symbol_if(
         &mut self,
@@ -136,31 +170,110 @@ impl<'a> Parser<'a> {
             if self.iter.next_if(|(_, c)| c.is_whitespace()).is_some() {
                 continue;
             }
+            if self.comment().is_some() {
+                continue;
+            }
+            break;
+        }
+    }
+    // line (`// ...`) or block (`/* ... */`) comment, the range covers the comment markers.
+    // a `#!` line at the very start of the source is a comment too, so scripts can be executables
+    pub(super) fn comment(&mut self) -> Option<Range<usize>> {
+        let old = self.iter.clone();
+        let is_slash = |(_, c): &(usize, char)| *c == '/';
 
-            let old = self.iter.clone();
-            let is_slash = |(_, c): &(usize, char)| *c == '/';
+        if self.iter.next_if(|(i, c)| *i == 0 && *c == '#').is_some() {
+            if self.iter.next_if(|(_, c)| *c == '!').is_some() {
+                return Some(self.line_comment(0, 2));
+            }
+            self.iter = old;
+            return None;
+        }
 
-            if self.iter.next_if(is_slash).is_some() {
-                if self.iter.next_if(is_slash).is_some() {
-                    for (_, c) in self.iter.by_ref() {
-                        if c == '\n' {
-                            break;
-                        }
-                    }
-                    continue;
-                }
-                if self.iter.next_if(|(_, c)| *c == '*').is_some() {
-                    while let Some((_, c)) = self.iter.next() {
-                        if c == '*' && self.iter.next_if(|(_, c)| *c == '/').is_some() {
-                            break;
-                        }
+        let (start, _) = self.iter.next_if(is_slash)?;
+        let end = start + 1;
+
+        if self.iter.next_if(is_slash).is_some() {
+            return Some(self.line_comment(start, end + 1));
+        }
+        if self.iter.next_if(|(_, c)| *c == '*').is_some() {
+            let mut end = end + 1;
+            while let Some((i, c)) = self.iter.next() {
+                end = i + c.len_utf8();
+                if c == '*' {
+                    if let Some((i, c)) = self.iter.next_if(|(_, c)| *c == '/') {
+                        end = i + c.len_utf8();
+                        break;
                     }
-                    continue;
                 }
             }
+            return Some(start..end);
+        }
+
+        self.iter = old;
+        None
+    }
+    // the rest of a line comment starting at `start`, with its markers ending at `end`
+    fn line_comment(&mut self, start: usize, mut end: usize) -> Range<usize> {
+        while let Some((i, c)) = self.iter.next_if(|(_, c)| *c != '\n') {
+            end = i + c.len_utf8();
+        }
+        start..start + self.source[start..end].trim_end().len()
+    }
+    // collects the ranges of every comment in the source, skipping over string and character literals
+    pub fn comments(&mut self) -> Vec<Range<usize>> {
+        let mut comments = vec![];
+
+        loop {
+            if self.iter.next_if(|(_, c)| c.is_whitespace()).is_some() {
+                continue;
+            }
+            if let Some(comment) = self.comment() {
+                comments.push(comment);
+                continue;
+            }
+
+            let old = self.iter.clone();
+            if matches!(self.string(), Ok(Some(_)))
+                || matches!(self.char(), Ok(Some(_)))
+                || self.ident_or_keyword().is_some()
+            {
+                continue;
+            }
 
             self.iter = old;
-            break;
+            if self.iter.next().is_none() {
+                break;
+            }
+        }
+
+        comments
+    }
+    // the first invisible character outside of strings and comments, which could make the code
+    // read differently from how it runs
+    pub(super) fn invisible_char(&mut self) -> Option<Error> {
+        loop {
+            self.skip();
+            let old = self.iter.clone();
+            if matches!(self.string(), Ok(Some(_)))
+                || matches!(self.char(), Ok(Some(_)))
+                || self.ident_or_keyword().is_some()
+            {
+                continue;
+            }
+
+            self.iter = old;
+            let (i, c) = self.iter.next()?;
+            if is_invisible(c) {
+                let range = i..i + c.len_utf8();
+                return Some(
+                    Error::new(range.clone(), ErrorType::InvisibleChar(c)).with_suggestion(
+                        range,
+                        "",
+                        "remove the character",
+                    ),
+                );
+            }
         }
     }
     fn parse_string(
@@ -181,7 +294,7 @@ impl<'a> Parser<'a> {
                 range.end = i + c.len_utf8();
 
                 if c == '\r' {
-                    if self.iter.next_if(|(i, c)| *c == '\n').is_some() {
+                    if self.iter.next_if(|(_, c)| *c == '\n').is_some() {
                         range.end = i + c.len_utf8();
                     }
                     out.push('\n');
@@ -201,8 +314,10 @@ impl<'a> Parser<'a> {
                             '"' => '"',
                             'x' | 'u' => {
                                 let is_ascii = c == 'x';
+                                let mut lcurly = 0..0;
                                 if !is_ascii {
                                     if let Some((i, c)) = self.iter.next_if(|(_, c)| *c == '{') {
+                                        lcurly = i..i + c.len_utf8();
                                         range.end = i + c.len_utf8();
                                     } else {
                                         return Err(Error::new(range, ErrorType::ExpectedLCurly));
@@ -210,7 +325,7 @@ impl<'a> Parser<'a> {
                                 }
 
                                 let mut char_code = 0;
-                                for digit in 0..(if is_ascii { 2 } else { 4 }) {
+                                for _ in 0..(if is_ascii { 2 } else { 4 }) {
                                     if let Some((i, c)) =
                                         self.iter.next_if(|(_, c)| c.is_ascii_hexdigit())
                                     {
@@ -230,7 +345,11 @@ impl<'a> Parser<'a> {
                                     if let Some((i, c)) = self.iter.next_if(|(_, c)| *c == '}') {
                                         range.end = i + c.len_utf8();
                                     } else {
-                                        return Err(Error::new(range, ErrorType::ExpectedRCurly));
+                                        return Err(Error::unclosed(
+                                            Symbol::RCurlyBracket,
+                                            lcurly,
+                                            range.end,
+                                        ));
                                     }
                                 }
 
@@ -382,28 +501,18 @@ impl<'a> Parser<'a> {
             return Ok(Some(number));
         }
 
-        let mut decimal = match number.data {
-            Number::Int(i) => i as f64,
-            Number::Real(r) => r,
-        };
-
-        let mut is_negative = false;
         if let Some((i, c)) = self.iter.next_if(|(_, c)| matches!(c, '+' | '-')) {
             number.range.end = i + c.len_utf8();
-            is_negative = c == '-';
         }
 
         let Some(exponent) = self.integer(10)? else {
             return Err(Error::new(number.range, ErrorType::ExpectedInteger));
         };
 
-        for _ in 0..exponent.data {
-            decimal *= if is_negative { 0.1 } else { 10.0 };
-        }
-
+        let range = number.start()..exponent.end();
         Ok(Some(ParseNode::new(
-            number.start()..exponent.end(),
-            Number::Real(decimal),
+            range.clone(),
+            Number::Real(self.real(range)),
         )))
     }
     fn decimal(&mut self) -> ParseResultOption<Number> {
@@ -421,24 +530,22 @@ impl<'a> Parser<'a> {
             return Ok(Some(integer.convert(|i| Number::Real(i as f64))));
         };
 
-        let mut real = decimal.data as f64;
-
-        for _ in 0..decimal.range.len() {
-            real *= 0.1;
-        }
-        real += integer.data as f64;
-
+        let range = integer.start()..decimal.end();
         Ok(Some(ParseNode::new(
-            integer.start()..decimal.end(),
-            Number::Real(real),
+            range.clone(),
+            Number::Real(self.real(range)),
         )))
     }
+    // the digits are already validated, so the standard library can do the (correctly rounded) conversion
+    fn real(&self, range: Range<usize>) -> f64 {
+        self.source[range].parse().unwrap_or(f64::INFINITY)
+    }
     fn radix(&mut self) -> ParseOption<u32> {
         let mut radix = 10;
         let mut range = 0..0;
         let mut iter = self.iter.clone();
 
-        if let Some((i, _)) = iter.next_if(|(_, c)| c.is_alphabetic()) {
+        if let Some((i, _)) = iter.next_if(|(_, c)| *c == '0') {
             range.start = i;
             if let Some((i, c)) = iter.next() {
                 range.end = i + c.len_utf8();
@@ -483,3 +590,23 @@ impl<'a> Parser<'a> {
         Ok(Some(ParseNode::new(range, num)))
     }
 }
+
+// characters with no visible glyph, that can hide in code or change how it's displayed:
+// zero-width characters, bidirectional controls, invisible operators and the byte order mark
+pub(super) fn is_invisible(c: char) -> bool {
+    matches!(
+        c,
+        '\u{AD}'
+            | '\u{34F}'
+            | '\u{61C}'
+            | '\u{180E}'
+            | '\u{200B}'..='\u{200F}'
+            | '\u{202A}'..='\u{202E}'
+            | '\u{2060}'..='\u{2064}'
+            | '\u{2066}'..='\u{2069}'
+            | '\u{3164}'
+            | '\u{FE00}'..='\u{FE0F}'
+            | '\u{FEFF}'
+            | '\u{FFA0}'
+    )
+}
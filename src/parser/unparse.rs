@@ -0,0 +1,494 @@
+// turns syntax trees back into source code (the `Display` impls of the nodes)
+// groupings are not kept, parentheses are only added where operator precedence requires them,
+// or where a grouping changes how the expression runs. parsing the output gives back the same
+// tree, apart from the groupings
+
+use std::fmt::{self, Display, Write};
+
+use crate::string_name::StringName;
+
+use super::parse_node::*;
+
+const INDENT: &str = "    ";
+
+/// Prints the statements as source code, one statement per line.
+pub fn unparse(statements: &[ParseNode<Statement>]) -> String {
+    let mut out = String::new();
+    for stmt in statements {
+        writeln!(out, "{}", stmt.data).unwrap();
+    }
+    out
+}
+
+// higher binds tighter
+fn precedence(expr: &Expression) -> u8 {
+    match expr {
+        Expression::Assign(_) => 0,
+        Expression::Binary(binary) => match binary.operator {
+            SymbolKeyword::Keyword(Keyword::Or) => 1,
+            SymbolKeyword::Keyword(_) => 2,
+            SymbolKeyword::Symbol(s) => binary_precedence(s),
+        },
+        Expression::Unary(_) => 11,
+        Expression::Grouping(expr) => precedence(&expr.data),
+        _ => 13,
+    }
+}
+fn binary_precedence(symbol: Symbol) -> u8 {
+    match symbol {
+        Symbol::Or => 3,
+        Symbol::Xor => 4,
+        Symbol::And => 5,
+        Symbol::Eq | Symbol::NotEq => 6,
+        Symbol::Less | Symbol::Greater | Symbol::LessEq | Symbol::GreaterEq => 7,
+        Symbol::LeftShift | Symbol::RightShift => 8,
+        Symbol::Add | Symbol::Sub => 9,
+        Symbol::Mul | Symbol::Div | Symbol::Mod => 10,
+        Symbol::Pow => 12,
+        _ => 13,
+    }
+}
+// groupings are transparent, the parentheses are decided by the precedence of their content
+fn ungroup(expr: &Expression) -> &Expression {
+    match expr {
+        Expression::Grouping(expr) => ungroup(&expr.data),
+        expr => expr,
+    }
+}
+fn operand(f: &mut fmt::Formatter<'_>, expr: &Expression, min_precedence: u8) -> fmt::Result {
+    if precedence(expr) < min_precedence {
+        write!(f, "({})", ungroup(expr))
+    } else {
+        write!(f, "{}", ungroup(expr))
+    }
+}
+// a statement starting with '{' is parsed as a block instead of a dictionary
+fn starts_with_dictionary(expr: &Expression) -> bool {
+    match expr {
+        Expression::Dictionary(_) => true,
+        Expression::Binary(binary) => starts_with_dictionary(&binary.left.data),
+        Expression::Assign(assign) => starts_with_dictionary(&assign.left.data),
+        Expression::Suffix(suffix) => starts_with_dictionary(&suffix.node.data),
+        Expression::Grouping(expr) => starts_with_dictionary(&expr.data),
+        _ => false,
+    }
+}
+fn list<T>(
+    f: &mut fmt::Formatter<'_>,
+    items: &[T],
+    mut item_fn: impl FnMut(&mut fmt::Formatter<'_>, &T) -> fmt::Result,
+) -> fmt::Result {
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        item_fn(f, item)?;
+    }
+    Ok(())
+}
+fn escape(f: &mut fmt::Formatter<'_>, c: char, quote: char) -> fmt::Result {
+    match c {
+        '\n' => write!(f, "\\n"),
+        '\t' => write!(f, "\\t"),
+        '\r' => write!(f, "\\r"),
+        '\0' => write!(f, "\\0"),
+        '\\' => write!(f, "\\\\"),
+        c if c == quote => write!(f, "\\{}", c),
+        c if c.is_ascii_control() => write!(f, "\\x{:02x}", c as u32),
+        c => write!(f, "{}", c),
+    }
+}
+fn block(f: &mut fmt::Formatter<'_>, block: &Block, indent: usize) -> fmt::Result {
+    if block.statements.is_empty() {
+        return write!(f, "{{}}");
+    }
+    writeln!(f, "{{")?;
+    for stmt in &block.statements {
+        write!(f, "{}", INDENT.repeat(indent + 1))?;
+        statement(f, &stmt.data, indent + 1)?;
+        writeln!(f)?;
+    }
+    write!(f, "{}}}", INDENT.repeat(indent))
+}
+fn statement(f: &mut fmt::Formatter<'_>, stmt: &Statement, indent: usize) -> fmt::Result {
+    match stmt {
+        Statement::Declaration(Declaration::Var(decl)) => write!(f, "{} {};", Keyword::Var, decl),
+        Statement::Declaration(Declaration::Func(decl)) => func_decl(f, decl, indent),
+        Statement::Expression(expr) if starts_with_dictionary(expr) => write!(f, "({});", expr),
+        Statement::Expression(expr) => write!(f, "{};", expr),
+        Statement::If(if_stmt) => if_statement(f, if_stmt, Keyword::If, indent),
+        Statement::While(while_stmt) => while_statement(f, while_stmt, indent),
+        Statement::Try(try_stmt) => try_statement(f, try_stmt, indent),
+        Statement::Block(stmts) => block(f, stmts, indent),
+        Statement::Break => write!(f, "{};", Keyword::Break),
+        Statement::Continue => write!(f, "{};", Keyword::Continue),
+        Statement::Return(Some(expr)) => write!(f, "{} {};", Keyword::Return, expr),
+        Statement::Return(None) => write!(f, "{};", Keyword::Return),
+        Statement::Throw(expr) => write!(f, "{} {};", Keyword::Throw, expr),
+    }
+}
+fn func_decl(f: &mut fmt::Formatter<'_>, decl: &FuncDecl, indent: usize) -> fmt::Result {
+    write!(f, "{} {}(", Keyword::Func, decl.name.data)?;
+    list(f, &decl.params, |f, param| write!(f, "{}", param.data))?;
+    write!(f, ")")?;
+    if let Some(return_type) = &decl.return_type {
+        write!(f, "{} {}", Symbol::Colon, return_type.data)?;
+    }
+    match &decl.block.data {
+        FuncBlock::Block(stmts) => {
+            write!(f, " ")?;
+            block(f, stmts, indent)
+        }
+        FuncBlock::ReturnExpr(expr) => write!(f, " -> {}", expr),
+    }
+}
+fn while_statement(
+    f: &mut fmt::Formatter<'_>,
+    while_stmt: &WhileStatement,
+    indent: usize,
+) -> fmt::Result {
+    write!(f, "{} {} ", Keyword::While, while_stmt.condition.data)?;
+    block(f, &while_stmt.loop_block.data, indent)?;
+    if let Some(on_break) = &while_stmt.on_break {
+        write!(f, " {} ", Keyword::OnBreak)?;
+        block(f, &on_break.data, indent)?;
+    }
+    if let Some(on_continue) = &while_stmt.on_continue {
+        write!(f, " {} ", Keyword::OnContinue)?;
+        block(f, &on_continue.data, indent)?;
+    }
+    Ok(())
+}
+fn try_statement(
+    f: &mut fmt::Formatter<'_>,
+    try_stmt: &TryStatement,
+    indent: usize,
+) -> fmt::Result {
+    write!(f, "{} ", Keyword::Try)?;
+    block(f, &try_stmt.try_block.data, indent)?;
+    if let Some(catch) = &try_stmt.catch {
+        let name = catch.data.name.data.map_or("_", |n| n.as_str());
+        write!(f, " {} {} ", Keyword::Catch, name)?;
+        block(f, &catch.data.block.data, indent)?;
+    }
+    if let Some(finally) = &try_stmt.finally {
+        write!(f, " {} ", Keyword::Finally)?;
+        block(f, &finally.data, indent)?;
+    }
+    Ok(())
+}
+// `keyword` is `if`, or `elif` for the chained else blocks
+fn if_statement(
+    f: &mut fmt::Formatter<'_>,
+    if_stmt: &IfStatement,
+    keyword: Keyword,
+    indent: usize,
+) -> fmt::Result {
+    write!(f, "{} {} ", keyword, if_stmt.condition.data)?;
+    block(f, &if_stmt.met_block.data, indent)?;
+    match if_stmt.else_block.as_ref().map(|b| &b.data) {
+        Some(ElseBlock::If(elif)) => {
+            write!(f, " ")?;
+            if_statement(f, elif, Keyword::Elif, indent)
+        }
+        Some(ElseBlock::Block(else_block)) => {
+            write!(f, " {} ", Keyword::Else)?;
+            block(f, else_block, indent)
+        }
+        None => Ok(()),
+    }
+}
+
+impl Display for Statement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        statement(f, self, 0)
+    }
+}
+impl Display for Declaration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Var(decl) => write!(f, "{} {};", Keyword::Var, decl),
+            Self::Func(decl) => decl.fmt(f),
+        }
+    }
+}
+impl Display for FuncDecl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        func_decl(f, self, 0)
+    }
+}
+impl Display for Block {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        block(f, self, 0)
+    }
+}
+impl Display for IfStatement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if_statement(f, self, Keyword::If, 0)
+    }
+}
+impl Display for WhileStatement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        while_statement(f, self, 0)
+    }
+}
+// the declaration without the `let` keyword, as written in function parameters
+impl Display for VarDecl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.pattern.data)?;
+        if let Some(annotation) = &self.annotation {
+            write!(f, ": {}", annotation.data)?;
+        }
+        if let Some(value) = &self.value {
+            write!(f, " = {}", value.data)?;
+        }
+        Ok(())
+    }
+}
+impl Display for TypeAnnotation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Name(name) => write!(f, "{}", name),
+            Self::Array(element) => write!(f, "[{}]", element.data),
+            Self::Dictionary(fields) => {
+                write!(f, "{{")?;
+                list(f, fields, |f, (name, annotation)| {
+                    write!(f, "{}: {}", name.data, annotation.data)
+                })?;
+                write!(f, "}}")
+            }
+        }
+    }
+}
+impl Display for VarNameType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = |name: &Option<StringName>| name.map_or("_", |n| n.as_str());
+
+        match self {
+            Self::Ident(ident) => write!(f, "{}", name(ident)),
+            Self::Array {
+                start_names,
+                end_names,
+            } => {
+                write!(f, "[")?;
+                list(f, start_names, |f, n| write!(f, "{}", name(&n.data)))?;
+                if !end_names.is_empty() {
+                    if !start_names.is_empty() {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}, ", Symbol::Dots)?;
+                    list(f, end_names, |f, n| write!(f, "{}", name(&n.data)))?;
+                }
+                write!(f, "]")
+            }
+            Self::Object(names) => {
+                write!(f, "{{")?;
+                list(f, names, |f, (field, name)| {
+                    if *field == name.data {
+                        write!(f, "{}", field)
+                    } else {
+                        write!(f, "{}: {}", field, name.data)
+                    }
+                })?;
+                write!(f, "}}")
+            }
+        }
+    }
+}
+impl Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::None => write!(f, "{}", Keyword::None),
+            Self::Bool(true) => write!(f, "{}", Keyword::True),
+            Self::Bool(false) => write!(f, "{}", Keyword::False),
+            Self::Int(i) => write!(f, "{}", i),
+            // debug formatting always includes a '.' or an exponent, so it is parsed back as a real
+            Self::Real(r) => write!(f, "{:?}", r),
+            Self::String(s) => {
+                write!(f, "\"")?;
+                for c in s.chars() {
+                    escape(f, c, '"')?;
+                }
+                write!(f, "\"")
+            }
+            Self::Char(c) => {
+                write!(f, "'")?;
+                escape(f, *c, '\'')?;
+                write!(f, "'")
+            }
+            Self::Array(items) => {
+                write!(f, "[")?;
+                list(f, items, |f, item| write!(f, "{}", item.data))?;
+                write!(f, "]")
+            }
+            Self::Dictionary(items) => {
+                write!(f, "{{")?;
+                list(f, items, |f, (key, value)| {
+                    write!(f, "{} = {}", key.data, value.data)
+                })?;
+                write!(f, "}}")
+            }
+            Self::Variable(name) => write!(f, "{}", name),
+            Self::Binary(binary) => {
+                let precedence = precedence(self);
+                operand(f, &binary.left.data, precedence)?;
+                match binary.operator {
+                    SymbolKeyword::Symbol(s) => write!(f, " {} ", s)?,
+                    SymbolKeyword::Keyword(k) => write!(f, " {} ", k)?,
+                }
+                // all binary operators are left associative
+                operand(f, &binary.right.data, precedence + 1)
+            }
+            Self::Assign(assign) => {
+                operand(f, &assign.left.data, precedence(&Expression::None))?;
+                match assign.operator {
+                    Some(operator) => write!(f, " {}= ", operator)?,
+                    None => write!(f, " {} ", Symbol::Assign)?,
+                }
+                write!(f, "{}", ungroup(&assign.right.data))
+            }
+            Self::Unary(unary) => {
+                write!(f, "{}", unary.operator)?;
+                match ungroup(&unary.operand.data) {
+                    // `-(9223372036854775808)` overflows, where `-9223372036854775808` is in range
+                    operand @ Expression::Int(i)
+                        if i64::try_from(*i).is_err()
+                            && matches!(unary.operand.data, Expression::Grouping(_)) =>
+                    {
+                        write!(f, "({})", operand)
+                    }
+                    // nested unary operators don't need parentheses, `--a` is `-(-a)`
+                    operand @ Expression::Unary(_) => write!(f, "{}", operand),
+                    operand => self::operand(f, operand, binary_precedence(Symbol::Pow)),
+                }
+            }
+            Self::Suffix(suffix) => {
+                match ungroup(&suffix.node.data) {
+                    // `(value.name)(args)` calls the property, where `value.name(args)` calls
+                    // the method
+                    node @ Expression::Suffix(Suffix {
+                        suffix: SuffixType::Property(_),
+                        ..
+                    }) if matches!(suffix.suffix, SuffixType::Call(_))
+                        && matches!(suffix.node.data, Expression::Grouping(_)) =>
+                    {
+                        write!(f, "({})", node)?
+                    }
+                    // `1.a` would be read as the real number `1.` followed by `a`
+                    node @ (Expression::Int(_) | Expression::Real(_)) => write!(f, "({})", node)?,
+                    node => operand(f, node, precedence(&Expression::None))?,
+                }
+                match &suffix.suffix {
+                    SuffixType::Call(args) => {
+                        write!(f, "(")?;
+                        list(f, args, |f, arg| write!(f, "{}", arg.data))?;
+                        write!(f, ")")
+                    }
+                    SuffixType::Index(index) => write!(f, "[{}]", index.data),
+                    SuffixType::Property(name) => write!(f, ".{}", name),
+                }
+            }
+            Self::Grouping(expr) => write!(f, "{}", expr.data),
+        }
+    }
+}
+impl Display for DictionaryKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ident(name) => write!(f, "{}", name),
+            Self::Expr(expr) => write!(f, "[{}]", expr.data),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Parser;
+    use super::*;
+
+    fn parse(source: &str) -> Vec<ParseNode<Statement>> {
+        Parser::new(source).parse().unwrap()
+    }
+    fn tree(statements: &[ParseNode<Statement>]) -> String {
+        statements
+            .iter()
+            .map(|s| format!("{:?}\n", s.data))
+            .collect()
+    }
+    fn round_trip(source: &str) -> String {
+        let statements = parse(source);
+        let output = unparse(&statements);
+        let reparsed = parse(&output);
+        assert_eq!(
+            tree(&reparsed),
+            tree(&statements),
+            "unparsed as:\n{}",
+            output
+        );
+        assert_eq!(unparse(&reparsed), output);
+        output
+    }
+
+    #[test]
+    fn statements_round_trip() {
+        round_trip(
+            r#"
+            let [a, b, ..., c] = [1, 2, 3, 4];
+            let [..., last] = [1, 2];
+            let _ = none;
+            let typed: [int] = [1, 2];
+            let point: {x: int, y: real} = {x = 1, y = 2.5};
+            func add(x: int, y = 2): int { return x + y; }
+            func double(n: int): int -> n * 2
+            if a < b { println("less"); } elif a == b { println("same"); } else { println("more"); }
+            while a > 0 {
+                a -= 1;
+                if a == 2 { continue; }
+                if a == 1 { break; }
+            } onbreak { println('b'); } oncontinue { println('\n'); }
+            try { throw "oops\t\"quoted\"\\"; } catch error { println(error.message); } finally { return; }
+            { let nested = {["key" + "s"] = [1, 2], plain = true}; }
+            ({x = 1}).x = 2;
+            "#,
+        );
+    }
+
+    #[test]
+    fn expressions_round_trip() {
+        round_trip(
+            r#"
+            let p = (1 + 2) * 3 - 4 / (5 % 2) ** 2 ** 3;
+            let q = (2 ** 3) ** 2;
+            let r = -(1 + 2) and !(a or b) or a & b | c ^ d;
+            let s = (1).abs() + (2.5).floor() + 0.5 + 1e10 + 3.0;
+            let t = point.x(1) + typed[0][1] + f(1)(2);
+            a = b = c;
+            "#,
+        );
+    }
+
+    #[test]
+    fn only_needed_parentheses_are_written() {
+        assert_eq!(
+            round_trip("let x = ((1 + 2)) + (3 * 4);"),
+            "let x = 1 + 2 + 3 * 4;\n"
+        );
+        assert_eq!(round_trip("let x = (a);"), "let x = a;\n");
+    }
+
+    #[test]
+    fn keeps_groupings_that_change_how_it_runs() {
+        // without the parentheses the literal is the minimum int instead of an overflow
+        assert_eq!(
+            round_trip("let x = -(9223372036854775808);"),
+            "let x = -(9223372036854775808);\n"
+        );
+        assert_eq!(
+            round_trip("let x = -9223372036854775808;"),
+            "let x = -9223372036854775808;\n"
+        );
+        // without the parentheses the property is called as a method
+        assert_eq!(round_trip("(d.f)(x);"), "(d.f)(x);\n");
+        assert_eq!(round_trip("d.f(x);"), "d.f(x);\n");
+    }
+}
@@ -0,0 +1,1200 @@
+// traversal of the syntax tree, for the passes that run after parsing.
+// every `visit_*` method defaults to the matching `walk_*` function, which visits the children of
+// the node. an overriding method can call the `walk_*` function itself to keep going deeper.
+// nodes are passed together with their range, since not every node is wrapped in a `ParseNode`
+
+use std::ops::Range;
+
+use crate::string_name::StringName;
+
+use super::parse_node::*;
+
+pub trait Visitor {
+    fn visit_statements(&mut self, statements: &[ParseNode<Statement>]) {
+        walk_statements(self, statements)
+    }
+    fn visit_statement(&mut self, statement: &Statement, range: &Range<usize>) {
+        walk_statement(self, statement, range)
+    }
+    fn visit_declaration(&mut self, declaration: &Declaration, range: &Range<usize>) {
+        walk_declaration(self, declaration, range)
+    }
+    fn visit_var_decl(&mut self, decl: &VarDecl, range: &Range<usize>) {
+        walk_var_decl(self, decl, range)
+    }
+    fn visit_var_name_type(&mut self, pattern: &VarNameType, range: &Range<usize>) {
+        walk_var_name_type(self, pattern, range)
+    }
+    // a declared name, `None` for `_`
+    fn visit_var_name(&mut self, _name: Option<StringName>, _range: &Range<usize>) {}
+    fn visit_type_annotation(&mut self, annotation: &TypeAnnotation, range: &Range<usize>) {
+        walk_type_annotation(self, annotation, range)
+    }
+    fn visit_func_decl(&mut self, decl: &FuncDecl, range: &Range<usize>) {
+        walk_func_decl(self, decl, range)
+    }
+    fn visit_func_name(&mut self, _name: StringName, _range: &Range<usize>) {}
+    fn visit_func_block(&mut self, block: &FuncBlock, range: &Range<usize>) {
+        walk_func_block(self, block, range)
+    }
+    fn visit_if_statement(&mut self, statement: &IfStatement, range: &Range<usize>) {
+        walk_if_statement(self, statement, range)
+    }
+    fn visit_else_block(&mut self, block: &ElseBlock, range: &Range<usize>) {
+        walk_else_block(self, block, range)
+    }
+    fn visit_while_statement(&mut self, statement: &WhileStatement, range: &Range<usize>) {
+        walk_while_statement(self, statement, range)
+    }
+    fn visit_try_statement(&mut self, statement: &TryStatement, range: &Range<usize>) {
+        walk_try_statement(self, statement, range)
+    }
+    fn visit_catch_block(&mut self, block: &CatchBlock, range: &Range<usize>) {
+        walk_catch_block(self, block, range)
+    }
+    fn visit_block(&mut self, block: &Block, range: &Range<usize>) {
+        walk_block(self, block, range)
+    }
+    fn visit_expression(&mut self, expr: &Expression, range: &Range<usize>) {
+        walk_expression(self, expr, range)
+    }
+    fn visit_variable(&mut self, _name: StringName, _range: &Range<usize>) {}
+    fn visit_dictionary_key(&mut self, key: &DictionaryKey, range: &Range<usize>) {
+        walk_dictionary_key(self, key, range)
+    }
+    fn visit_binary(&mut self, binary: &Binary, range: &Range<usize>) {
+        walk_binary(self, binary, range)
+    }
+    fn visit_assign(&mut self, assign: &Assign, range: &Range<usize>) {
+        walk_assign(self, assign, range)
+    }
+    fn visit_unary(&mut self, unary: &Unary, range: &Range<usize>) {
+        walk_unary(self, unary, range)
+    }
+    fn visit_suffix(&mut self, suffix: &Suffix, range: &Range<usize>) {
+        walk_suffix(self, suffix, range)
+    }
+    fn visit_suffix_type(&mut self, suffix: &SuffixType, range: &Range<usize>) {
+        walk_suffix_type(self, suffix, range)
+    }
+}
+
+pub fn walk_statements<V: Visitor + ?Sized>(v: &mut V, statements: &[ParseNode<Statement>]) {
+    for stmt in statements {
+        v.visit_statement(&stmt.data, &stmt.range);
+    }
+}
+pub fn walk_statement<V: Visitor + ?Sized>(v: &mut V, statement: &Statement, range: &Range<usize>) {
+    match statement {
+        Statement::Declaration(decl) => v.visit_declaration(decl, range),
+        Statement::Expression(expr) => v.visit_expression(expr, range),
+        Statement::If(if_stmt) => v.visit_if_statement(if_stmt, range),
+        Statement::While(while_stmt) => v.visit_while_statement(while_stmt, range),
+        Statement::Try(try_stmt) => v.visit_try_statement(try_stmt, range),
+        Statement::Block(block) => v.visit_block(block, range),
+        Statement::Return(Some(expr)) | Statement::Throw(expr) => {
+            v.visit_expression(&expr.data, &expr.range)
+        }
+        Statement::Break | Statement::Continue | Statement::Return(None) => {}
+    }
+}
+pub fn walk_declaration<V: Visitor + ?Sized>(
+    v: &mut V,
+    declaration: &Declaration,
+    range: &Range<usize>,
+) {
+    match declaration {
+        Declaration::Var(decl) => v.visit_var_decl(decl, range),
+        Declaration::Func(decl) => v.visit_func_decl(decl, range),
+    }
+}
+pub fn walk_var_decl<V: Visitor + ?Sized>(v: &mut V, decl: &VarDecl, _range: &Range<usize>) {
+    // the value is visited first, it is evaluated before the names are declared
+    if let Some(value) = &decl.value {
+        v.visit_expression(&value.data, &value.range);
+    }
+    v.visit_var_name_type(&decl.pattern.data, &decl.pattern.range);
+    if let Some(annotation) = &decl.annotation {
+        v.visit_type_annotation(&annotation.data, &annotation.range);
+    }
+}
+pub fn walk_var_name_type<V: Visitor + ?Sized>(
+    v: &mut V,
+    pattern: &VarNameType,
+    range: &Range<usize>,
+) {
+    match pattern {
+        VarNameType::Ident(name) => v.visit_var_name(*name, range),
+        VarNameType::Array {
+            start_names,
+            end_names,
+        } => {
+            for name in start_names.iter().chain(end_names) {
+                v.visit_var_name(name.data, &name.range);
+            }
+        }
+        VarNameType::Object(names) => {
+            for (_, name) in names {
+                v.visit_var_name(Some(name.data), &name.range);
+            }
+        }
+    }
+}
+pub fn walk_type_annotation<V: Visitor + ?Sized>(
+    v: &mut V,
+    annotation: &TypeAnnotation,
+    _range: &Range<usize>,
+) {
+    match annotation {
+        TypeAnnotation::Name(_) => {}
+        TypeAnnotation::Array(element) => v.visit_type_annotation(&element.data, &element.range),
+        TypeAnnotation::Dictionary(fields) => {
+            for (_, field) in fields {
+                v.visit_type_annotation(&field.data, &field.range);
+            }
+        }
+    }
+}
+pub fn walk_func_decl<V: Visitor + ?Sized>(v: &mut V, decl: &FuncDecl, _range: &Range<usize>) {
+    v.visit_func_name(decl.name.data, &decl.name.range);
+    for param in &decl.params {
+        v.visit_var_decl(&param.data, &param.range);
+    }
+    if let Some(return_type) = &decl.return_type {
+        v.visit_type_annotation(&return_type.data, &return_type.range);
+    }
+    v.visit_func_block(&decl.block.data, &decl.block.range);
+}
+pub fn walk_func_block<V: Visitor + ?Sized>(v: &mut V, block: &FuncBlock, range: &Range<usize>) {
+    match block {
+        FuncBlock::Block(block) => v.visit_block(block, range),
+        FuncBlock::ReturnExpr(expr) => v.visit_expression(expr, range),
+    }
+}
+pub fn walk_if_statement<V: Visitor + ?Sized>(
+    v: &mut V,
+    statement: &IfStatement,
+    _range: &Range<usize>,
+) {
+    v.visit_expression(&statement.condition.data, &statement.condition.range);
+    v.visit_block(&statement.met_block.data, &statement.met_block.range);
+    if let Some(else_block) = &statement.else_block {
+        v.visit_else_block(&else_block.data, &else_block.range);
+    }
+}
+pub fn walk_else_block<V: Visitor + ?Sized>(v: &mut V, block: &ElseBlock, range: &Range<usize>) {
+    match block {
+        ElseBlock::Block(block) => v.visit_block(block, range),
+        ElseBlock::If(if_stmt) => v.visit_if_statement(if_stmt, range),
+    }
+}
+pub fn walk_while_statement<V: Visitor + ?Sized>(
+    v: &mut V,
+    statement: &WhileStatement,
+    _range: &Range<usize>,
+) {
+    v.visit_expression(&statement.condition.data, &statement.condition.range);
+    v.visit_block(&statement.loop_block.data, &statement.loop_block.range);
+    if let Some(block) = &statement.on_break {
+        v.visit_block(&block.data, &block.range);
+    }
+    if let Some(block) = &statement.on_continue {
+        v.visit_block(&block.data, &block.range);
+    }
+}
+pub fn walk_try_statement<V: Visitor + ?Sized>(
+    v: &mut V,
+    statement: &TryStatement,
+    _range: &Range<usize>,
+) {
+    v.visit_block(&statement.try_block.data, &statement.try_block.range);
+    if let Some(catch) = &statement.catch {
+        v.visit_catch_block(&catch.data, &catch.range);
+    }
+    if let Some(block) = &statement.finally {
+        v.visit_block(&block.data, &block.range);
+    }
+}
+pub fn walk_catch_block<V: Visitor + ?Sized>(v: &mut V, block: &CatchBlock, _range: &Range<usize>) {
+    v.visit_var_name(block.name.data, &block.name.range);
+    v.visit_block(&block.block.data, &block.block.range);
+}
+pub fn walk_block<V: Visitor + ?Sized>(v: &mut V, block: &Block, _range: &Range<usize>) {
+    v.visit_statements(&block.statements);
+}
+pub fn walk_expression<V: Visitor + ?Sized>(v: &mut V, expr: &Expression, range: &Range<usize>) {
+    match expr {
+        Expression::None
+        | Expression::Bool(_)
+        | Expression::Int(_)
+        | Expression::Real(_)
+        | Expression::String(_)
+        | Expression::Char(_) => {}
+        Expression::Array(items) => {
+            for item in items {
+                v.visit_expression(&item.data, &item.range);
+            }
+        }
+        Expression::Dictionary(items) => {
+            for (key, value) in items {
+                v.visit_dictionary_key(&key.data, &key.range);
+                v.visit_expression(&value.data, &value.range);
+            }
+        }
+        Expression::Variable(name) => v.visit_variable(*name, range),
+        Expression::Binary(binary) => v.visit_binary(binary, range),
+        Expression::Assign(assign) => v.visit_assign(assign, range),
+        Expression::Unary(unary) => v.visit_unary(unary, range),
+        Expression::Suffix(suffix) => v.visit_suffix(suffix, range),
+        Expression::Grouping(expr) => v.visit_expression(&expr.data, &expr.range),
+    }
+}
+pub fn walk_dictionary_key<V: Visitor + ?Sized>(
+    v: &mut V,
+    key: &DictionaryKey,
+    _range: &Range<usize>,
+) {
+    match key {
+        DictionaryKey::Ident(_) => {}
+        DictionaryKey::Expr(expr) => v.visit_expression(&expr.data, &expr.range),
+    }
+}
+pub fn walk_binary<V: Visitor + ?Sized>(v: &mut V, binary: &Binary, _range: &Range<usize>) {
+    v.visit_expression(&binary.left.data, &binary.left.range);
+    v.visit_expression(&binary.right.data, &binary.right.range);
+}
+pub fn walk_assign<V: Visitor + ?Sized>(v: &mut V, assign: &Assign, _range: &Range<usize>) {
+    v.visit_expression(&assign.left.data, &assign.left.range);
+    v.visit_expression(&assign.right.data, &assign.right.range);
+}
+pub fn walk_unary<V: Visitor + ?Sized>(v: &mut V, unary: &Unary, _range: &Range<usize>) {
+    v.visit_expression(&unary.operand.data, &unary.operand.range);
+}
+pub fn walk_suffix<V: Visitor + ?Sized>(v: &mut V, suffix: &Suffix, range: &Range<usize>) {
+    v.visit_expression(&suffix.node.data, &suffix.node.range);
+    v.visit_suffix_type(&suffix.suffix, range);
+}
+pub fn walk_suffix_type<V: Visitor + ?Sized>(
+    v: &mut V,
+    suffix: &SuffixType,
+    _range: &Range<usize>,
+) {
+    match suffix {
+        SuffixType::Call(args) => {
+            for arg in args {
+                v.visit_expression(&arg.data, &arg.range);
+            }
+        }
+        SuffixType::Index(index) => v.visit_expression(&index.data, &index.range),
+        SuffixType::Property(_) => {}
+    }
+}
+
+// same as `Visitor`, but the nodes can be modified in place
+pub trait VisitorMut {
+    fn visit_statements_mut(&mut self, statements: &mut Vec<ParseNode<Statement>>) {
+        walk_statements_mut(self, statements)
+    }
+    fn visit_statement_mut(&mut self, statement: &mut Statement, range: &Range<usize>) {
+        walk_statement_mut(self, statement, range)
+    }
+    fn visit_declaration_mut(&mut self, declaration: &mut Declaration, range: &Range<usize>) {
+        walk_declaration_mut(self, declaration, range)
+    }
+    fn visit_var_decl_mut(&mut self, decl: &mut VarDecl, range: &Range<usize>) {
+        walk_var_decl_mut(self, decl, range)
+    }
+    fn visit_var_name_type_mut(&mut self, pattern: &mut VarNameType, range: &Range<usize>) {
+        walk_var_name_type_mut(self, pattern, range)
+    }
+    fn visit_var_name_mut(&mut self, _name: &mut Option<StringName>, _range: &Range<usize>) {}
+    fn visit_type_annotation_mut(&mut self, annotation: &mut TypeAnnotation, range: &Range<usize>) {
+        walk_type_annotation_mut(self, annotation, range)
+    }
+    fn visit_func_decl_mut(&mut self, decl: &mut FuncDecl, range: &Range<usize>) {
+        walk_func_decl_mut(self, decl, range)
+    }
+    fn visit_func_name_mut(&mut self, _name: &mut StringName, _range: &Range<usize>) {}
+    fn visit_func_block_mut(&mut self, block: &mut FuncBlock, range: &Range<usize>) {
+        walk_func_block_mut(self, block, range)
+    }
+    fn visit_if_statement_mut(&mut self, statement: &mut IfStatement, range: &Range<usize>) {
+        walk_if_statement_mut(self, statement, range)
+    }
+    fn visit_else_block_mut(&mut self, block: &mut ElseBlock, range: &Range<usize>) {
+        walk_else_block_mut(self, block, range)
+    }
+    fn visit_while_statement_mut(&mut self, statement: &mut WhileStatement, range: &Range<usize>) {
+        walk_while_statement_mut(self, statement, range)
+    }
+    fn visit_try_statement_mut(&mut self, statement: &mut TryStatement, range: &Range<usize>) {
+        walk_try_statement_mut(self, statement, range)
+    }
+    fn visit_catch_block_mut(&mut self, block: &mut CatchBlock, range: &Range<usize>) {
+        walk_catch_block_mut(self, block, range)
+    }
+    fn visit_block_mut(&mut self, block: &mut Block, range: &Range<usize>) {
+        walk_block_mut(self, block, range)
+    }
+    fn visit_expression_mut(&mut self, expr: &mut Expression, range: &Range<usize>) {
+        walk_expression_mut(self, expr, range)
+    }
+    fn visit_variable_mut(&mut self, _name: &mut StringName, _range: &Range<usize>) {}
+    fn visit_dictionary_key_mut(&mut self, key: &mut DictionaryKey, range: &Range<usize>) {
+        walk_dictionary_key_mut(self, key, range)
+    }
+    fn visit_binary_mut(&mut self, binary: &mut Binary, range: &Range<usize>) {
+        walk_binary_mut(self, binary, range)
+    }
+    fn visit_assign_mut(&mut self, assign: &mut Assign, range: &Range<usize>) {
+        walk_assign_mut(self, assign, range)
+    }
+    fn visit_unary_mut(&mut self, unary: &mut Unary, range: &Range<usize>) {
+        walk_unary_mut(self, unary, range)
+    }
+    fn visit_suffix_mut(&mut self, suffix: &mut Suffix, range: &Range<usize>) {
+        walk_suffix_mut(self, suffix, range)
+    }
+    fn visit_suffix_type_mut(&mut self, suffix: &mut SuffixType, range: &Range<usize>) {
+        walk_suffix_type_mut(self, suffix, range)
+    }
+}
+
+pub fn walk_statements_mut<V: VisitorMut + ?Sized>(
+    v: &mut V,
+    statements: &mut Vec<ParseNode<Statement>>,
+) {
+    for stmt in statements {
+        v.visit_statement_mut(&mut stmt.data, &stmt.range);
+    }
+}
+pub fn walk_statement_mut<V: VisitorMut + ?Sized>(
+    v: &mut V,
+    statement: &mut Statement,
+    range: &Range<usize>,
+) {
+    match statement {
+        Statement::Declaration(decl) => v.visit_declaration_mut(decl, range),
+        Statement::Expression(expr) => v.visit_expression_mut(expr, range),
+        Statement::If(if_stmt) => v.visit_if_statement_mut(if_stmt, range),
+        Statement::While(while_stmt) => v.visit_while_statement_mut(while_stmt, range),
+        Statement::Try(try_stmt) => v.visit_try_statement_mut(try_stmt, range),
+        Statement::Block(block) => v.visit_block_mut(block, range),
+        Statement::Return(Some(expr)) | Statement::Throw(expr) => {
+            v.visit_expression_mut(&mut expr.data, &expr.range)
+        }
+        Statement::Break | Statement::Continue | Statement::Return(None) => {}
+    }
+}
+pub fn walk_declaration_mut<V: VisitorMut + ?Sized>(
+    v: &mut V,
+    declaration: &mut Declaration,
+    range: &Range<usize>,
+) {
+    match declaration {
+        Declaration::Var(decl) => v.visit_var_decl_mut(decl, range),
+        Declaration::Func(decl) => v.visit_func_decl_mut(decl, range),
+    }
+}
+pub fn walk_var_decl_mut<V: VisitorMut + ?Sized>(
+    v: &mut V,
+    decl: &mut VarDecl,
+    _range: &Range<usize>,
+) {
+    if let Some(value) = &mut decl.value {
+        v.visit_expression_mut(&mut value.data, &value.range);
+    }
+    v.visit_var_name_type_mut(&mut decl.pattern.data, &decl.pattern.range);
+    if let Some(annotation) = &mut decl.annotation {
+        v.visit_type_annotation_mut(&mut annotation.data, &annotation.range);
+    }
+}
+pub fn walk_var_name_type_mut<V: VisitorMut + ?Sized>(
+    v: &mut V,
+    pattern: &mut VarNameType,
+    range: &Range<usize>,
+) {
+    match pattern {
+        VarNameType::Ident(name) => v.visit_var_name_mut(name, range),
+        VarNameType::Array {
+            start_names,
+            end_names,
+        } => {
+            for name in start_names.iter_mut().chain(end_names) {
+                v.visit_var_name_mut(&mut name.data, &name.range);
+            }
+        }
+        VarNameType::Object(names) => {
+            // object names can't be discarded, so a `None` is ignored
+            for (_, name) in names {
+                let mut new_name = Some(name.data);
+                v.visit_var_name_mut(&mut new_name, &name.range);
+                if let Some(new_name) = new_name {
+                    name.data = new_name;
+                }
+            }
+        }
+    }
+}
+pub fn walk_type_annotation_mut<V: VisitorMut + ?Sized>(
+    v: &mut V,
+    annotation: &mut TypeAnnotation,
+    _range: &Range<usize>,
+) {
+    match annotation {
+        TypeAnnotation::Name(_) => {}
+        TypeAnnotation::Array(element) => {
+            v.visit_type_annotation_mut(&mut element.data, &element.range)
+        }
+        TypeAnnotation::Dictionary(fields) => {
+            for (_, field) in fields {
+                v.visit_type_annotation_mut(&mut field.data, &field.range);
+            }
+        }
+    }
+}
+pub fn walk_func_decl_mut<V: VisitorMut + ?Sized>(
+    v: &mut V,
+    decl: &mut FuncDecl,
+    _range: &Range<usize>,
+) {
+    v.visit_func_name_mut(&mut decl.name.data, &decl.name.range);
+    for param in &mut decl.params {
+        v.visit_var_decl_mut(&mut param.data, &param.range);
+    }
+    if let Some(return_type) = &mut decl.return_type {
+        v.visit_type_annotation_mut(&mut return_type.data, &return_type.range);
+    }
+    v.visit_func_block_mut(&mut decl.block.data, &decl.block.range);
+}
+pub fn walk_func_block_mut<V: VisitorMut + ?Sized>(
+    v: &mut V,
+    block: &mut FuncBlock,
+    range: &Range<usize>,
+) {
+    match block {
+        FuncBlock::Block(block) => v.visit_block_mut(block, range),
+        FuncBlock::ReturnExpr(expr) => v.visit_expression_mut(expr, range),
+    }
+}
+pub fn walk_if_statement_mut<V: VisitorMut + ?Sized>(
+    v: &mut V,
+    statement: &mut IfStatement,
+    _range: &Range<usize>,
+) {
+    v.visit_expression_mut(&mut statement.condition.data, &statement.condition.range);
+    v.visit_block_mut(&mut statement.met_block.data, &statement.met_block.range);
+    if let Some(else_block) = &mut statement.else_block {
+        v.visit_else_block_mut(&mut else_block.data, &else_block.range);
+    }
+}
+pub fn walk_else_block_mut<V: VisitorMut + ?Sized>(
+    v: &mut V,
+    block: &mut ElseBlock,
+    range: &Range<usize>,
+) {
+    match block {
+        ElseBlock::Block(block) => v.visit_block_mut(block, range),
+        ElseBlock::If(if_stmt) => v.visit_if_statement_mut(if_stmt, range),
+    }
+}
+pub fn walk_while_statement_mut<V: VisitorMut + ?Sized>(
+    v: &mut V,
+    statement: &mut WhileStatement,
+    _range: &Range<usize>,
+) {
+    v.visit_expression_mut(&mut statement.condition.data, &statement.condition.range);
+    v.visit_block_mut(&mut statement.loop_block.data, &statement.loop_block.range);
+    if let Some(block) = &mut statement.on_break {
+        v.visit_block_mut(&mut block.data, &block.range);
+    }
+    if let Some(block) = &mut statement.on_continue {
+        v.visit_block_mut(&mut block.data, &block.range);
+    }
+}
+pub fn walk_try_statement_mut<V: VisitorMut + ?Sized>(
+    v: &mut V,
+    statement: &mut TryStatement,
+    _range: &Range<usize>,
+) {
+    v.visit_block_mut(&mut statement.try_block.data, &statement.try_block.range);
+    if let Some(catch) = &mut statement.catch {
+        v.visit_catch_block_mut(&mut catch.data, &catch.range);
+    }
+    if let Some(block) = &mut statement.finally {
+        v.visit_block_mut(&mut block.data, &block.range);
+    }
+}
+pub fn walk_catch_block_mut<V: VisitorMut + ?Sized>(
+    v: &mut V,
+    block: &mut CatchBlock,
+    _range: &Range<usize>,
+) {
+    v.visit_var_name_mut(&mut block.name.data, &block.name.range);
+    v.visit_block_mut(&mut block.block.data, &block.block.range);
+}
+pub fn walk_block_mut<V: VisitorMut + ?Sized>(v: &mut V, block: &mut Block, _range: &Range<usize>) {
+    v.visit_statements_mut(&mut block.statements);
+}
+pub fn walk_expression_mut<V: VisitorMut + ?Sized>(
+    v: &mut V,
+    expr: &mut Expression,
+    range: &Range<usize>,
+) {
+    match expr {
+        Expression::None
+        | Expression::Bool(_)
+        | Expression::Int(_)
+        | Expression::Real(_)
+        | Expression::String(_)
+        | Expression::Char(_) => {}
+        Expression::Array(items) => {
+            for item in items {
+                v.visit_expression_mut(&mut item.data, &item.range);
+            }
+        }
+        Expression::Dictionary(items) => {
+            for (key, value) in items {
+                v.visit_dictionary_key_mut(&mut key.data, &key.range);
+                v.visit_expression_mut(&mut value.data, &value.range);
+            }
+        }
+        Expression::Variable(name) => v.visit_variable_mut(name, range),
+        Expression::Binary(binary) => v.visit_binary_mut(binary, range),
+        Expression::Assign(assign) => v.visit_assign_mut(assign, range),
+        Expression::Unary(unary) => v.visit_unary_mut(unary, range),
+        Expression::Suffix(suffix) => v.visit_suffix_mut(suffix, range),
+        Expression::Grouping(expr) => v.visit_expression_mut(&mut expr.data, &expr.range),
+    }
+}
+pub fn walk_dictionary_key_mut<V: VisitorMut + ?Sized>(
+    v: &mut V,
+    key: &mut DictionaryKey,
+    _range: &Range<usize>,
+) {
+    match key {
+        DictionaryKey::Ident(_) => {}
+        DictionaryKey::Expr(expr) => v.visit_expression_mut(&mut expr.data, &expr.range),
+    }
+}
+pub fn walk_binary_mut<V: VisitorMut + ?Sized>(
+    v: &mut V,
+    binary: &mut Binary,
+    _range: &Range<usize>,
+) {
+    v.visit_expression_mut(&mut binary.left.data, &binary.left.range);
+    v.visit_expression_mut(&mut binary.right.data, &binary.right.range);
+}
+pub fn walk_assign_mut<V: VisitorMut + ?Sized>(
+    v: &mut V,
+    assign: &mut Assign,
+    _range: &Range<usize>,
+) {
+    v.visit_expression_mut(&mut assign.left.data, &assign.left.range);
+    v.visit_expression_mut(&mut assign.right.data, &assign.right.range);
+}
+pub fn walk_unary_mut<V: VisitorMut + ?Sized>(v: &mut V, unary: &mut Unary, _range: &Range<usize>) {
+    v.visit_expression_mut(&mut unary.operand.data, &unary.operand.range);
+}
+pub fn walk_suffix_mut<V: VisitorMut + ?Sized>(
+    v: &mut V,
+    suffix: &mut Suffix,
+    range: &Range<usize>,
+) {
+    v.visit_expression_mut(&mut suffix.node.data, &suffix.node.range);
+    v.visit_suffix_type_mut(&mut suffix.suffix, range);
+}
+pub fn walk_suffix_type_mut<V: VisitorMut + ?Sized>(
+    v: &mut V,
+    suffix: &mut SuffixType,
+    _range: &Range<usize>,
+) {
+    match suffix {
+        SuffixType::Call(args) => {
+            for arg in args {
+                v.visit_expression_mut(&mut arg.data, &arg.range);
+            }
+        }
+        SuffixType::Index(index) => v.visit_expression_mut(&mut index.data, &index.range),
+        SuffixType::Property(_) => {}
+    }
+}
+
+// rewrites the tree by taking each node and returning its replacement.
+// the replacement keeps the range of the original node
+pub trait Fold {
+    fn fold_statements(
+        &mut self,
+        statements: Vec<ParseNode<Statement>>,
+    ) -> Vec<ParseNode<Statement>> {
+        fold_statements(self, statements)
+    }
+    fn fold_statement(&mut self, statement: Statement, range: &Range<usize>) -> Statement {
+        fold_statement(self, statement, range)
+    }
+    fn fold_declaration(&mut self, declaration: Declaration, range: &Range<usize>) -> Declaration {
+        fold_declaration(self, declaration, range)
+    }
+    fn fold_var_decl(&mut self, decl: VarDecl, range: &Range<usize>) -> VarDecl {
+        fold_var_decl(self, decl, range)
+    }
+    fn fold_var_name_type(&mut self, pattern: VarNameType, range: &Range<usize>) -> VarNameType {
+        fold_var_name_type(self, pattern, range)
+    }
+    fn fold_var_name(
+        &mut self,
+        name: Option<StringName>,
+        _range: &Range<usize>,
+    ) -> Option<StringName> {
+        name
+    }
+    fn fold_type_annotation(
+        &mut self,
+        annotation: TypeAnnotation,
+        range: &Range<usize>,
+    ) -> TypeAnnotation {
+        fold_type_annotation(self, annotation, range)
+    }
+    fn fold_func_decl(&mut self, decl: FuncDecl, range: &Range<usize>) -> FuncDecl {
+        fold_func_decl(self, decl, range)
+    }
+    fn fold_func_name(&mut self, name: StringName, _range: &Range<usize>) -> StringName {
+        name
+    }
+    fn fold_func_block(&mut self, block: FuncBlock, range: &Range<usize>) -> FuncBlock {
+        fold_func_block(self, block, range)
+    }
+    fn fold_if_statement(&mut self, statement: IfStatement, range: &Range<usize>) -> IfStatement {
+        fold_if_statement(self, statement, range)
+    }
+    fn fold_else_block(&mut self, block: ElseBlock, range: &Range<usize>) -> ElseBlock {
+        fold_else_block(self, block, range)
+    }
+    fn fold_while_statement(
+        &mut self,
+        statement: WhileStatement,
+        range: &Range<usize>,
+    ) -> WhileStatement {
+        fold_while_statement(self, statement, range)
+    }
+    fn fold_try_statement(
+        &mut self,
+        statement: TryStatement,
+        range: &Range<usize>,
+    ) -> TryStatement {
+        fold_try_statement(self, statement, range)
+    }
+    fn fold_catch_block(&mut self, block: CatchBlock, range: &Range<usize>) -> CatchBlock {
+        fold_catch_block(self, block, range)
+    }
+    fn fold_block(&mut self, block: Block, range: &Range<usize>) -> Block {
+        fold_block(self, block, range)
+    }
+    fn fold_expression(&mut self, expr: Expression, range: &Range<usize>) -> Expression {
+        fold_expression(self, expr, range)
+    }
+    fn fold_dictionary_key(&mut self, key: DictionaryKey, range: &Range<usize>) -> DictionaryKey {
+        fold_dictionary_key(self, key, range)
+    }
+    fn fold_binary(&mut self, binary: Binary, range: &Range<usize>) -> Expression {
+        fold_binary(self, binary, range)
+    }
+    fn fold_assign(&mut self, assign: Assign, range: &Range<usize>) -> Expression {
+        fold_assign(self, assign, range)
+    }
+    fn fold_unary(&mut self, unary: Unary, range: &Range<usize>) -> Expression {
+        fold_unary(self, unary, range)
+    }
+    fn fold_suffix(&mut self, suffix: Suffix, range: &Range<usize>) -> Expression {
+        fold_suffix(self, suffix, range)
+    }
+    fn fold_suffix_type(&mut self, suffix: SuffixType, range: &Range<usize>) -> SuffixType {
+        fold_suffix_type(self, suffix, range)
+    }
+}
+
+pub(crate) fn fold_node<T>(
+    node: ParseNode<T>,
+    fold_fn: impl FnOnce(T, &Range<usize>) -> T,
+) -> ParseNode<T> {
+    let data = fold_fn(node.data, &node.range);
+    ParseNode::new(node.range, data)
+}
+pub(crate) fn fold_expr_node<F: Fold + ?Sized>(
+    f: &mut F,
+    expr: ParseNode<Expression>,
+) -> ParseNode<Expression> {
+    fold_node(expr, |expr, range| f.fold_expression(expr, range))
+}
+pub(crate) fn fold_expr_box<F: Fold + ?Sized>(
+    f: &mut F,
+    mut expr: Box<ParseNode<Expression>>,
+) -> Box<ParseNode<Expression>> {
+    // reuses the allocation
+    let data = std::mem::replace(&mut expr.data, Expression::None);
+    expr.data = f.fold_expression(data, &expr.range);
+    expr
+}
+pub(crate) fn fold_annotation_node<F: Fold + ?Sized>(
+    f: &mut F,
+    annotation: ParseNode<TypeAnnotation>,
+) -> ParseNode<TypeAnnotation> {
+    fold_node(annotation, |annotation, range| {
+        f.fold_type_annotation(annotation, range)
+    })
+}
+pub(crate) fn fold_block_node<F: Fold + ?Sized>(
+    f: &mut F,
+    block: ParseNode<Block>,
+) -> ParseNode<Block> {
+    fold_node(block, |block, range| f.fold_block(block, range))
+}
+
+pub fn fold_statements<F: Fold + ?Sized>(
+    f: &mut F,
+    statements: Vec<ParseNode<Statement>>,
+) -> Vec<ParseNode<Statement>> {
+    statements
+        .into_iter()
+        .map(|stmt| fold_node(stmt, |stmt, range| f.fold_statement(stmt, range)))
+        .collect()
+}
+pub fn fold_statement<F: Fold + ?Sized>(
+    f: &mut F,
+    statement: Statement,
+    range: &Range<usize>,
+) -> Statement {
+    match statement {
+        Statement::Declaration(decl) => Statement::Declaration(f.fold_declaration(decl, range)),
+        Statement::Expression(expr) => Statement::Expression(f.fold_expression(expr, range)),
+        Statement::If(if_stmt) => Statement::If(f.fold_if_statement(if_stmt, range)),
+        Statement::While(while_stmt) => Statement::While(f.fold_while_statement(while_stmt, range)),
+        Statement::Try(try_stmt) => Statement::Try(f.fold_try_statement(try_stmt, range)),
+        Statement::Block(block) => Statement::Block(f.fold_block(block, range)),
+        Statement::Return(Some(expr)) => Statement::Return(Some(fold_expr_node(f, expr))),
+        Statement::Throw(expr) => Statement::Throw(fold_expr_node(f, expr)),
+        Statement::Break | Statement::Continue | Statement::Return(None) => statement,
+    }
+}
+pub fn fold_declaration<F: Fold + ?Sized>(
+    f: &mut F,
+    declaration: Declaration,
+    range: &Range<usize>,
+) -> Declaration {
+    match declaration {
+        Declaration::Var(decl) => Declaration::Var(f.fold_var_decl(decl, range)),
+        Declaration::Func(decl) => Declaration::Func(f.fold_func_decl(decl, range)),
+    }
+}
+pub fn fold_var_decl<F: Fold + ?Sized>(f: &mut F, decl: VarDecl, _range: &Range<usize>) -> VarDecl {
+    let value = decl.value.map(|value| fold_expr_node(f, value));
+    let pattern = fold_node(decl.pattern, |pattern, range| {
+        f.fold_var_name_type(pattern, range)
+    });
+    let annotation = decl
+        .annotation
+        .map(|annotation| fold_annotation_node(f, annotation));
+    VarDecl {
+        pattern,
+        annotation,
+        value,
+    }
+}
+pub fn fold_var_name_type<F: Fold + ?Sized>(
+    f: &mut F,
+    pattern: VarNameType,
+    range: &Range<usize>,
+) -> VarNameType {
+    let mut fold_names = |names: Vec<ParseNode<Option<StringName>>>| -> Vec<_> {
+        names
+            .into_iter()
+            .map(|name| fold_node(name, |name, range| f.fold_var_name(name, range)))
+            .collect()
+    };
+
+    match pattern {
+        VarNameType::Ident(name) => VarNameType::Ident(f.fold_var_name(name, range)),
+        VarNameType::Array {
+            start_names,
+            end_names,
+        } => VarNameType::Array {
+            start_names: fold_names(start_names),
+            end_names: fold_names(end_names),
+        },
+        VarNameType::Object(names) => VarNameType::Object(
+            names
+                .into_iter()
+                .map(|(field, name)| {
+                    // object names can't be discarded, so a `None` keeps the old name
+                    let new_name = f.fold_var_name(Some(name.data), &name.range);
+                    (
+                        field,
+                        ParseNode::new(name.range, new_name.unwrap_or(name.data)),
+                    )
+                })
+                .collect(),
+        ),
+    }
+}
+pub fn fold_type_annotation<F: Fold + ?Sized>(
+    f: &mut F,
+    annotation: TypeAnnotation,
+    _range: &Range<usize>,
+) -> TypeAnnotation {
+    match annotation {
+        TypeAnnotation::Name(_) => annotation,
+        TypeAnnotation::Array(element) => {
+            TypeAnnotation::Array(Box::new(fold_annotation_node(f, *element)))
+        }
+        TypeAnnotation::Dictionary(fields) => TypeAnnotation::Dictionary(
+            fields
+                .into_iter()
+                .map(|(name, field)| (name, fold_annotation_node(f, field)))
+                .collect(),
+        ),
+    }
+}
+pub fn fold_func_decl<F: Fold + ?Sized>(
+    f: &mut F,
+    decl: FuncDecl,
+    _range: &Range<usize>,
+) -> FuncDecl {
+    let name = fold_node(decl.name, |name, range| f.fold_func_name(name, range));
+    let params = decl
+        .params
+        .into_iter()
+        .map(|param| fold_node(param, |param, range| f.fold_var_decl(param, range)))
+        .collect();
+    let return_type = decl
+        .return_type
+        .map(|return_type| fold_annotation_node(f, return_type));
+    let block = fold_node(decl.block, |block, range| f.fold_func_block(block, range));
+    FuncDecl {
+        name,
+        params,
+        return_type,
+        block,
+    }
+}
+pub fn fold_func_block<F: Fold + ?Sized>(
+    f: &mut F,
+    block: FuncBlock,
+    range: &Range<usize>,
+) -> FuncBlock {
+    match block {
+        FuncBlock::Block(block) => FuncBlock::Block(f.fold_block(block, range)),
+        FuncBlock::ReturnExpr(expr) => FuncBlock::ReturnExpr(f.fold_expression(expr, range)),
+    }
+}
+pub fn fold_if_statement<F: Fold + ?Sized>(
+    f: &mut F,
+    statement: IfStatement,
+    _range: &Range<usize>,
+) -> IfStatement {
+    IfStatement {
+        condition: fold_expr_node(f, statement.condition),
+        met_block: fold_block_node(f, statement.met_block),
+        else_block: statement
+            .else_block
+            .map(|block| fold_node(block, |block, range| f.fold_else_block(block, range))),
+    }
+}
+pub fn fold_else_block<F: Fold + ?Sized>(
+    f: &mut F,
+    block: ElseBlock,
+    range: &Range<usize>,
+) -> ElseBlock {
+    match block {
+        ElseBlock::Block(block) => ElseBlock::Block(f.fold_block(block, range)),
+        ElseBlock::If(if_stmt) => ElseBlock::If(f.fold_if_statement(*if_stmt, range).into()),
+    }
+}
+pub fn fold_while_statement<F: Fold + ?Sized>(
+    f: &mut F,
+    statement: WhileStatement,
+    _range: &Range<usize>,
+) -> WhileStatement {
+    WhileStatement {
+        condition: fold_expr_node(f, statement.condition),
+        loop_block: fold_block_node(f, statement.loop_block),
+        on_break: statement.on_break.map(|block| fold_block_node(f, block)),
+        on_continue: statement.on_continue.map(|block| fold_block_node(f, block)),
+    }
+}
+pub fn fold_try_statement<F: Fold + ?Sized>(
+    f: &mut F,
+    statement: TryStatement,
+    _range: &Range<usize>,
+) -> TryStatement {
+    TryStatement {
+        try_block: fold_block_node(f, statement.try_block),
+        catch: statement
+            .catch
+            .map(|block| fold_node(block, |block, range| f.fold_catch_block(block, range))),
+        finally: statement.finally.map(|block| fold_block_node(f, block)),
+    }
+}
+pub fn fold_catch_block<F: Fold + ?Sized>(
+    f: &mut F,
+    block: CatchBlock,
+    _range: &Range<usize>,
+) -> CatchBlock {
+    CatchBlock {
+        name: fold_node(block.name, |name, range| f.fold_var_name(name, range)),
+        block: fold_block_node(f, block.block),
+    }
+}
+pub fn fold_block<F: Fold + ?Sized>(f: &mut F, block: Block, _range: &Range<usize>) -> Block {
+    Block {
+        statements: f.fold_statements(block.statements),
+    }
+}
+pub fn fold_expression<F: Fold + ?Sized>(
+    f: &mut F,
+    expr: Expression,
+    range: &Range<usize>,
+) -> Expression {
+    match expr {
+        Expression::None
+        | Expression::Bool(_)
+        | Expression::Int(_)
+        | Expression::Real(_)
+        | Expression::String(_)
+        | Expression::Char(_)
+        | Expression::Variable(_) => expr,
+        Expression::Array(items) => Expression::Array(
+            items
+                .into_iter()
+                .map(|item| fold_expr_node(f, item))
+                .collect(),
+        ),
+        Expression::Dictionary(items) => Expression::Dictionary(
+            items
+                .into_iter()
+                .map(|(key, value)| {
+                    let key = fold_node(key, |key, range| f.fold_dictionary_key(key, range));
+                    (key, fold_expr_node(f, value))
+                })
+                .collect(),
+        ),
+        Expression::Binary(binary) => f.fold_binary(binary, range),
+        Expression::Assign(assign) => f.fold_assign(assign, range),
+        Expression::Unary(unary) => f.fold_unary(unary, range),
+        Expression::Suffix(suffix) => f.fold_suffix(suffix, range),
+        Expression::Grouping(expr) => Expression::Grouping(fold_expr_box(f, expr)),
+    }
+}
+pub fn fold_dictionary_key<F: Fold + ?Sized>(
+    f: &mut F,
+    key: DictionaryKey,
+    _range: &Range<usize>,
+) -> DictionaryKey {
+    match key {
+        DictionaryKey::Ident(_) => key,
+        DictionaryKey::Expr(expr) => DictionaryKey::Expr(fold_expr_node(f, expr)),
+    }
+}
+pub fn fold_binary<F: Fold + ?Sized>(
+    f: &mut F,
+    binary: Binary,
+    _range: &Range<usize>,
+) -> Expression {
+    Expression::Binary(Binary {
+        left: fold_expr_box(f, binary.left),
+        right: fold_expr_box(f, binary.right),
+        operator: binary.operator,
+    })
+}
+pub fn fold_assign<F: Fold + ?Sized>(
+    f: &mut F,
+    assign: Assign,
+    _range: &Range<usize>,
+) -> Expression {
+    Expression::Assign(Assign {
+        left: fold_expr_box(f, assign.left),
+        right: fold_expr_box(f, assign.right),
+        operator: assign.operator,
+    })
+}
+pub fn fold_unary<F: Fold + ?Sized>(f: &mut F, unary: Unary, _range: &Range<usize>) -> Expression {
+    Expression::Unary(Unary {
+        operand: fold_expr_box(f, unary.operand),
+        operator: unary.operator,
+    })
+}
+pub fn fold_suffix<F: Fold + ?Sized>(
+    f: &mut F,
+    suffix: Suffix,
+    range: &Range<usize>,
+) -> Expression {
+    Expression::Suffix(Suffix {
+        node: fold_expr_box(f, suffix.node),
+        suffix: f.fold_suffix_type(suffix.suffix, range),
+    })
+}
+pub fn fold_suffix_type<F: Fold + ?Sized>(
+    f: &mut F,
+    suffix: SuffixType,
+    _range: &Range<usize>,
+) -> SuffixType {
+    match suffix {
+        SuffixType::Call(args) => {
+            SuffixType::Call(args.into_iter().map(|arg| fold_expr_node(f, arg)).collect())
+        }
+        SuffixType::Index(index) => SuffixType::Index(fold_expr_box(f, index)),
+        SuffixType::Property(_) => suffix,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{unparse, Parser};
+    use super::*;
+
+    const SOURCE: &str = r#"
+        let [a, ..., b]: [int] = [1, 2, 3];
+        let point: {x: int, y: [real]} = {x = 1, ["y"] = [2.5]};
+        func add(x: int, y = 2): int -> x + y
+        func run() {
+            while a < b { a += 1; } onbreak {} oncontinue { continue; }
+            try { throw "error"; } catch e { print(e.message); } finally { return; }
+            if a {} elif b {} else { -a; }
+        }
+    "#;
+
+    fn parse() -> Vec<ParseNode<Statement>> {
+        Parser::new(SOURCE).parse().unwrap()
+    }
+
+    // records the names and annotations it reaches, in the order it reaches them
+    #[derive(Default)]
+    struct Recorder {
+        var_names: Vec<&'static str>,
+        func_names: Vec<&'static str>,
+        annotations: Vec<String>,
+        variables: Vec<&'static str>,
+        blocks: usize,
+    }
+    impl Visitor for Recorder {
+        fn visit_var_name(&mut self, name: Option<StringName>, _range: &Range<usize>) {
+            self.var_names.push(name.map_or("_", |n| n.as_str()));
+        }
+        fn visit_func_name(&mut self, name: StringName, _range: &Range<usize>) {
+            self.func_names.push(name.as_str());
+        }
+        fn visit_type_annotation(&mut self, annotation: &TypeAnnotation, range: &Range<usize>) {
+            self.annotations.push(format!("{:?}", annotation));
+            walk_type_annotation(self, annotation, range);
+        }
+        fn visit_variable(&mut self, name: StringName, _range: &Range<usize>) {
+            self.variables.push(name.as_str());
+        }
+        fn visit_block(&mut self, block: &Block, range: &Range<usize>) {
+            self.blocks += 1;
+            walk_block(self, block, range);
+        }
+    }
+
+    #[test]
+    fn visitor_reaches_every_node() {
+        let mut recorder = Recorder::default();
+        recorder.visit_statements(&parse());
+
+        assert_eq!(recorder.var_names, ["a", "b", "point", "x", "y", "e"]);
+        assert_eq!(recorder.func_names, ["add", "run"]);
+        assert_eq!(
+            recorder.annotations,
+            [
+                "[int]",
+                "int",
+                "{x: int, y: [real]}",
+                "int",
+                "[real]",
+                "real",
+                "int",
+                "int"
+            ]
+        );
+        assert_eq!(
+            recorder.variables,
+            ["x", "y", "a", "b", "a", "print", "e", "a", "b", "a"]
+        );
+        // the body of `run`, then while, onbreak, oncontinue, try, catch, finally, if, elif, else
+        assert_eq!(recorder.blocks, 10);
+    }
+
+    struct Identity;
+    impl Fold for Identity {}
+
+    #[test]
+    fn identity_fold_keeps_the_tree() {
+        let statements = parse();
+        let folded = Identity.fold_statements(statements.clone());
+
+        assert_eq!(unparse(&folded), unparse(&statements));
+        assert_eq!(format!("{:?}", folded), format!("{:?}", statements));
+        let ranges = |statements: &[ParseNode<Statement>]| -> Vec<Range<usize>> {
+            statements.iter().map(|s| s.range.clone()).collect()
+        };
+        assert_eq!(ranges(&folded), ranges(&statements));
+    }
+
+    // renames the functions and the `int` type
+    struct Rename;
+    impl VisitorMut for Rename {
+        fn visit_func_name_mut(&mut self, name: &mut StringName, _range: &Range<usize>) {
+            *name = format!("{}_fn", name).into();
+        }
+        fn visit_type_annotation_mut(
+            &mut self,
+            annotation: &mut TypeAnnotation,
+            range: &Range<usize>,
+        ) {
+            if let TypeAnnotation::Name(name) = annotation {
+                if name.as_str() == "int" {
+                    *name = "float".into();
+                }
+            }
+            walk_type_annotation_mut(self, annotation, range);
+        }
+    }
+    impl Fold for Rename {
+        fn fold_func_name(&mut self, name: StringName, _range: &Range<usize>) -> StringName {
+            format!("{}_fn", name).into()
+        }
+        fn fold_type_annotation(
+            &mut self,
+            annotation: TypeAnnotation,
+            range: &Range<usize>,
+        ) -> TypeAnnotation {
+            match annotation {
+                TypeAnnotation::Name(name) if name.as_str() == "int" => {
+                    TypeAnnotation::Name("float".into())
+                }
+                annotation => fold_type_annotation(self, annotation, range),
+            }
+        }
+    }
+
+    #[test]
+    fn names_and_annotations_can_be_rewritten() {
+        let mut statements = parse();
+        Rename.visit_statements_mut(&mut statements);
+        let folded = Rename.fold_statements(parse());
+        assert_eq!(unparse(&folded), unparse(&statements));
+
+        let output = unparse(&statements);
+        assert!(
+            output.contains("let [a, ..., b]: [float] = [1, 2, 3];"),
+            "{}",
+            output
+        );
+        assert!(output.contains("{x: float, y: [real]}"), "{}", output);
+        assert!(
+            output.contains("func add_fn(x: float, y = 2): float -> x + y"),
+            "{}",
+            output
+        );
+        assert!(output.contains("func run_fn()"), "{}", output);
+    }
+}
@@ -0,0 +1,325 @@
+// the interactive prompt of `repl`, and of `run` without a program
+
+use std::{env, path::PathBuf, process::exit};
+
+use rustyline::{
+    completion::Completer, error::ReadlineError, highlight::Highlighter, hint::Hinter,
+    history::DefaultHistory, validate::Validator, Context, Editor, Helper,
+};
+use unicode_xid::UnicodeXID;
+
+use lox_rs::check::*;
+use lox_rs::interpreter::{value::Value, Interpreter};
+use lox_rs::lint::*;
+use lox_rs::optimize::optimize;
+use lox_rs::parser::error::*;
+use lox_rs::parser::parse_node::{Expression, Keyword, ParseNode, Statement, Symbol, Token};
+use lox_rs::parser::*;
+use lox_rs::source::*;
+use lox_rs::source_map::*;
+
+use crate::{interpreter, print_runtime_error, ErrorFormat, Options, Reporter, EXIT_IO};
+
+const HELP: &str = "\
+Type statements to run them, the value of an expression is printed. The ';' after the last
+statement can be left out, and an entry with an unclosed bracket continues on the next line.
+The declarations of every entry stay defined for the next ones.
+
+Commands:
+    :ast <code>     print the syntax tree of the code
+    :tokens <code>  print the tokens of the code
+    :load <file>    run a file, its declarations stay defined too
+    :reset          forget every declaration
+    :help           print this help
+    :quit           exit, same as Ctrl-D
+";
+const COMMANDS: [&str; 6] = [":ast", ":tokens", ":load", ":reset", ":help", ":quit"];
+
+struct Repl {
+    interpreter: Interpreter,
+    sources: SourceMap,
+    lints: LintConfig,
+    error_format: ErrorFormat,
+    script_args: Vec<String>,
+}
+
+pub fn repl(options: Options) {
+    if !options.positional.is_empty() {
+        crate::usage_error("'repl' doesn't take files");
+    }
+    // every entry is checked on its own, so most of its variables are only used by later ones
+    let mut lints = options.lints;
+    lints.set_default(Lint::UnusedVariables, Level::Allow);
+    let mut repl = Repl {
+        interpreter: interpreter(&options.script_args),
+        sources: SourceMap::new(),
+        lints,
+        error_format: options.error_format,
+        script_args: options.script_args,
+    };
+
+    let mut editor: Editor<ReplHelper, DefaultHistory> = match Editor::new() {
+        Ok(editor) => editor,
+        Err(err) => {
+            eprintln!("Failed to start the REPL: {}", err);
+            exit(EXIT_IO);
+        }
+    };
+    editor.set_helper(Some(ReplHelper { names: vec![] }));
+    let history = history_path();
+    if let Some(history) = &history {
+        // there's no history file before the first session
+        let _ = editor.load_history(history);
+    }
+
+    let mut entry = String::new();
+    loop {
+        let prompt = if entry.is_empty() { "> " } else { "... " };
+        let line = match editor.readline(prompt) {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => {
+                entry.clear();
+                continue;
+            }
+            Err(ReadlineError::Eof) => break,
+            Err(err) => {
+                eprintln!("Failed to read the input: {}", err);
+                exit(EXIT_IO);
+            }
+        };
+
+        if entry.is_empty() && line.trim_start().starts_with(':') {
+            let _ = editor.add_history_entry(line.as_str());
+            if !repl.command(line.trim()) {
+                break;
+            }
+        } else {
+            entry.push_str(&line);
+            entry.push('\n');
+            if !repl.entry(&entry) {
+                continue;
+            }
+            let _ = editor.add_history_entry(entry.trim_end());
+            entry.clear();
+        }
+
+        let names = repl.interpreter.globals().iter().map(|n| n.to_string());
+        editor.helper_mut().unwrap().names = names.collect();
+    }
+
+    if let Some(history) = &history {
+        if let Err(err) = editor.save_history(history) {
+            eprintln!(
+                "Failed to save the history to '{}': {}",
+                history.display(),
+                err
+            );
+        }
+    }
+}
+
+// `$LOX_HISTORY`, or `.lox_history` in the home directory
+fn history_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("LOX_HISTORY") {
+        return Some(path.into());
+    }
+    env::var_os("HOME").map(|home| PathBuf::from(home).join(".lox_history"))
+}
+
+impl Repl {
+    // runs a `:` command, returns false to exit
+    fn command(&mut self, line: &str) -> bool {
+        let (command, arg) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let arg = arg.trim();
+        match command {
+            ":ast" => {
+                let mut sources = SourceMap::new();
+                let file = sources.add("<repl>", with_semicolon(arg));
+                match parse_file(&sources, file) {
+                    Ok(nodes) => {
+                        for node in nodes {
+                            println!("{:?}", node);
+                        }
+                    }
+                    Err(err) => self.report(&[err], &sources, file),
+                }
+            }
+            ":tokens" => {
+                let mut sources = SourceMap::new();
+                let file = sources.add("<repl>", arg);
+                match Parser::new(sources.get(file).source()).tokens() {
+                    Ok(tokens) => {
+                        for token in tokens {
+                            let (line, column) = sources.get(file).line_column(token.start());
+                            println!("{}:{} {}", line, column, token);
+                        }
+                    }
+                    Err(err) => self.report(&[err.in_file(file)], &sources, file),
+                }
+            }
+            ":load" if arg.is_empty() => eprintln!("Expected a file after ':load'"),
+            ":load" => {
+                let input = SourceInput::File(arg.into());
+                match input.load() {
+                    Ok(source) => self.run(input.name(), source, false),
+                    Err(err) => eprintln!("{}", err),
+                }
+            }
+            ":reset" => {
+                self.interpreter = interpreter(&self.script_args);
+                self.sources = SourceMap::new();
+            }
+            ":help" => print!("{}", HELP),
+            ":quit" | ":q" => return false,
+            _ => eprintln!("Unknown command '{}', ':help' lists the commands", command),
+        }
+        true
+    }
+    // runs the entry, returns false if it's incomplete and continues on the next line
+    fn entry(&mut self, entry: &str) -> bool {
+        if let Err(err) = Parser::new(entry).parse() {
+            if is_incomplete(&err, entry) {
+                return false;
+            }
+        }
+        self.run("<repl>".to_string(), with_semicolon(entry), true);
+        true
+    }
+    // `print` shows the value of the last statement, if it's an expression
+    fn run(&mut self, name: String, source: String, print: bool) {
+        let file_id = self.sources.add(name, source);
+        let nodes = match parse_file(&self.sources, file_id) {
+            Ok(nodes) => nodes,
+            Err(err) => return self.report(&[err], &self.sources, file_id),
+        };
+        let errors = check(
+            &nodes,
+            &self.sources,
+            file_id,
+            &self.lints,
+            self.interpreter.globals(),
+        );
+        self.report(&errors, &self.sources, file_id);
+        if errors.iter().any(|err| err.severity == Severity::Error) {
+            return;
+        }
+
+        // folding can remove the statements after the last expression
+        let print = print && prints_value(&nodes);
+        let nodes = optimize(nodes);
+        match self.interpreter.run(&nodes, &self.sources, file_id) {
+            Ok(Value::None) => {}
+            Ok(value) if print => println!("{:?}", value),
+            Ok(_) => {}
+            Err(err) => print_runtime_error(&err, &self.sources),
+        }
+    }
+    // the errors are in `file` of `sources`
+    fn report(&self, errors: &[Error], sources: &SourceMap, file: FileId) {
+        let mut reporter = Reporter::new(self.error_format);
+        // the entries are all `<repl>`, files loaded with `:load` are named like in `check`
+        let name = sources.get(file).name();
+        if !errors.is_empty() && name != "<repl>" {
+            reporter.header(&format!("In '{}':", name));
+        }
+        for err in errors {
+            reporter.report(err, sources);
+        }
+        reporter.finish();
+    }
+}
+
+// the source with the missing `;` after its last statement added
+fn with_semicolon(source: &str) -> String {
+    let mut source = source.to_string();
+    if let Err(err) = Parser::new(&source).parse() {
+        let end = source.trim_end().len();
+        if matches!(err.error, ErrorType::ExpectedSemicolon) && err.range.end == end {
+            source.insert(end, ';');
+        }
+    }
+    source
+}
+
+// an unclosed bracket at the end of the entry, so more lines can close it
+fn is_incomplete(err: &Error, source: &str) -> bool {
+    if !matches!(
+        err.error,
+        ErrorType::ExpectedRCurly | ErrorType::ExpectedRParen | ErrorType::ExpectedRSquare
+    ) {
+        return false;
+    }
+    let Ok(tokens) = Parser::new(source).tokens() else {
+        return false;
+    };
+    let depth: isize = tokens
+        .iter()
+        .map(|token| match token.data {
+            Token::Symbol(
+                Symbol::LParenthesis | Symbol::LSquareBracket | Symbol::LCurlyBracket,
+            ) => 1,
+            Token::Symbol(
+                Symbol::RParenthesis | Symbol::RSquareBracket | Symbol::RCurlyBracket,
+            ) => -1,
+            _ => 0,
+        })
+        .sum();
+    depth > 0
+}
+
+// the value of an assignment is the value just assigned, so it's not repeated
+fn prints_value(nodes: &[ParseNode<Statement>]) -> bool {
+    matches!(
+        nodes.last().map(|node| &node.data),
+        Some(Statement::Expression(expr)) if !matches!(expr, Expression::Assign(_))
+    )
+}
+
+// completes keywords, declared names and the commands
+struct ReplHelper {
+    names: Vec<String>,
+}
+impl Completer for ReplHelper {
+    type Candidate = String;
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let before = &line[..pos];
+        if before.starts_with(':') && !before.contains(char::is_whitespace) {
+            let commands = COMMANDS
+                .iter()
+                .filter(|command| command.starts_with(before));
+            return Ok((0, commands.map(|command| command.to_string()).collect()));
+        }
+
+        let start = before
+            .char_indices()
+            .rev()
+            .take_while(|(_, c)| c.is_xid_continue())
+            .last()
+            .map_or(pos, |(i, _)| i);
+        let word = &before[start..];
+        if word.is_empty() {
+            return Ok((pos, vec![]));
+        }
+        let mut candidates: Vec<String> = Keyword::ALL
+            .iter()
+            .map(|keyword| keyword.as_str())
+            .chain(self.names.iter().map(String::as_str))
+            .filter(|candidate| candidate.starts_with(word))
+            .map(String::from)
+            .collect();
+        candidates.sort();
+        candidates.dedup();
+        Ok((start, candidates))
+    }
+}
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+impl Highlighter for ReplHelper {}
+impl Validator for ReplHelper {}
+impl Helper for ReplHelper {}
@@ -0,0 +1,430 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use crate::parser::suggest::{closest, foreign_value};
+use crate::parser::{error::*, parse_node::*, visit::*};
+use crate::string_name::StringName;
+
+/// Where a variable lives at runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VarLocation {
+    pub depth: usize, // how many scopes up from the use the variable is declared, 0 is the innermost scope
+    pub slot: usize,  // index of the variable in its scope, in declaration order
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Resolution {
+    // keyed by the range of the variable expression or the declared name
+    pub locations: HashMap<Range<usize>, VarLocation>,
+    // names of the slots of the outermost scope, the globals followed by the declarations of
+    // the program. a name declared again takes a new slot, so it can be in here more than once
+    pub globals: Vec<StringName>,
+    pub errors: Vec<Error>,
+}
+
+/// Builds the lexical scopes of the program and resolves every variable to its declaration.
+/// `globals` are the names defined before the program runs (such as native functions), they
+/// take the first slots of the outermost scope.
+///
+/// Function declarations are hoisted to the start of their scope, and function bodies are
+/// resolved at the end of it, so they can use names declared after them.
+pub fn resolve(
+    statements: &[ParseNode<Statement>],
+    globals: impl IntoIterator<Item = StringName>,
+) -> Resolution {
+    let mut resolver = Resolver {
+        scopes: vec![Scope::default()],
+        resolution: Resolution::default(),
+        loop_depth: 0,
+        in_function: false,
+    };
+    for global in globals {
+        resolver.declare(global, None, true);
+    }
+
+    resolver.visit_statements(statements);
+
+    let mut resolution = resolver.resolution;
+    resolution.globals = resolver.scopes[0].slot_names.clone();
+    resolution.errors.sort_by_key(|e| e.range.start);
+    resolution
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Variable {
+    slot: usize,
+    initialized: bool, // false while its own initializer is being resolved
+}
+#[derive(Debug, Default)]
+struct Scope {
+    names: HashMap<StringName, Variable>,
+    slot_names: Vec<StringName>,
+}
+
+struct Resolver {
+    scopes: Vec<Scope>,
+    resolution: Resolution,
+    loop_depth: usize, // loops around the current statement, in the current function
+    in_function: bool,
+}
+impl Resolver {
+    fn declare(&mut self, name: StringName, range: Option<&Range<usize>>, initialized: bool) {
+        let scope = self.scopes.last_mut().unwrap();
+        let slot = scope.slot_names.len();
+        scope.slot_names.push(name);
+        scope.names.insert(name, Variable { slot, initialized });
+
+        if let Some(range) = range {
+            self.resolution
+                .locations
+                .insert(range.clone(), VarLocation { depth: 0, slot });
+        }
+    }
+    fn initialize(&mut self, name: StringName) {
+        if let Some(var) = self.scopes.last_mut().unwrap().names.get_mut(&name) {
+            var.initialized = true;
+        }
+    }
+    fn error(&mut self, range: &Range<usize>, error: ErrorType) {
+        self.resolution
+            .errors
+            .push(Error::new(range.clone(), error));
+    }
+    // the keyword or visible variable the undefined name is likely a typo of
+    fn similar_name(&self, name: StringName) -> Option<&'static str> {
+        if let Some(keyword) = foreign_value(name.as_str()) {
+            return Some(keyword.as_str());
+        }
+        // sorted, so ties don't depend on the order of the hash maps
+        let mut visible: Vec<&str> = self
+            .scopes
+            .iter()
+            .flat_map(|scope| scope.names.keys().map(|name| name.as_str()))
+            .collect();
+        visible.sort_unstable();
+        closest(name.as_str(), visible)
+    }
+    fn scope(&mut self, resolve_fn: impl FnOnce(&mut Self)) {
+        self.scopes.push(Scope::default());
+        resolve_fn(self);
+        self.scopes.pop();
+    }
+    fn function(&mut self, decl: &FuncDecl) {
+        // loops outside of the function can't be broken from inside of it
+        let loop_depth = std::mem::replace(&mut self.loop_depth, 0);
+        let in_function = std::mem::replace(&mut self.in_function, true);
+        self.function_scope(decl);
+        self.loop_depth = loop_depth;
+        self.in_function = in_function;
+    }
+    fn function_scope(&mut self, decl: &FuncDecl) {
+        self.scope(|r| {
+            let mut param_names: Vec<(StringName, Range<usize>)> = vec![];
+            for param in &decl.params {
+                for (name, range) in pattern_names(&param.data.pattern) {
+                    let Some(name) = name else {
+                        continue;
+                    };
+                    if let Some((_, first)) = param_names.iter().find(|(n, _)| *n == name) {
+                        let error = Error::new(range.clone(), ErrorType::DuplicateParameter(name))
+                            .with_label(first.clone(), "first declared here");
+                        r.resolution.errors.push(error);
+                    }
+                    param_names.push((name, range));
+                }
+                r.visit_var_decl(&param.data, &param.range);
+            }
+
+            // the parameters and the body share the same scope
+            match &decl.block.data {
+                FuncBlock::Block(block) => r.visit_statements(&block.statements),
+                FuncBlock::ReturnExpr(expr) => r.visit_expression(expr, &decl.block.range),
+            }
+        });
+    }
+}
+
+// declared names of a pattern with their ranges, `None` for `_`
+fn pattern_names(pattern: &ParseNode<VarNameType>) -> Vec<(Option<StringName>, Range<usize>)> {
+    match &pattern.data {
+        VarNameType::Ident(name) => vec![(*name, pattern.range.clone())],
+        VarNameType::Array {
+            start_names,
+            end_names,
+        } => start_names
+            .iter()
+            .chain(end_names)
+            .map(|name| (name.data, name.range.clone()))
+            .collect(),
+        VarNameType::Object(names) => names
+            .iter()
+            .map(|(_, name)| (Some(name.data), name.range.clone()))
+            .collect(),
+    }
+}
+
+impl Visitor for Resolver {
+    fn visit_statements(&mut self, statements: &[ParseNode<Statement>]) {
+        let mut functions = vec![];
+
+        for stmt in statements {
+            if let Statement::Declaration(Declaration::Func(decl)) = &stmt.data {
+                self.declare(decl.name.data, Some(&decl.name.range), true);
+                functions.push(decl);
+            }
+        }
+        for stmt in statements {
+            if !matches!(stmt.data, Statement::Declaration(Declaration::Func(_))) {
+                self.visit_statement(&stmt.data, &stmt.range);
+            }
+        }
+        for decl in functions {
+            self.function(decl);
+        }
+    }
+    fn visit_statement(&mut self, statement: &Statement, range: &Range<usize>) {
+        match statement {
+            Statement::Break if self.loop_depth == 0 => {
+                self.error(range, ErrorType::OutsideLoop(Keyword::Break))
+            }
+            Statement::Continue if self.loop_depth == 0 => {
+                self.error(range, ErrorType::OutsideLoop(Keyword::Continue))
+            }
+            Statement::Return(_) if !self.in_function => {
+                self.error(range, ErrorType::ReturnOutsideFunction)
+            }
+            _ => {}
+        }
+        walk_statement(self, statement, range);
+    }
+    fn visit_while_statement(&mut self, statement: &WhileStatement, _range: &Range<usize>) {
+        let condition = &statement.condition;
+        self.visit_expression(&condition.data, &condition.range);
+
+        // only the loop block itself is inside the loop, not `onbreak` and `oncontinue`
+        self.loop_depth += 1;
+        self.visit_block(&statement.loop_block.data, &statement.loop_block.range);
+        self.loop_depth -= 1;
+
+        for block in [&statement.on_break, &statement.on_continue]
+            .into_iter()
+            .flatten()
+        {
+            self.visit_block(&block.data, &block.range);
+        }
+    }
+    fn visit_block(&mut self, block: &Block, range: &Range<usize>) {
+        self.scope(|r| walk_block(r, block, range));
+    }
+    // the caught error and the block share the same scope
+    fn visit_catch_block(&mut self, block: &CatchBlock, _range: &Range<usize>) {
+        self.scope(|r| {
+            if let Some(name) = block.name.data {
+                r.declare(name, Some(&block.name.range), true);
+            }
+            r.visit_statements(&block.block.data.statements);
+        });
+    }
+    fn visit_func_decl(&mut self, decl: &FuncDecl, _range: &Range<usize>) {
+        // declarations in statement lists are hoisted by `visit_statements` instead
+        self.declare(decl.name.data, Some(&decl.name.range), true);
+        self.function(decl);
+    }
+    fn visit_var_decl(&mut self, decl: &VarDecl, _range: &Range<usize>) {
+        let names = pattern_names(&decl.pattern);
+
+        // the names are declared before the initializer, so reading them in it is reported
+        for (name, range) in &names {
+            if let Some(name) = name {
+                self.declare(*name, Some(range), false);
+            }
+        }
+        if let Some(value) = &decl.value {
+            self.visit_expression(&value.data, &value.range);
+        }
+        for (name, _) in names {
+            if let Some(name) = name {
+                self.initialize(name);
+            }
+        }
+    }
+    fn visit_variable(&mut self, name: StringName, range: &Range<usize>) {
+        let found = self
+            .scopes
+            .iter()
+            .rev()
+            .enumerate()
+            .find_map(|(depth, scope)| scope.names.get(&name).map(|var| (depth, *var)));
+
+        let Some((depth, var)) = found else {
+            let mut error = Error::new(range.clone(), ErrorType::UndefinedVariable(name));
+            if let Some(similar) = self.similar_name(name) {
+                let message = format!("did you mean '{}'?", similar);
+                error = error.with_suggestion(range.clone(), similar, message);
+            }
+            return self.resolution.errors.push(error);
+        };
+        if !var.initialized {
+            let error = Error::new(range.clone(), ErrorType::ReadInOwnInitializer(name))
+                .with_note("a variable can only be read after its initializer is evaluated");
+            self.resolution.errors.push(error);
+        }
+        self.resolution.locations.insert(
+            range.clone(),
+            VarLocation {
+                depth,
+                slot: var.slot,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::Interpreter;
+    use crate::parser::Parser;
+    use crate::source_map::SourceMap;
+
+    // the locations of the variables in source order, written as `name depth:slot`
+    fn locations(source: &str, globals: &[&str]) -> Vec<String> {
+        let statements = Parser::new(source).parse().unwrap();
+        let resolution = resolve(&statements, globals.iter().map(|&g| StringName::from(g)));
+        assert!(resolution.errors.is_empty(), "{:?}", resolution.errors);
+
+        let mut locations: Vec<_> = resolution.locations.into_iter().collect();
+        locations.sort_by_key(|(range, _)| range.start);
+        locations
+            .into_iter()
+            .map(|(range, l)| format!("{} {}:{}", &source[range], l.depth, l.slot))
+            .collect()
+    }
+    // runs the programs one after another in the same interpreter, and writes the value of the
+    // last one
+    fn run(programs: &[&str]) -> String {
+        let mut sources = SourceMap::new();
+        let mut interpreter = Interpreter::new();
+        let mut value = String::new();
+        for &source in programs {
+            let file = sources.add("test.lox", source);
+            let statements = Parser::new(sources.get(file).source()).parse().unwrap();
+            value = interpreter
+                .run(&statements, &sources, file)
+                .unwrap()
+                .to_string();
+        }
+        value
+    }
+
+    #[test]
+    fn nested_scopes() {
+        let source = "let a = 1; let b = 2; { let c = a; { b = c; } } b;";
+        assert_eq!(
+            locations(source, &[]),
+            ["a 0:0", "b 0:1", "c 0:0", "a 1:0", "b 2:1", "c 1:0", "b 0:1"]
+        );
+        assert_eq!(run(&[source]), "1");
+    }
+
+    #[test]
+    fn globals_take_the_first_slots() {
+        let source = "let a = 1; a;";
+        assert_eq!(locations(source, &["g", "h"]), ["a 0:2", "a 0:2"]);
+        // the top level declarations of a program stay visible to the next ones
+        assert_eq!(run(&["let a = 1;", "let b = a + 1;", "[a, b];"]), "[1, 2]");
+    }
+
+    #[test]
+    fn shadowing() {
+        let source = r#"
+            let x = "outer";
+            let seen = [];
+            { seen.push(x); let x = "inner"; seen.push(x); }
+            let x = "again";
+            seen.push(x);
+            seen;
+        "#;
+        assert_eq!(
+            locations(source, &[]),
+            [
+                "x 0:0", "seen 0:1", "seen 1:1", "x 1:0", "x 0:0", "seen 1:1", "x 0:0", "x 0:2",
+                "seen 0:1", "x 0:2", "seen 0:1",
+            ]
+        );
+        assert_eq!(run(&[source]), r#"["outer", "inner", "again"]"#);
+    }
+
+    #[test]
+    fn hoisted_functions() {
+        let source = r#"
+            let result = twice(3);
+            func twice(n) -> add_one(add_one(n))
+            func add_one(n) -> n + 1
+            result;
+        "#;
+        assert_eq!(
+            locations(source, &[]),
+            [
+                "result 0:2",
+                "twice 0:0",
+                "twice 0:0",
+                "n 0:0",
+                "add_one 1:1",
+                "add_one 1:1",
+                "n 0:0",
+                "add_one 0:1",
+                "n 0:0",
+                "n 0:0",
+                "result 0:2",
+            ]
+        );
+        assert_eq!(run(&[source]), "5");
+    }
+
+    #[test]
+    fn closures() {
+        let source = r#"
+            func counter(start) {
+                let count = start;
+                func next() {
+                    count += 1;
+                    return count;
+                }
+                return next;
+            }
+            let next = counter(10);
+            next();
+            next();
+        "#;
+        assert_eq!(
+            locations(source, &[]),
+            [
+                "counter 0:0",
+                "start 0:0",
+                "count 0:2",
+                "start 0:0",
+                "next 0:1",
+                "count 1:2",
+                "count 1:2",
+                "next 0:1",
+                "next 0:1",
+                "counter 0:0",
+                "next 0:1",
+                "next 0:1",
+            ]
+        );
+        assert_eq!(run(&[source]), "12");
+    }
+
+    #[test]
+    fn loops_and_caught_errors() {
+        let source = r#"
+            let total = 0;
+            let i = 0;
+            while i < 3 { let step = i * 10; total += step; i += 1; }
+            try { throw "failed"; } catch total { i = total.message; }
+            [total, i];
+        "#;
+        assert_eq!(run(&[source]), r#"[30, "failed"]"#);
+    }
+}
@@ -1,3 +1,7 @@
+use std::fmt;
+use std::fs;
+use std::io::{self, Read};
+use std::path::PathBuf;
 use std::str::CharIndices;
 
 // Handles iterating over the source code
@@ -32,3 +36,131 @@ impl<'a> Iterator for SourceIter<'a> {
         ch
     }
 }
+
+/// Where the source of a program is read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SourceInput {
+    File(PathBuf),
+    Stdin,
+    Inline(String),
+}
+impl SourceInput {
+    /// `-` reads from stdin, anything else is a file path.
+    pub fn from_arg(arg: &str) -> Self {
+        if arg == "-" {
+            SourceInput::Stdin
+        } else {
+            SourceInput::File(arg.into())
+        }
+    }
+    /// Name of the source in diagnostics.
+    pub fn name(&self) -> String {
+        match self {
+            SourceInput::File(path) => path.display().to_string(),
+            SourceInput::Stdin => "<stdin>".to_string(),
+            SourceInput::Inline(_) => "<inline>".to_string(),
+        }
+    }
+    /// Reads the source and decodes it as UTF-8, or as UTF-16 when it starts with a UTF-16 byte
+    /// order mark. A UTF-8 byte order mark is removed.
+    pub fn load(&self) -> Result<String, LoadError> {
+        let bytes = match self {
+            SourceInput::File(path) => fs::read(path),
+            SourceInput::Stdin => {
+                let mut bytes = vec![];
+                io::stdin().read_to_end(&mut bytes).map(|_| bytes)
+            }
+            SourceInput::Inline(code) => return Ok(code.clone()),
+        };
+        let bytes = bytes.map_err(|error| LoadError::Io {
+            name: self.name(),
+            error,
+        })?;
+        decode(&bytes).map_err(|(encoding, offset, valid)| {
+            let line_start = valid.rfind('\n').map_or(0, |i| i + 1);
+            LoadError::InvalidEncoding {
+                name: self.name(),
+                encoding,
+                offset,
+                line: valid.matches('\n').count() + 1,
+                column: valid[line_start..].chars().count() + 1,
+            }
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io {
+        name: String,
+        error: io::Error,
+    },
+    // `offset` is the byte offset of the first invalid byte in the input, the 1-based `line` and
+    // `column` are counted in the characters before it
+    InvalidEncoding {
+        name: String,
+        encoding: &'static str,
+        offset: usize,
+        line: usize,
+        column: usize,
+    },
+}
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io { name, error } => write!(f, "Failed to read '{}': {}", name, error),
+            LoadError::InvalidEncoding {
+                name,
+                encoding,
+                offset,
+                line,
+                column,
+            } => write!(
+                f,
+                "'{}' is not valid {}: invalid data at byte offset {} (line {}, column {})",
+                name, encoding, offset, line, column
+            ),
+        }
+    }
+}
+impl std::error::Error for LoadError {}
+
+// the source text, or the encoding, the byte offset of the first invalid character and the text
+// decoded before it
+fn decode(bytes: &[u8]) -> Result<String, (&'static str, usize, String)> {
+    let utf16 = |bytes: &[u8], from_bytes: fn([u8; 2]) -> u16| {
+        let units = bytes.chunks(2).map(|pair| match pair {
+            [a, b] => from_bytes([*a, *b]),
+            _ => 0xD800, // a lone byte at the end, reported as an unpaired surrogate
+        });
+        let mut text = String::new();
+        let mut offset = 2;
+        for ch in char::decode_utf16(units) {
+            let Ok(ch) = ch else {
+                return Err(("UTF-16", offset, text));
+            };
+            text.push(ch);
+            offset += ch.len_utf16() * 2;
+        }
+        Ok(text)
+    };
+
+    if let Some(bytes) = bytes.strip_prefix(b"\xFF\xFE") {
+        return utf16(bytes, u16::from_le_bytes);
+    }
+    if let Some(bytes) = bytes.strip_prefix(b"\xFE\xFF") {
+        return utf16(bytes, u16::from_be_bytes);
+    }
+    let (bom, bytes) = match bytes.strip_prefix(b"\xEF\xBB\xBF") {
+        Some(bytes) => (3, bytes),
+        None => (0, bytes),
+    };
+    match std::str::from_utf8(bytes) {
+        Ok(text) => Ok(text.to_string()),
+        Err(error) => {
+            let valid = &bytes[..error.valid_up_to()];
+            let valid = String::from_utf8_lossy(valid).into_owned();
+            Err(("UTF-8", bom + error.valid_up_to(), valid))
+        }
+    }
+}
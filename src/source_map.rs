@@ -0,0 +1,112 @@
+use std::ops::Range;
+use std::rc::Rc;
+
+use crate::source::SourceIter;
+
+/// Identifies a file registered in a `SourceMap`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FileId(u32);
+
+/// A byte range in a specific file.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub file: FileId,
+    pub range: Range<usize>,
+}
+impl Span {
+    pub fn new(file: FileId, range: Range<usize>) -> Self {
+        Self { file, range }
+    }
+}
+
+/// A source file with a precomputed table of line starts, for fast offset to line lookups.
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+    name: String,
+    source: String,
+    line_starts: Vec<usize>,
+}
+impl SourceFile {
+    pub fn new(name: impl Into<String>, source: impl Into<String>) -> Self {
+        let source = source.into();
+        let mut line_starts = vec![0];
+        for (i, c) in SourceIter::from(source.as_str()) {
+            if c == '\n' {
+                line_starts.push(i + 1); // '\n' will always have length 1
+            }
+        }
+        Self {
+            name: name.into(),
+            source,
+            line_starts,
+        }
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+    /// 0-based index of the line the offset is on. Offsets at a line ending belong to that line.
+    pub fn line_index(&self, offset: usize) -> usize {
+        self.line_starts.partition_point(|&start| start <= offset) - 1
+    }
+    /// Byte range of the line, without its line ending.
+    pub fn line_range(&self, line: usize) -> Range<usize> {
+        let start = self.line_starts[line];
+        let Some(&next_start) = self.line_starts.get(line + 1) else {
+            return start..self.source.len();
+        };
+        // the line ending is either '\n', '\r' or '\r\n'
+        let end = next_start - 1;
+        if self.source[..end].ends_with('\r') && self.source[end..].starts_with('\n') {
+            start..end - 1
+        } else {
+            start..end
+        }
+    }
+    /// 1-based line and column of the offset, the column is counted in characters.
+    pub fn line_column(&self, offset: usize) -> (usize, usize) {
+        let line = self.line_index(offset);
+        let line_start = self.line_starts[line];
+        let column = self.source[line_start..offset].chars().count();
+        (line + 1, column + 1)
+    }
+}
+
+/// Owns every loaded source file, and gives them a `FileId` so spans can refer to any of them.
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap {
+    files: Vec<Rc<SourceFile>>,
+}
+impl SourceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn add(&mut self, name: impl Into<String>, source: impl Into<String>) -> FileId {
+        self.files.push(Rc::new(SourceFile::new(name, source)));
+        FileId(self.files.len() as u32 - 1)
+    }
+    pub fn get(&self, file: FileId) -> &SourceFile {
+        &self.files[file.0 as usize]
+    }
+    /// The file, for keeping it after the map is gone or changed.
+    pub fn shared(&self, file: FileId) -> Rc<SourceFile> {
+        self.files[file.0 as usize].clone()
+    }
+    pub fn files(&self) -> impl Iterator<Item = (FileId, &SourceFile)> {
+        self.files
+            .iter()
+            .enumerate()
+            .map(|(i, file)| (FileId(i as u32), file.as_ref()))
+    }
+    /// File name with the 1-based line and column of the start of the span.
+    pub fn location(&self, span: &Span) -> (&str, usize, usize) {
+        let file = self.get(span.file);
+        let (line, column) = file.line_column(span.range.start);
+        (file.name(), line, column)
+    }
+}
@@ -1,40 +1,221 @@
-use indexmap::IndexSet;
 use once_cell::sync::Lazy;
 use std::{
+    collections::HashMap,
     fmt::{self, Debug, Display},
     hash::Hash,
-    mem::transmute,
-    sync::RwLock,
+    sync::{Arc, OnceLock, RwLock},
 };
 
-static INTERN_MAP: Lazy<RwLock<IndexSet<String>>> = Lazy::new(|| RwLock::new(IndexSet::new()));
+// the first chunk of the arena has this many slots, every next one twice as many as the last
+const FIRST_CHUNK_BITS: u32 = 6;
+const CHUNKS: usize = (u32::BITS - FIRST_CHUNK_BITS + 1) as usize;
 
-fn intern(name: &str) -> usize {
-    let intern_map = INTERN_MAP.read().unwrap();
+// slots are filled once, in order, and never change after that
+type Chunk = Box<[OnceLock<Arc<str>>]>;
 
-    match intern_map.get_index_of(name) {
-        Some(index) => index,
-        None => {
-            drop(intern_map);
+macro_rules! preinterned {
+    ($($name:ident = $string:literal,)*) => {
+        #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+        #[repr(u32)]
+        enum Preinterned {
+            $($name,)*
+        }
+        const PREINTERNED: &[&str] = &[$($string,)*];
 
-            let mut intern_map = INTERN_MAP.write().unwrap();
-            let (index, _) = intern_map.insert_full(name.to_string());
+        impl StringName {
+            $(pub const $name: StringName = StringName(Preinterned::$name as u32);)*
+        }
+    };
+}
+
+// names every interner starts with, at fixed indices, so they can be constants
+preinterned! {
+    // keywords
+    NONE = "none",
+    TRUE = "true",
+    FALSE = "false",
+    LET = "let",
+    FUNC = "func",
+    IF = "if",
+    ELIF = "elif",
+    ELSE = "else",
+    FOR = "for",
+    WHILE = "while",
+    BREAK = "break",
+    CONTINUE = "continue",
+    RETURN = "return",
+    ONBREAK = "onbreak",
+    ONCONTINUE = "oncontinue",
+    THROW = "throw",
+    TRY = "try",
+    CATCH = "catch",
+    FINALLY = "finally",
+    AND = "and",
+    OR = "or",
+    UNDERSCORE = "_",
+    // native functions and globals
+    PRINT = "print",
+    PRINTLN = "println",
+    LEN = "len",
+    TYPE_OF = "type_of",
+    ERROR = "error",
+    STR = "str",
+    INT = "int",
+    REAL = "real",
+    CHAR = "char",
+    ORD = "ord",
+    ABS = "abs",
+    MIN = "min",
+    MAX = "max",
+    FLOOR = "floor",
+    CEIL = "ceil",
+    ROUND = "round",
+    POW = "pow",
+    SQRT = "sqrt",
+    SIN = "sin",
+    COS = "cos",
+    TAN = "tan",
+    ASIN = "asin",
+    ACOS = "acos",
+    ATAN = "atan",
+    ATAN2 = "atan2",
+    CLOCK = "clock",
+    SEED = "seed",
+    RANDOM = "random",
+    ARGS = "args",
+    // methods of the built in types, the ones not named like a native function
+    PUSH = "push",
+    POP = "pop",
+    INSERT = "insert",
+    REMOVE = "remove",
+    SORT = "sort",
+    REVERSE = "reverse",
+    MAP = "map",
+    FILTER = "filter",
+    REDUCE = "reduce",
+    FIND = "find",
+    CONTAINS = "contains",
+    ZIP = "zip",
+    ENUMERATE = "enumerate",
+    KEYS = "keys",
+    VALUES = "values",
+    ITEMS = "items",
+    GET = "get",
+    HAS = "has",
+    MERGE = "merge",
+    SPLIT = "split",
+    JOIN = "join",
+    TRIM = "trim",
+    TRIM_START = "trim_start",
+    TRIM_END = "trim_end",
+    REPLACE = "replace",
+    STARTS_WITH = "starts_with",
+    ENDS_WITH = "ends_with",
+    TO_UPPER = "to_upper",
+    TO_LOWER = "to_lower",
+    CHARS = "chars",
+    GRAPHEMES = "graphemes",
+    BYTES = "bytes",
+    REPEAT = "repeat",
+    PAD_LEFT = "pad_left",
+    PAD_RIGHT = "pad_right",
+    IS_DIGIT = "is_digit",
+    IS_ALPHA = "is_alpha",
+    IS_ALPHANUMERIC = "is_alphanumeric",
+    IS_WHITESPACE = "is_whitespace",
+    IS_UPPER = "is_upper",
+    IS_LOWER = "is_lower",
+    // properties of errors
+    KIND = "kind",
+    MESSAGE = "message",
+    VALUE = "value",
+    TRACE = "trace",
+}
 
-            if cfg!(debug_assertions) {
-                println!("Interned {}: {:?}", index, name);
-            }
+/// An append-only set of strings, giving each one a `StringName`.
+///
+/// The global interner is used by `StringName::from` and `StringName::as_str`, and its names are
+/// never freed. Hosts running many isolated scripts can make their own with `Interner::new`,
+/// whose strings are freed when it's dropped. The names of such an interner can only be read
+/// back with its `as_str`, except for the pre-interned ones (such as `StringName::LET`), which
+/// are the same in every interner.
+//
+// the strings are stored in an arena of chunks that are never moved, so reading a string back is
+// a couple of atomic loads, without locking. interning takes the lock around the lookup table:
+// the read lock for a string that is already interned, the write lock for a new one
+pub struct Interner {
+    lookup: RwLock<HashMap<Arc<str>, u32>>,
+    chunks: [OnceLock<Chunk>; CHUNKS],
+}
+impl Interner {
+    /// An interner with only the pre-interned names.
+    pub fn new() -> Self {
+        let interner = Self {
+            lookup: RwLock::new(HashMap::new()),
+            chunks: std::array::from_fn(|_| OnceLock::new()),
+        };
+        for name in PREINTERNED {
+            interner.intern(name);
+        }
+        interner
+    }
+    pub fn intern(&self, name: &str) -> StringName {
+        if let Some(&index) = self.lookup.read().unwrap().get(name) {
+            return StringName(index);
+        }
 
-            index
+        let mut lookup = self.lookup.write().unwrap();
+        // another thread may have interned it between the two locks
+        if let Some(&index) = lookup.get(name) {
+            return StringName(index);
         }
+        let index = u32::try_from(lookup.len()).expect("too many interned strings");
+        let name: Arc<str> = name.into();
+        let (chunk, slot) = Self::position(index);
+        let chunk = self.chunks[chunk].get_or_init(|| {
+            let len = 1 << (chunk as u32 + FIRST_CHUNK_BITS);
+            (0..len).map(|_| OnceLock::new()).collect()
+        });
+        // the slot is only written here, while holding the write lock
+        let _ = chunk[slot].set(name.clone());
+        lookup.insert(name, index);
+
+        StringName(index)
+    }
+    /// The string of the name.
+    ///
+    /// # Panics
+    /// If the name was made by another interner, and isn't one of the pre-interned names.
+    pub fn as_str(&self, name: StringName) -> &str {
+        let (chunk, slot) = Self::position(name.0);
+        self.chunks[chunk]
+            .get()
+            .and_then(|chunk| chunk[slot].get())
+            .expect("the name was made by another interner")
+    }
+    // chunk and slot in the chunk of the index
+    fn position(index: u32) -> (usize, usize) {
+        let index = index as u64 + (1 << FIRST_CHUNK_BITS);
+        let bit = u64::BITS - 1 - index.leading_zeros();
+        let chunk = bit - FIRST_CHUNK_BITS;
+        (chunk as usize, (index - (1 << bit)) as usize)
+    }
+}
+impl Default for Interner {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
-/// An immutable string that is interned. The strings are simply compared by comparing the pointers.
+static GLOBAL: Lazy<Interner> = Lazy::new(Interner::new);
+
+/// An immutable string that is interned in the global interner. The strings are simply compared
+/// by comparing their indices in the interner.
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
-pub struct StringName(usize);
+pub struct StringName(u32);
 impl StringName {
     pub fn as_str(&self) -> &'static str {
-        unsafe { transmute(INTERN_MAP.read().unwrap()[self.0].as_str()) }
+        GLOBAL.as_str(*self)
     }
 }
 impl Display for StringName {
@@ -49,12 +230,12 @@ impl Debug for StringName {
 }
 impl From<&str> for StringName {
     fn from(name: &str) -> Self {
-        Self(intern(name))
+        GLOBAL.intern(name)
     }
 }
 impl From<String> for StringName {
     fn from(name: String) -> Self {
-        Self(intern(name.as_str()))
+        GLOBAL.intern(name.as_str())
     }
 }
 impl From<StringName> for String {
@@ -67,3 +248,120 @@ impl From<StringName> for &str {
         name.as_str()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn positions_at_chunk_boundaries() {
+        let first = 1 << FIRST_CHUNK_BITS;
+        assert_eq!(Interner::position(0), (0, 0));
+        assert_eq!(Interner::position(first - 1), (0, first as usize - 1));
+        assert_eq!(Interner::position(first), (1, 0));
+        assert_eq!(
+            Interner::position(3 * first - 1),
+            (1, 2 * first as usize - 1)
+        );
+        assert_eq!(Interner::position(3 * first), (2, 0));
+        assert_eq!(Interner::position(7 * first), (3, 0));
+        // the last index still has a chunk
+        assert_eq!(
+            Interner::position(u32::MAX),
+            (CHUNKS - 1, first as usize - 1)
+        );
+    }
+
+    #[test]
+    fn names_are_read_back_across_chunks() {
+        let interner = Interner::new();
+        let strings: Vec<String> = (0..1000).map(|i| format!("name{}", i)).collect();
+        let names: Vec<StringName> = strings.iter().map(|s| interner.intern(s)).collect();
+
+        for (string, name) in strings.iter().zip(&names) {
+            assert_eq!(interner.as_str(*name), string);
+            assert_eq!(interner.intern(string), *name);
+        }
+        // every name has its own index, after the pre-interned ones
+        let indices: HashSet<u32> = names.iter().map(|name| name.0).collect();
+        assert_eq!(indices.len(), strings.len());
+        assert!(indices.iter().all(|&i| i as usize >= PREINTERNED.len()));
+    }
+
+    #[test]
+    fn preinterned_names() {
+        let unique: HashSet<&str> = PREINTERNED.iter().copied().collect();
+        assert_eq!(unique.len(), PREINTERNED.len());
+
+        // at the same index in the global interner and in every scoped one
+        let interner = Interner::new();
+        for (index, string) in PREINTERNED.iter().enumerate() {
+            assert_eq!(StringName::from(*string), StringName(index as u32));
+            assert_eq!(interner.intern(string), StringName(index as u32));
+        }
+
+        assert_eq!(StringName::LET.as_str(), "let");
+        assert_eq!(StringName::UNDERSCORE.as_str(), "_");
+        assert_eq!(StringName::PRINTLN.as_str(), "println");
+        assert_eq!(StringName::TYPE_OF.as_str(), "type_of");
+        assert_eq!(StringName::PAD_LEFT.as_str(), "pad_left");
+        assert_eq!(StringName::IS_ALPHANUMERIC.as_str(), "is_alphanumeric");
+        assert_eq!(interner.as_str(StringName::GRAPHEMES), "graphemes");
+        assert_eq!(StringName::from("trace"), StringName::TRACE);
+    }
+
+    #[test]
+    fn scoped_interners_are_separate() {
+        let a = Interner::new();
+        let b = Interner::new();
+        let in_a = a.intern("only in a");
+        let in_b = b.intern("only in b");
+        // both are the first name after the pre-interned ones
+        assert_eq!(in_a, in_b);
+        assert_eq!(a.as_str(in_a), "only in a");
+        assert_eq!(b.as_str(in_b), "only in b");
+
+        let unknown = StringName(PREINTERNED.len() as u32 + 1);
+        let result = std::panic::catch_unwind(|| a.as_str(unknown).to_string());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn concurrent_interning() {
+        const THREADS: usize = 8;
+        let interner = Interner::new();
+        let strings: Vec<String> = (0..500).map(|i| format!("shared{}", i)).collect();
+
+        // every thread interns the same strings in a different order, and reads back the ones
+        // the others may still be interning
+        let results: Vec<Vec<(usize, StringName)>> = std::thread::scope(|scope| {
+            let threads: Vec<_> = (0..THREADS)
+                .map(|thread| {
+                    let (interner, strings) = (&interner, &strings);
+                    scope.spawn(move || {
+                        let mut names = vec![];
+                        for i in 0..strings.len() {
+                            let i = (i * 7 + thread * 61) % strings.len();
+                            let name = interner.intern(&strings[i]);
+                            assert_eq!(interner.as_str(name), strings[i]);
+                            let global = StringName::from(strings[i].as_str());
+                            assert_eq!(global.as_str(), strings[i]);
+                            names.push((i, name));
+                        }
+                        names
+                    })
+                })
+                .collect();
+            threads.into_iter().map(|t| t.join().unwrap()).collect()
+        });
+
+        // the threads agree on the name of every string
+        let mut names = vec![None; strings.len()];
+        for (i, name) in results.into_iter().flatten() {
+            assert_eq!(*names[i].get_or_insert(name), name);
+        }
+        let unique: HashSet<u32> = names.iter().map(|name| name.unwrap().0).collect();
+        assert_eq!(unique.len(), strings.len());
+    }
+}
@@ -0,0 +1,837 @@
+// the gradual type checker. a variable has the type of its annotation, or the type inferred from
+// its value when it's never assigned again, where the elements and fields of containers are only
+// known from annotations. values without an annotation can have any type, so a mismatch is only
+// reported when one of its types comes from an annotation. unannotated code is never reported
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::ops::Range;
+use std::rc::Rc;
+
+use crate::interpreter::operator;
+use crate::interpreter::value::Value;
+use crate::parser::suggest::closest;
+use crate::parser::{error::*, parse_node::*, visit::*};
+use crate::string_name::StringName;
+
+/// Checks the values given to annotated variables, parameters and returns, and the operands of
+/// operators, against the types of the annotations. The operands are checked with the operators
+/// of the interpreter, so an operation is reported exactly when it would fail at runtime.
+pub fn type_check(statements: &[ParseNode<Statement>]) -> Vec<Error> {
+    let mut reassigned = Reassigned::default();
+    reassigned.visit_statements(statements);
+
+    let mut checker = Checker {
+        scopes: vec![HashMap::new()],
+        reassigned: reassigned.names,
+        returns: None,
+        errors: vec![],
+    };
+    checker.visit_statements(statements);
+    checker.errors.sort_by_key(|e| e.range.start);
+    checker.errors
+}
+
+// the names of the types that can be written in annotations
+const TYPE_NAMES: [&str; 11] = [
+    "any",
+    "none",
+    "bool",
+    "int",
+    "real",
+    "string",
+    "char",
+    "array",
+    "dictionary",
+    "function",
+    "error",
+];
+
+#[derive(Debug, Clone)]
+enum Type {
+    Any, // not known until the program runs
+    None,
+    Bool,
+    Int,
+    Real,
+    String,
+    Char,
+    Error,
+    Array(Box<Type>),
+    // the keys the dictionary is known to have, `None` for any dictionary
+    Dictionary(Option<Vec<(StringName, Type)>>),
+    // `None` for a function without a known signature, such as a native function
+    Function(Option<Rc<Signature>>),
+}
+impl Type {
+    fn named(name: StringName) -> Option<Self> {
+        Some(match name.as_str() {
+            "any" => Type::Any,
+            "none" => Type::None,
+            "bool" => Type::Bool,
+            "int" => Type::Int,
+            "real" => Type::Real,
+            "string" => Type::String,
+            "char" => Type::Char,
+            "array" => Type::Array(Type::Any.into()),
+            "dictionary" => Type::Dictionary(None),
+            "function" => Type::Function(None),
+            "error" => Type::Error,
+            _ => return None,
+        })
+    }
+    // the type of a value the interpreter returned, arrays are of any values
+    fn of(value: &Value) -> Self {
+        match value {
+            Value::None => Type::None,
+            Value::Bool(_) => Type::Bool,
+            Value::Int(_) => Type::Int,
+            Value::Real(_) => Type::Real,
+            Value::String(_) => Type::String,
+            Value::Char(_) => Type::Char,
+            Value::Array(_) => Type::Array(Type::Any.into()),
+            Value::Dictionary(_) => Type::Dictionary(None),
+            Value::Function(_) | Value::Native(_) => Type::Function(None),
+            Value::Error(_) => Type::Error,
+        }
+    }
+    // a value of the type, to try the operators of the interpreter on.
+    // `None` when the type isn't known or has no simple value
+    fn sample(&self) -> Option<Value> {
+        Some(match self {
+            Type::None => Value::None,
+            Type::Bool => Value::Bool(true),
+            Type::Int => Value::Int(1),
+            Type::Real => Value::Real(1.0),
+            Type::String => Value::String("".into()),
+            Type::Char => Value::Char('a'),
+            Type::Array(_) => Value::array(vec![]),
+            Type::Dictionary(_) => Value::dictionary(Default::default()),
+            Type::Any | Type::Error | Type::Function(_) => return None,
+        })
+    }
+    // whether a value of type `found` can be given where this type is expected
+    fn accepts(&self, found: &Type) -> bool {
+        match (self, found) {
+            (Type::Any, _) | (_, Type::Any) => true,
+            (Type::Array(expected), Type::Array(found)) => expected.accepts(found),
+            (Type::Dictionary(Some(expected)), Type::Dictionary(Some(found))) => {
+                expected.iter().all(|(name, expected)| {
+                    found
+                        .iter()
+                        .find(|(key, _)| key == name)
+                        .is_some_and(|(_, found)| expected.accepts(found))
+                })
+            }
+            _ => std::mem::discriminant(self) == std::mem::discriminant(found),
+        }
+    }
+    // the type of a variable holding a value of this type. elements and fields can be changed
+    // through any variable holding the container, so only the kind of container is known
+    fn widened(&self) -> Type {
+        match self {
+            Type::Array(_) => Type::Array(Type::Any.into()),
+            Type::Dictionary(_) => Type::Dictionary(None),
+            _ => self.clone(),
+        }
+    }
+    // the type of a value that is either of the two types
+    fn join(&self, other: &Type) -> Type {
+        match (self, other) {
+            (Type::Any, _) | (_, Type::Any) => Type::Any,
+            _ if self.accepts(other) && other.accepts(self) => self.clone(),
+            _ => Type::Any,
+        }
+    }
+}
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Any => write!(f, "any"),
+            Type::None => write!(f, "none"),
+            Type::Bool => write!(f, "bool"),
+            Type::Int => write!(f, "int"),
+            Type::Real => write!(f, "real"),
+            Type::String => write!(f, "string"),
+            Type::Char => write!(f, "char"),
+            Type::Error => write!(f, "error"),
+            Type::Array(element) => write!(f, "[{}]", element),
+            Type::Dictionary(None) => write!(f, "dictionary"),
+            Type::Dictionary(Some(fields)) => {
+                write!(f, "{{")?;
+                for (i, (name, ty)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", name, ty)?;
+                }
+                write!(f, "}}")
+            }
+            Type::Function(_) => write!(f, "function"),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Signature {
+    params: Vec<Param>,
+    returns: Type,
+}
+#[derive(Debug)]
+struct Param {
+    ty: Type,
+    annotation: Option<Range<usize>>,
+}
+
+// the type of an expression, and whether it comes from an annotation
+#[derive(Debug, Clone)]
+struct Typed {
+    ty: Type,
+    annotated: bool,
+}
+impl Typed {
+    const ANY: Typed = Typed {
+        ty: Type::Any,
+        annotated: false,
+    };
+    fn inferred(ty: Type) -> Self {
+        Typed {
+            ty,
+            annotated: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Variable {
+    typed: Typed,
+    annotation: Option<Range<usize>>,
+}
+
+// the names that are assigned to after their declaration. their type can change, so it is only
+// known if they are annotated
+#[derive(Default)]
+struct Reassigned {
+    names: HashSet<StringName>,
+}
+impl Visitor for Reassigned {
+    fn visit_assign(&mut self, assign: &Assign, range: &Range<usize>) {
+        if let Expression::Variable(name) = assign.left.data {
+            self.names.insert(name);
+        }
+        walk_assign(self, assign, range);
+    }
+}
+
+struct Checker {
+    scopes: Vec<HashMap<StringName, Variable>>,
+    reassigned: HashSet<StringName>,
+    // the annotated return type of the function being checked, with the range of the annotation
+    returns: Option<(Type, Range<usize>)>,
+    errors: Vec<Error>,
+}
+impl Checker {
+    fn scope(&mut self, check_fn: impl FnOnce(&mut Self)) {
+        self.scopes.push(HashMap::new());
+        check_fn(self);
+        self.scopes.pop();
+    }
+    fn declare(&mut self, name: StringName, typed: Typed, annotation: Option<&Range<usize>>) {
+        // the type of a variable that is assigned again is only known from its annotation, and
+        // so are the contents of a container that isn't annotated
+        let typed = match annotation {
+            Some(_) => typed,
+            None if self.reassigned.contains(&name) => Typed::ANY,
+            None if typed.annotated => typed,
+            None => Typed::inferred(typed.ty.widened()),
+        };
+        let variable = Variable {
+            typed,
+            annotation: annotation.cloned(),
+        };
+        self.scopes.last_mut().unwrap().insert(name, variable);
+    }
+    // names that aren't declared in the program, such as native functions, have any type
+    fn variable(&self, name: StringName) -> Option<&Variable> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(&name))
+    }
+    fn declare_pattern(
+        &mut self,
+        pattern: &VarNameType,
+        typed: Typed,
+        annotation: Option<&Range<usize>>,
+    ) {
+        match pattern {
+            VarNameType::Ident(Some(name)) => self.declare(*name, typed, annotation),
+            VarNameType::Ident(None) => {}
+            VarNameType::Array {
+                start_names,
+                end_names,
+            } => {
+                let element = Typed {
+                    ty: match typed.ty {
+                        Type::Array(element) => *element,
+                        _ => Type::Any,
+                    },
+                    annotated: typed.annotated,
+                };
+                for name in start_names.iter().chain(end_names) {
+                    if let Some(name) = name.data {
+                        self.declare(name, element.clone(), annotation);
+                    }
+                }
+            }
+            VarNameType::Object(names) => {
+                for (_, name) in names {
+                    self.declare(name.data, Typed::ANY, None);
+                }
+            }
+        }
+    }
+    fn annotation(&mut self, annotation: &ParseNode<TypeAnnotation>) -> Type {
+        match &annotation.data {
+            TypeAnnotation::Name(name) => Type::named(*name).unwrap_or_else(|| {
+                let range = annotation.range.clone();
+                let mut error = Error::new(range.clone(), ErrorType::UnknownType(*name));
+                if let Some(similar) = closest(name.as_str(), TYPE_NAMES) {
+                    let message = format!("did you mean '{}'?", similar);
+                    error = error.with_suggestion(range, similar, message);
+                }
+                self.errors.push(error);
+                Type::Any
+            }),
+            TypeAnnotation::Array(element) => Type::Array(self.annotation(element).into()),
+            TypeAnnotation::Dictionary(fields) => Type::Dictionary(Some(
+                fields
+                    .iter()
+                    .map(|(name, field)| (name.data, self.annotation(field)))
+                    .collect(),
+            )),
+        }
+    }
+    fn check(
+        &mut self,
+        expected: &Type,
+        found: &Typed,
+        range: &Range<usize>,
+        annotation: Option<&Range<usize>>,
+    ) {
+        if expected.accepts(&found.ty) {
+            return;
+        }
+        let message = format!("expected {}, found {}", expected, found.ty);
+        let mut error = Error::new(range.clone(), ErrorType::MismatchedTypes(message.into()));
+        if let Some(annotation) = annotation {
+            error = error.with_label(annotation.clone(), "expected because of this annotation");
+        }
+        self.errors.push(error);
+    }
+    // checks the expression against the expected type. the elements of an array literal are
+    // checked one by one, so a mismatch is reported at the element instead of for the array
+    fn checked(
+        &mut self,
+        expected: &Type,
+        expr: &Expression,
+        range: &Range<usize>,
+        annotation: Option<&Range<usize>>,
+    ) -> Typed {
+        match (expected, expr) {
+            (_, Expression::Grouping(inner)) => {
+                self.checked(expected, &inner.data, &inner.range, annotation)
+            }
+            (Type::Array(element), Expression::Array(items)) => {
+                let mut annotated = false;
+                for item in items {
+                    annotated |= self
+                        .checked(element, &item.data, &item.range, annotation)
+                        .annotated;
+                }
+                Typed {
+                    ty: expected.clone(),
+                    annotated,
+                }
+            }
+            _ => {
+                let found = self.expression(expr, range);
+                self.check(expected, &found, range, annotation);
+                found
+            }
+        }
+    }
+    fn signature(&mut self, decl: &FuncDecl) -> Signature {
+        let params = decl
+            .params
+            .iter()
+            .map(|param| {
+                let annotation = param.data.annotation.as_ref();
+                Param {
+                    ty: annotation.map_or(Type::Any, |a| self.annotation(a)),
+                    annotation: annotation.map(|a| a.range.clone()),
+                }
+            })
+            .collect();
+        let returns = match &decl.return_type {
+            Some(return_type) => self.annotation(return_type),
+            None => Type::Any,
+        };
+        Signature { params, returns }
+    }
+    fn declare_function(&mut self, decl: &FuncDecl) -> Rc<Signature> {
+        let signature = Rc::new(self.signature(decl));
+        let ty = Type::Function(Some(signature.clone()));
+        self.declare(decl.name.data, Typed::inferred(ty), None);
+        signature
+    }
+    // the parameters and the body share the same scope, like in the resolver
+    fn function(&mut self, decl: &FuncDecl, signature: &Signature) {
+        let returns = decl
+            .return_type
+            .as_ref()
+            .map(|annotation| (signature.returns.clone(), annotation.range.clone()));
+        let returns = std::mem::replace(&mut self.returns, returns);
+
+        self.scope(|c| {
+            for (param, expected) in decl.params.iter().zip(&signature.params) {
+                let annotation = expected.annotation.as_ref();
+                if let Some(value) = &param.data.value {
+                    c.checked(&expected.ty, &value.data, &value.range, annotation);
+                }
+                let typed = Typed {
+                    ty: expected.ty.clone(),
+                    annotated: annotation.is_some(),
+                };
+                c.declare_pattern(&param.data.pattern.data, typed, annotation);
+            }
+            match &decl.block.data {
+                FuncBlock::Block(block) => c.visit_statements(&block.statements),
+                FuncBlock::ReturnExpr(expr) => c.returned(Some(expr), &decl.block.range),
+            }
+        });
+        self.returns = returns;
+    }
+    // a `return` in the function being checked, `None` returns `none`
+    fn returned(&mut self, expr: Option<&Expression>, range: &Range<usize>) {
+        match (expr, self.returns.clone()) {
+            (Some(expr), Some((expected, annotation))) => {
+                self.checked(&expected, expr, range, Some(&annotation));
+            }
+            (Some(expr), None) => {
+                self.expression(expr, range);
+            }
+            (None, Some((expected, annotation))) => {
+                let found = Typed::inferred(Type::None);
+                self.check(&expected, &found, range, Some(&annotation));
+            }
+            (None, None) => {}
+        }
+    }
+
+    fn expression(&mut self, expr: &Expression, range: &Range<usize>) -> Typed {
+        match expr {
+            Expression::None => Typed::inferred(Type::None),
+            Expression::Bool(_) => Typed::inferred(Type::Bool),
+            Expression::Int(_) => Typed::inferred(Type::Int),
+            Expression::Real(_) => Typed::inferred(Type::Real),
+            Expression::String(_) => Typed::inferred(Type::String),
+            Expression::Char(_) => Typed::inferred(Type::Char),
+            Expression::Array(items) => {
+                let mut element: Option<Type> = None;
+                let mut annotated = false;
+                for item in items {
+                    let item = self.expression(&item.data, &item.range);
+                    annotated |= item.annotated;
+                    element = Some(match element {
+                        Some(element) => element.join(&item.ty),
+                        None => item.ty,
+                    });
+                }
+                Typed {
+                    ty: Type::Array(element.unwrap_or(Type::Any).into()),
+                    annotated,
+                }
+            }
+            Expression::Dictionary(items) => self.dictionary(items),
+            Expression::Variable(name) => self
+                .variable(*name)
+                .map_or(Typed::ANY, |variable| variable.typed.clone()),
+            Expression::Binary(binary) => {
+                let left = self.expression(&binary.left.data, &binary.left.range);
+                let right = self.expression(&binary.right.data, &binary.right.range);
+                match binary.operator {
+                    SymbolKeyword::Symbol(operator) => self.operator(
+                        operator,
+                        (&left, &binary.left.range),
+                        (&right, &binary.right.range),
+                        range,
+                    ),
+                    // `and` and `or` are one of their operands
+                    SymbolKeyword::Keyword(_) => Typed {
+                        ty: left.ty.join(&right.ty),
+                        annotated: left.annotated || right.annotated,
+                    },
+                }
+            }
+            Expression::Assign(assign) => self.assign(assign, range),
+            Expression::Unary(unary) => {
+                let operand = self.expression(&unary.operand.data, &unary.operand.range);
+                let Some(value) = operand.ty.sample() else {
+                    return Typed::ANY;
+                };
+                match operator::unary(unary.operator, value) {
+                    Ok(value) => Typed {
+                        ty: Type::of(&value),
+                        annotated: operand.annotated,
+                    },
+                    Err(error) => {
+                        if operand.annotated {
+                            let message = error.to_string().into();
+                            let error =
+                                Error::new(range.clone(), ErrorType::InvalidOperandTypes(message))
+                                    .with_label(
+                                        unary.operand.range.clone(),
+                                        format!("this is {}", operand.ty),
+                                    );
+                            self.errors.push(error);
+                        }
+                        Typed::ANY
+                    }
+                }
+            }
+            Expression::Suffix(suffix) => self.suffix(suffix),
+            Expression::Grouping(expr) => self.expression(&expr.data, &expr.range),
+        }
+    }
+    // the keys written as names or strings are known, unless a key is another expression
+    fn dictionary(&mut self, items: &Dictionary) -> Typed {
+        let mut fields = Some(vec![]);
+        let mut annotated = false;
+        for (key, value) in items {
+            let name = match &key.data {
+                DictionaryKey::Ident(name) => Some(*name),
+                DictionaryKey::Expr(ParseNode {
+                    data: Expression::String(s),
+                    ..
+                }) => Some(StringName::from(s.as_str())),
+                DictionaryKey::Expr(expr) => {
+                    self.expression(&expr.data, &expr.range);
+                    None
+                }
+            };
+            let value = self.expression(&value.data, &value.range);
+            annotated |= value.annotated;
+            match (name, &mut fields) {
+                (Some(name), Some(fields)) => {
+                    fields.retain(|(key, _)| *key != name);
+                    fields.push((name, value.ty));
+                }
+                _ => fields = None,
+            }
+        }
+        Typed {
+            ty: Type::Dictionary(fields),
+            annotated,
+        }
+    }
+    // the operator is applied by the interpreter to values of the operand types, the result
+    // is the type of the value it returns
+    fn operator(
+        &mut self,
+        operator: Symbol,
+        (left, left_range): (&Typed, &Range<usize>),
+        (right, right_range): (&Typed, &Range<usize>),
+        range: &Range<usize>,
+    ) -> Typed {
+        let annotated = left.annotated || right.annotated;
+        let comparison = matches!(
+            operator,
+            Symbol::Eq
+                | Symbol::NotEq
+                | Symbol::Less
+                | Symbol::Greater
+                | Symbol::LessEq
+                | Symbol::GreaterEq
+        );
+        let (Some(a), Some(b)) = (left.ty.sample(), right.ty.sample()) else {
+            let ty = if comparison { Type::Bool } else { Type::Any };
+            return Typed { ty, annotated };
+        };
+        let ty = match (operator::binary(operator, a, b), &left.ty, &right.ty) {
+            // an int to the power of a negative int is a real
+            (Ok(_), Type::Int, Type::Int) if operator == Symbol::Pow => Type::Any,
+            (Ok(_), Type::Array(a), Type::Array(b)) => Type::Array(a.join(b).into()),
+            (Ok(value), _, _) => Type::of(&value),
+            (Err(error), _, _) => {
+                if annotated {
+                    let message = error.to_string().into();
+                    let error = Error::new(range.clone(), ErrorType::InvalidOperandTypes(message))
+                        .with_label(left_range.clone(), format!("this is {}", left.ty))
+                        .with_label(right_range.clone(), format!("this is {}", right.ty));
+                    self.errors.push(error);
+                }
+                Type::Any
+            }
+        };
+        Typed { ty, annotated }
+    }
+    fn assign(&mut self, assign: &Assign, range: &Range<usize>) -> Typed {
+        let (left, right) = (&assign.left, &assign.right);
+        let target = self.expression(&left.data, &left.range);
+        if !target.annotated {
+            let value = self.expression(&right.data, &right.range);
+            return match assign.operator {
+                Some(operator) => self.operator(
+                    operator,
+                    (&target, &left.range),
+                    (&value, &right.range),
+                    range,
+                ),
+                None => value,
+            };
+        }
+        let annotation = match left.data {
+            Expression::Variable(name) => self
+                .variable(name)
+                .and_then(|variable| variable.annotation.clone()),
+            _ => None,
+        };
+        let annotation = annotation.as_ref();
+        match assign.operator {
+            Some(operator) => {
+                let value = self.expression(&right.data, &right.range);
+                let value = self.operator(
+                    operator,
+                    (&target, &left.range),
+                    (&value, &right.range),
+                    range,
+                );
+                self.check(&target.ty, &value, range, annotation);
+                value
+            }
+            None => self.checked(&target.ty, &right.data, &right.range, annotation),
+        }
+    }
+    fn suffix(&mut self, suffix: &Suffix) -> Typed {
+        let node = self.expression(&suffix.node.data, &suffix.node.range);
+        match &suffix.suffix {
+            SuffixType::Call(args) => {
+                // `value.name(args)` calls a method, which has no known signature
+                let method = matches!(
+                    &suffix.node.data,
+                    Expression::Suffix(Suffix {
+                        suffix: SuffixType::Property(_),
+                        ..
+                    })
+                );
+                let signature = match node.ty {
+                    Type::Function(Some(signature)) if !method => signature,
+                    _ => {
+                        for arg in args {
+                            self.expression(&arg.data, &arg.range);
+                        }
+                        return Typed::ANY;
+                    }
+                };
+                for (i, arg) in args.iter().enumerate() {
+                    match signature.params.get(i) {
+                        Some(param) => {
+                            let annotation = param.annotation.as_ref();
+                            self.checked(&param.ty, &arg.data, &arg.range, annotation);
+                        }
+                        None => {
+                            self.expression(&arg.data, &arg.range);
+                        }
+                    }
+                }
+                Typed {
+                    annotated: !matches!(signature.returns, Type::Any),
+                    ty: signature.returns.clone(),
+                }
+            }
+            SuffixType::Index(index) => {
+                let index = self.expression(&index.data, &index.range);
+                let ty = match (node.ty, index.ty) {
+                    (Type::Array(element), Type::Int | Type::Any) => *element,
+                    (Type::String, Type::Int | Type::Any) => Type::Char,
+                    _ => Type::Any,
+                };
+                Typed {
+                    ty,
+                    annotated: node.annotated,
+                }
+            }
+            SuffixType::Property(name) => {
+                let ty = match node.ty {
+                    Type::Dictionary(Some(fields)) => fields
+                        .into_iter()
+                        .find(|(key, _)| key == name)
+                        .map_or(Type::Any, |(_, ty)| ty),
+                    _ => Type::Any,
+                };
+                Typed {
+                    ty,
+                    annotated: node.annotated,
+                }
+            }
+        }
+    }
+}
+
+impl Visitor for Checker {
+    // function declarations are hoisted and their bodies checked at the end of the scope, like
+    // in the resolver
+    fn visit_statements(&mut self, statements: &[ParseNode<Statement>]) {
+        let mut functions = vec![];
+
+        for stmt in statements {
+            if let Statement::Declaration(Declaration::Func(decl)) = &stmt.data {
+                functions.push((decl, self.declare_function(decl)));
+            }
+        }
+        for stmt in statements {
+            if !matches!(stmt.data, Statement::Declaration(Declaration::Func(_))) {
+                self.visit_statement(&stmt.data, &stmt.range);
+            }
+        }
+        for (decl, signature) in functions {
+            self.function(decl, &signature);
+        }
+    }
+    fn visit_statement(&mut self, statement: &Statement, range: &Range<usize>) {
+        match statement {
+            Statement::Return(expr) => {
+                let range = expr.as_ref().map_or(range, |expr| &expr.range);
+                self.returned(expr.as_ref().map(|expr| &expr.data), range);
+            }
+            _ => walk_statement(self, statement, range),
+        }
+    }
+    fn visit_block(&mut self, block: &Block, range: &Range<usize>) {
+        self.scope(|c| walk_block(c, block, range));
+    }
+    fn visit_catch_block(&mut self, block: &CatchBlock, _range: &Range<usize>) {
+        self.scope(|c| {
+            if let Some(name) = block.name.data {
+                c.declare(name, Typed::inferred(Type::Error), None);
+            }
+            c.visit_statements(&block.block.data.statements);
+        });
+    }
+    fn visit_func_decl(&mut self, decl: &FuncDecl, _range: &Range<usize>) {
+        // declarations in statement lists are hoisted by `visit_statements` instead
+        let signature = self.declare_function(decl);
+        self.function(decl, &signature);
+    }
+    fn visit_var_decl(&mut self, decl: &VarDecl, _range: &Range<usize>) {
+        let typed = match (&decl.annotation, &decl.value) {
+            (Some(annotation), value) => {
+                let expected = self.annotation(annotation);
+                if let Some(value) = value {
+                    let annotation = Some(&annotation.range);
+                    self.checked(&expected, &value.data, &value.range, annotation);
+                }
+                Typed {
+                    ty: expected,
+                    annotated: true,
+                }
+            }
+            (None, Some(value)) => self.expression(&value.data, &value.range),
+            // `let x;` is assigned later
+            (None, None) => Typed::ANY,
+        };
+        let annotation = decl.annotation.as_ref().map(|a| &a.range);
+        self.declare_pattern(&decl.pattern.data, typed, annotation);
+    }
+    fn visit_expression(&mut self, expr: &Expression, range: &Range<usize>) {
+        self.expression(expr, range);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    fn codes(source: &str) -> Vec<&'static str> {
+        let statements = Parser::new(source).parse().unwrap();
+        type_check(&statements)
+            .iter()
+            .map(|error| error.error.code())
+            .collect()
+    }
+
+    #[test]
+    fn array_elements_are_any_without_annotation() {
+        let source = "let a = [1]; a.push(\"s\"); let s: string = a[1];";
+        assert_eq!(codes(source), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn dictionary_fields_are_any_without_annotation() {
+        let source = "let d = {n = 1}; d.n = \"x\"; let t: string = d.n;";
+        assert_eq!(codes(source), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn annotated_containers_keep_their_types() {
+        assert_eq!(codes("let a: [int] = [\"no\"];"), ["E0043"]);
+        assert_eq!(
+            codes("let a: [int] = [1]; let s: string = a[0];"),
+            ["E0043"]
+        );
+        assert_eq!(
+            codes("let d: {n: int} = {n = 1}; let t: string = d.n;"),
+            ["E0043"]
+        );
+    }
+
+    // the ranges of the mismatches, as the source they point at
+    fn mismatches(source: &str) -> Vec<&str> {
+        let statements = Parser::new(source).parse().unwrap();
+        type_check(&statements)
+            .iter()
+            .map(|error| {
+                assert_eq!(error.error.code(), "E0043");
+                &source[error.range.clone()]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn array_elements_are_checked_against_the_annotation() {
+        assert_eq!(mismatches("let x: [int] = [1, \"a\"];"), ["\"a\""]);
+        assert_eq!(mismatches("let x: [int] = [1, 2.5, 'c'];"), ["2.5", "'c'"]);
+        assert_eq!(mismatches("let x: [[int]] = [[1], [true]];"), ["true"]);
+        assert_eq!(mismatches("let x: [int] = ([1, none]);"), ["none"]);
+        assert_eq!(mismatches("let x: [int] = []; x = [\"a\"];"), ["\"a\""]);
+        assert_eq!(
+            mismatches("func f(a: [int]) -> a\nf([1, \"a\"]);"),
+            ["\"a\""]
+        );
+        assert_eq!(mismatches("func f(): [int] -> [\"a\"]"), ["\"a\""]);
+        assert_eq!(
+            mismatches("func f(): [int] { return [1, \"a\"]; }"),
+            ["\"a\""]
+        );
+        // an array that isn't a literal is checked as a whole
+        assert_eq!(
+            mismatches("let s: [string] = [\"a\"]; let x: [int] = s;"),
+            ["s"]
+        );
+
+        assert_eq!(mismatches("let x: [int] = [1, 2, 3];"), Vec::<&str>::new());
+        assert_eq!(mismatches("let x: array = [1, \"a\"];"), Vec::<&str>::new());
+        assert_eq!(mismatches("let x: [any] = [1, \"a\"];"), Vec::<&str>::new());
+        // elements without a known type can be anything
+        assert_eq!(
+            mismatches("func f(n) -> [n, f(n)]\nlet x: [int] = f(1);"),
+            Vec::<&str>::new()
+        );
+    }
+
+    #[test]
+    fn mismatches_need_an_annotation() {
+        assert_eq!(codes("let x = 1; let y = x + \"a\";"), Vec::<&str>::new());
+        assert_eq!(codes("let x: int = 1; let y = x + \"a\";"), ["E0044"]);
+        assert_eq!(codes("let x: int = \"a\";"), ["E0043"]);
+    }
+}
@@ -0,0 +1,183 @@
+// runs the `lox-rs` binary and checks its output and exit codes
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+fn lox(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_lox-rs"))
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .unwrap()
+}
+fn lox_stdin(args: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_lox-rs"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+// writes the source to a file in the temp directory, the name is unique to the test
+fn temp_file(name: &str, source: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("lox-rs-{}-{}.lx", std::process::id(), name));
+    std::fs::write(&path, source).unwrap();
+    path
+}
+fn code(output: &Output) -> i32 {
+    output.status.code().unwrap()
+}
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn runs_programs() {
+    let output = lox(&["-e", "println(1 + 2);"]);
+    assert_eq!(code(&output), 0, "{}", stderr(&output));
+    assert_eq!(stdout(&output), "3\n");
+
+    let path = temp_file("run", "println(args);");
+    let output = lox(&["run", path.to_str().unwrap(), "a", "b"]);
+    assert_eq!(code(&output), 0, "{}", stderr(&output));
+    assert_eq!(stdout(&output), "[\"a\", \"b\"]\n");
+
+    let output = lox_stdin(&["-", "--", "c"], "println(args);");
+    assert_eq!(code(&output), 0, "{}", stderr(&output));
+    assert_eq!(stdout(&output), "[\"c\"]\n");
+}
+
+#[test]
+fn fmt_check_reports_unformatted_files() {
+    let unformatted = temp_file("fmt-unformatted", "let   x=1;\n");
+    let formatted = temp_file("fmt-formatted", "let x = 1;\n");
+
+    let output = lox(&["fmt", "--check", unformatted.to_str().unwrap()]);
+    assert_eq!(code(&output), 1);
+    assert!(stdout(&output).contains(unformatted.to_str().unwrap()));
+    // the file is left as it is
+    assert_eq!(
+        std::fs::read_to_string(&unformatted).unwrap(),
+        "let   x=1;\n"
+    );
+
+    let output = lox(&["fmt", "--check", formatted.to_str().unwrap()]);
+    assert_eq!(code(&output), 0, "{}", stdout(&output));
+
+    let output = lox_stdin(&["fmt"], "let   x=1;");
+    assert_eq!(code(&output), 0, "{}", stderr(&output));
+    assert_eq!(stdout(&output), "let x = 1;\n");
+}
+
+#[test]
+fn usage_errors() {
+    let output = lox(&["--bogus"]);
+    assert_eq!(code(&output), 64);
+    assert!(stderr(&output).contains("lox-rs --help"));
+
+    assert_eq!(code(&lox(&["check", "-e", "1;"])), 64);
+    assert_eq!(code(&lox(&["--error-format=xml", "-e", "1;"])), 64);
+    assert_eq!(code(&lox(&["explain", "E9999"])), 64);
+    assert_eq!(code(&lox(&["explain", "E0007"])), 0);
+    assert_eq!(code(&lox(&["explain", "r0006"])), 0);
+}
+
+#[test]
+fn compile_errors() {
+    let output = lox(&["-e", "let x = ;"]);
+    assert_eq!(code(&output), 65);
+    assert!(stderr(&output).contains("Error at line: 1"));
+
+    // not supported, but still a compile error
+    let output = lox(&["-e", "let {a} = x;"]);
+    assert_eq!(code(&output), 65);
+    assert!(stderr(&output).contains("[E0045] Cannot unpack a dictionary"));
+
+    let path = temp_file("check", "let x = y;\n");
+    let output = lox(&["check", path.to_str().unwrap()]);
+    assert_eq!(code(&output), 65);
+    assert!(stderr(&output).contains("[E0032] Undefined variable 'y'"));
+
+    // one JSON object per diagnostic
+    let output = lox(&["check", "--error-format=json", path.to_str().unwrap()]);
+    assert_eq!(code(&output), 65);
+    let stderr = stderr(&output);
+    let diagnostic: serde_json::Value = serde_json::from_str(stderr.trim()).unwrap();
+    assert_eq!(diagnostic["code"], "E0032");
+    assert_eq!(diagnostic["file"], path.to_str().unwrap());
+}
+
+#[test]
+fn lint_levels() {
+    let path = temp_file("lint", "{ let a = 1; }\n");
+    let path = path.to_str().unwrap();
+
+    let output = lox(&["check", path]);
+    assert_eq!(code(&output), 0);
+    assert!(stderr(&output).contains("Warning at line: 1"));
+    assert!(stderr(&output).contains("[W0001] Unused variable 'a'"));
+
+    let output = lox(&["check", "--allow", "unused_variables", path]);
+    assert_eq!(code(&output), 0);
+    assert_eq!(stderr(&output), "");
+
+    let output = lox(&["check", "--deny", "unused_variables", path]);
+    assert_eq!(code(&output), 65);
+    assert!(stderr(&output).contains("Error at line: 1"));
+
+    // the last flag wins
+    let args = [
+        "check",
+        "--deny",
+        "unused_variables",
+        "--warn",
+        "unused_variables",
+        path,
+    ];
+    assert_eq!(code(&lox(&args)), 0);
+
+    // constant errors are denied unless the flag says otherwise
+    let path = temp_file("lint-constant", "println(1 / 0);\n");
+    let path = path.to_str().unwrap();
+    assert_eq!(code(&lox(&["check", path])), 65);
+    assert_eq!(code(&lox(&["check", "--warn", "constant_errors", path])), 0);
+
+    let output = lox(&["check", "--deny", "bogus", path]);
+    assert_eq!(code(&output), 64);
+    assert!(stderr(&output).contains("Unknown lint 'bogus'"));
+    assert_eq!(code(&lox(&["check", path, "--deny"])), 64);
+}
+
+#[test]
+fn runtime_errors() {
+    let output = lox(&["-e", "let a = [1];\na[3];"]);
+    assert_eq!(code(&output), 70);
+    let stderr = stderr(&output);
+    assert!(stderr.contains("[R0015] Index 3 is out of range for length 1"));
+    assert!(stderr.contains("at <top level> (<inline>:2:1)"));
+
+    assert_eq!(code(&lox(&["-e", "throw \"oops\";"])), 70);
+}
+
+#[test]
+fn io_errors() {
+    let path = std::env::temp_dir().join("lox-rs-missing-file.lx");
+    let output = lox(&[path.to_str().unwrap()]);
+    assert_eq!(code(&output), 74);
+    assert!(stderr(&output).contains("Failed to read"));
+
+    let output = lox(&["check", path.to_str().unwrap()]);
+    assert_eq!(code(&output), 74);
+}